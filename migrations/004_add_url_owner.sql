@@ -0,0 +1,13 @@
+-- Track which user created each URL and allow superusers to see everything
+
+-- Owner of the URL (NULL for anonymously created links)
+ALTER TABLE urls
+ADD COLUMN IF NOT EXISTS owner_id BIGINT REFERENCES users(id) ON DELETE SET NULL;
+
+-- Index for owner-scoped listing, stats and deletes
+CREATE INDEX IF NOT EXISTS idx_urls_owner_created
+ON urls(owner_id, created_at DESC);
+
+-- Superusers bypass owner scoping on admin endpoints
+ALTER TABLE users
+ADD COLUMN IF NOT EXISTS is_superuser BOOLEAN NOT NULL DEFAULT FALSE;
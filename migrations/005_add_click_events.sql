@@ -0,0 +1,14 @@
+-- Per-click event log for traffic analytics
+CREATE TABLE IF NOT EXISTS click_events (
+    id BIGSERIAL PRIMARY KEY,
+    url_id BIGINT NOT NULL REFERENCES urls(id) ON DELETE CASCADE,
+    clicked_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    referrer TEXT,
+    user_agent TEXT,
+    -- Salted SHA-256 of the client IP; raw addresses are never stored
+    ip_hash CHAR(64)
+);
+
+-- Index for per-URL time range queries
+CREATE INDEX IF NOT EXISTS idx_click_events_url_clicked
+ON click_events(url_id, clicked_at);
@@ -0,0 +1,3 @@
+-- Country of origin for click events (ISO 3166-1 alpha-2, taken from a trusted proxy header)
+ALTER TABLE click_events
+ADD COLUMN IF NOT EXISTS country CHAR(2);
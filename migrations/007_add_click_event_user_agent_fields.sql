@@ -0,0 +1,6 @@
+-- Classified user-agent fields for click analytics
+ALTER TABLE click_events
+ADD COLUMN IF NOT EXISTS browser_family VARCHAR(64),
+ADD COLUMN IF NOT EXISTS browser_version VARCHAR(16),
+ADD COLUMN IF NOT EXISTS os_family VARCHAR(64),
+ADD COLUMN IF NOT EXISTS device_class VARCHAR(16);
@@ -0,0 +1,9 @@
+-- Separate bot/crawler hits from human clicks
+
+-- Bot hits are excluded from click_count and tracked here instead
+ALTER TABLE urls
+ADD COLUMN IF NOT EXISTS bot_click_count BIGINT NOT NULL DEFAULT 0;
+
+-- Flag bot hits in the event log so analytics can exclude them
+ALTER TABLE click_events
+ADD COLUMN IF NOT EXISTS is_bot BOOLEAN NOT NULL DEFAULT FALSE;
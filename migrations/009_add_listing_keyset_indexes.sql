@@ -0,0 +1,14 @@
+-- Indexes for keyset pagination of /_list
+-- Each sort column is paired with id, the tie-breaker used by the cursor
+
+CREATE INDEX IF NOT EXISTS idx_urls_created_id
+ON urls(created_at, id);
+
+CREATE INDEX IF NOT EXISTS idx_urls_owner_created_id
+ON urls(owner_id, created_at, id);
+
+CREATE INDEX IF NOT EXISTS idx_urls_clicks_id
+ON urls(click_count, id);
+
+CREATE INDEX IF NOT EXISTS idx_urls_last_clicked_id
+ON urls(last_clicked_at, id);
@@ -0,0 +1,4 @@
+-- Sequence behind SHORT_CODE_STRATEGY=sequence
+-- Each value is encoded into exactly one short code, so codes never collide
+
+CREATE SEQUENCE IF NOT EXISTS short_code_seq START WITH 1;
@@ -0,0 +1,6 @@
+-- Pre-generated, unused short codes for SHORT_CODE_STRATEGY=pool
+-- Creates claim a row with DELETE ... RETURNING; a background task refills the table
+
+CREATE TABLE IF NOT EXISTS short_code_pool (
+    code VARCHAR(16) PRIMARY KEY
+);
@@ -0,0 +1,7 @@
+-- Codes reserved with `rustlink admin reserve-code`, stored lowercase
+-- Route names and RESERVED_CODES are reserved in process and not stored here
+
+CREATE TABLE IF NOT EXISTS reserved_codes (
+    code VARCHAR(16) PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
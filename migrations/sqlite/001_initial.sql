@@ -0,0 +1,50 @@
+-- Schema for the embedded SQLite backend, equivalent to the PostgreSQL
+-- migrations in the parent directory. Timestamps are RFC 3339 text in UTC.
+
+CREATE TABLE IF NOT EXISTS users (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    username TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now')),
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    -- Superusers bypass owner scoping on admin endpoints
+    is_superuser BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE IF NOT EXISTS urls (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    short_code TEXT NOT NULL UNIQUE,
+    original_url TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT,
+    click_count INTEGER NOT NULL DEFAULT 0,
+    last_clicked_at TEXT,
+    -- Bot hits are excluded from click_count and tracked here instead
+    bot_click_count INTEGER NOT NULL DEFAULT 0,
+    -- Owner of the URL (NULL for anonymously created links)
+    owner_id INTEGER REFERENCES users(id) ON DELETE SET NULL
+);
+
+-- Listing, expiry cleanup and owner-scoped queries
+CREATE INDEX IF NOT EXISTS idx_urls_created ON urls(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_urls_expires_at ON urls(expires_at) WHERE expires_at IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_urls_owner_created ON urls(owner_id, created_at DESC);
+
+-- Per-click event log for traffic analytics
+CREATE TABLE IF NOT EXISTS click_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    url_id INTEGER NOT NULL REFERENCES urls(id) ON DELETE CASCADE,
+    clicked_at TEXT NOT NULL,
+    referrer TEXT,
+    user_agent TEXT,
+    -- Salted SHA-256 of the client IP; raw addresses are never stored
+    ip_hash TEXT,
+    country TEXT,
+    browser_family TEXT,
+    browser_version TEXT,
+    os_family TEXT,
+    device_class TEXT,
+    is_bot BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX IF NOT EXISTS idx_click_events_url_clicked ON click_events(url_id, clicked_at);
@@ -0,0 +1,8 @@
+-- Single-row counter standing in for the PostgreSQL short_code_seq sequence
+
+CREATE TABLE IF NOT EXISTS short_code_sequence (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    value INTEGER NOT NULL
+);
+
+INSERT OR IGNORE INTO short_code_sequence (id, value) VALUES (1, 0);
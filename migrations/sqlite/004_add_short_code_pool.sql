@@ -0,0 +1,5 @@
+-- Pre-generated, unused short codes for SHORT_CODE_STRATEGY=pool
+
+CREATE TABLE IF NOT EXISTS short_code_pool (
+    code TEXT PRIMARY KEY
+);
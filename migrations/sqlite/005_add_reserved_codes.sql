@@ -0,0 +1,6 @@
+-- Codes reserved with `rustlink admin reserve-code`, stored lowercase
+
+CREATE TABLE IF NOT EXISTS reserved_codes (
+    code TEXT PRIMARY KEY,
+    created_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ', 'now'))
+);
@@ -2,14 +2,15 @@
 //!
 //! This module contains CLI command handlers for administrative tasks
 //! such as cleaning expired URLs, running migrations, viewing statistics,
-//! and pinging the cache server.
+//! managing reserved short codes, and pinging the cache server.
 
-use crate::cache::Cache;
-use crate::config::Config;
-use crate::db::Repository;
+use crate::cache;
+use crate::config::{Config, ShortCodeStrategy};
+use crate::db;
 use crate::error::AppResult;
+use crate::services::ReservedCodes;
 use clap::Subcommand;
-use tracing::info;
+use tracing::{info, warn};
 
 /// Administrative commands available via CLI.
 #[derive(Subcommand, Debug)]
@@ -25,6 +26,37 @@ pub enum AdminCommands {
 
     /// Ping the cache server
     PingCache,
+
+    /// Reserve a short code so no link can be created with it
+    ReserveCode {
+        /// Code to reserve (letters, digits, `_` and `-`, at most 16 characters)
+        #[arg(value_parser = parse_code)]
+        code: String,
+    },
+
+    /// Release a code reserved with `reserve-code`
+    ReleaseCode {
+        /// Code to release
+        #[arg(value_parser = parse_code)]
+        code: String,
+    },
+
+    /// List reserved short codes
+    ListReserved,
+}
+
+/// Validate a code argument and normalize it to lowercase
+fn parse_code(code: &str) -> Result<String, String> {
+    let valid = !code.is_empty()
+        && code.len() <= 16
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if valid {
+        Ok(code.to_ascii_lowercase())
+    } else {
+        Err("codes are 1-16 letters, digits, underscores or hyphens".to_string())
+    }
 }
 
 /// Run an administrative command with the given configuration.
@@ -51,6 +83,15 @@ pub async fn run(config: Config, admin_command: AdminCommands) -> AppResult<()>
         AdminCommands::PingCache => {
             ping_cache(config).await
         }
+        AdminCommands::ReserveCode { code } => {
+            reserve_code(config, &code).await
+        }
+        AdminCommands::ReleaseCode { code } => {
+            release_code(config, &code).await
+        }
+        AdminCommands::ListReserved => {
+            list_reserved(config).await
+        }
     }
 }
 
@@ -58,13 +99,7 @@ pub async fn run(config: Config, admin_command: AdminCommands) -> AppResult<()>
 async fn clean_expired(config: Config) -> AppResult<()> {
     info!("Cleaning expired URLs...");
 
-    let repository = Repository::new(
-        &config.database.url,
-        config.database.max_connections,
-        config.database.min_connections,
-        config.database.acquire_timeout_seconds,
-    )
-    .await?;
+    let repository = db::connect(&config.database).await?;
 
     let deleted_count = repository.delete_expired_urls().await?;
 
@@ -76,13 +111,7 @@ async fn clean_expired(config: Config) -> AppResult<()> {
 async fn migrate(config: Config) -> AppResult<()> {
     info!("Running database migrations...");
 
-    let repository = Repository::new(
-        &config.database.url,
-        config.database.max_connections,
-        config.database.min_connections,
-        config.database.acquire_timeout_seconds,
-    )
-    .await?;
+    let repository = db::connect(&config.database).await?;
 
     repository.run_migrations().await?;
 
@@ -94,21 +123,22 @@ async fn migrate(config: Config) -> AppResult<()> {
 async fn stats(config: Config) -> AppResult<()> {
     info!("Fetching statistics...");
 
-    let repository = Repository::new(
-        &config.database.url,
-        config.database.max_connections,
-        config.database.min_connections,
-        config.database.acquire_timeout_seconds,
-    )
-    .await?;
+    let repository = db::connect(&config.database).await?;
 
     let stats = repository.get_stats().await?;
 
     println!("\n=== rustLink Statistics ===");
     println!("Total URLs:      {}", stats.total_urls);
     println!("Total Clicks:    {}", stats.total_clicks);
+    println!("Bot Clicks:      {}", stats.bot_clicks);
     println!("Active URLs:     {}", stats.active_urls);
     println!("Expired URLs:    {}", stats.expired_urls);
+    if config.url.short_code_strategy == ShortCodeStrategy::Pool {
+        println!(
+            "Code Pool:       {}",
+            repository.count_pooled_codes().await?
+        );
+    }
     println!();
 
     Ok(())
@@ -118,12 +148,7 @@ async fn stats(config: Config) -> AppResult<()> {
 async fn ping_cache(config: Config) -> AppResult<()> {
     info!("Pinging cache server...");
 
-    let cache = Cache::new(
-        &config.cache.url,
-        config.cache.max_connections,
-        config.cache.default_ttl_seconds,
-    )
-    .await?;
+    let cache = cache::from_config(&config.cache).await?;
 
     let response = cache.ping().await?;
 
@@ -131,3 +156,53 @@ async fn ping_cache(config: Config) -> AppResult<()> {
 
     Ok(())
 }
+
+/// Reserve a short code in the database.
+async fn reserve_code(config: Config, code: &str) -> AppResult<()> {
+    let repository = db::connect(&config.database).await?;
+
+    if repository.reserve_code(code).await? {
+        info!("Reserved '{}'", code);
+    } else {
+        info!("'{}' was already reserved", code);
+    }
+
+    // Reserving doesn't take over an existing link
+    if repository.short_code_exists(code).await? {
+        warn!(
+            "A link already uses '{}'; it keeps working until it is deleted",
+            code
+        );
+    }
+
+    Ok(())
+}
+
+/// Release a short code reserved in the database.
+async fn release_code(config: Config, code: &str) -> AppResult<()> {
+    let repository = db::connect(&config.database).await?;
+
+    if repository.release_reserved_code(code).await? {
+        info!("Released '{}'", code);
+    } else {
+        warn!("'{}' was not reserved with reserve-code", code);
+    }
+
+    Ok(())
+}
+
+/// List reserved short codes.
+async fn list_reserved(config: Config) -> AppResult<()> {
+    let repository = db::connect(&config.database).await?;
+
+    let stored = repository.list_reserved_codes().await?;
+    let configured = ReservedCodes::new(&config.url.reserved_codes).to_vec();
+
+    println!("\n=== Reserved Short Codes ===");
+    println!("Reserved with reserve-code: {}", stored.join(", "));
+    println!("From RESERVED_CODES:        {}", configured.join(", "));
+    println!("Route names (login, _stats, ...) are reserved by the server itself.");
+    println!();
+
+    Ok(())
+}
@@ -0,0 +1,271 @@
+//! Click analytics helpers.
+//!
+//! This module contains the per-click event model recorded by the background
+//! worker, the salted hashing used to avoid storing raw client IPs, and the
+//! bucketing/grouping options used by the analytics queries.
+
+use chrono::{DateTime, Datelike, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::user_agent::{classify, DeviceClass};
+
+/// A single recorded click on a short URL
+#[derive(Debug, Clone)]
+pub struct ClickEvent {
+    /// Database ID of the clicked URL
+    pub url_id: i64,
+    /// When the click happened
+    pub clicked_at: DateTime<Utc>,
+    /// Value of the `Referer` header, if sent
+    pub referrer: Option<String>,
+    /// Value of the `User-Agent` header, if sent
+    pub user_agent: Option<String>,
+    /// Salted SHA-256 of the client IP (hex encoded)
+    pub ip_hash: Option<String>,
+    /// ISO 3166-1 alpha-2 country code, if a proxy supplied one
+    pub country: Option<String>,
+    /// Browser family parsed from the user agent
+    pub browser_family: Option<String>,
+    /// Major browser version parsed from the user agent
+    pub browser_version: Option<String>,
+    /// OS family parsed from the user agent
+    pub os_family: Option<String>,
+    /// Device class parsed from the user agent
+    pub device_class: Option<DeviceClass>,
+    /// Whether the hit came from a bot, crawler or link previewer
+    pub is_bot: bool,
+}
+
+impl ClickEvent {
+    /// Create a click event, classifying the user agent if one was sent
+    pub fn new(
+        url_id: i64,
+        referrer: Option<String>,
+        user_agent: Option<String>,
+        ip_hash: Option<String>,
+        country: Option<String>,
+        is_bot: bool,
+    ) -> Self {
+        let info = user_agent.as_deref().map(classify);
+
+        Self {
+            url_id,
+            clicked_at: Utc::now(),
+            referrer,
+            user_agent,
+            ip_hash,
+            country,
+            browser_family: info.as_ref().map(|i| i.browser_family.clone()),
+            browser_version: info.as_ref().and_then(|i| i.browser_version.clone()),
+            os_family: info.as_ref().map(|i| i.os_family.clone()),
+            device_class: info.map(|i| i.device_class),
+            is_bot,
+        }
+    }
+}
+
+/// Time bucket size for click time series
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsInterval {
+    Hour,
+    #[default]
+    Day,
+    Week,
+}
+
+impl StatsInterval {
+    /// Postgres `date_trunc` field name for this interval
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatsInterval::Hour => "hour",
+            StatsInterval::Day => "day",
+            StatsInterval::Week => "week",
+        }
+    }
+
+    /// Length of a single bucket
+    pub fn duration(&self) -> chrono::Duration {
+        match self {
+            StatsInterval::Hour => chrono::Duration::hours(1),
+            StatsInterval::Day => chrono::Duration::days(1),
+            StatsInterval::Week => chrono::Duration::weeks(1),
+        }
+    }
+
+    /// Start of the bucket containing `t`, like Postgres `date_trunc` in UTC
+    ///
+    /// Weeks start on Monday.
+    pub fn truncate(&self, t: DateTime<Utc>) -> DateTime<Utc> {
+        let day_start = t.duration_trunc(chrono::Duration::days(1)).unwrap_or(t);
+        match self {
+            StatsInterval::Hour => t.duration_trunc(chrono::Duration::hours(1)).unwrap_or(t),
+            StatsInterval::Day => day_start,
+            StatsInterval::Week => {
+                day_start - chrono::Duration::days(t.weekday().num_days_from_monday() as i64)
+            }
+        }
+    }
+}
+
+/// Click event column that analytics can be grouped by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClickDimension {
+    Referrer,
+    Country,
+    Browser,
+    Os,
+    Device,
+}
+
+impl ClickDimension {
+    /// Column name in `click_events` (whitelisted, safe to interpolate into SQL)
+    pub fn column(&self) -> &'static str {
+        match self {
+            ClickDimension::Referrer => "referrer",
+            ClickDimension::Country => "country",
+            ClickDimension::Browser => "browser_family",
+            ClickDimension::Os => "os_family",
+            ClickDimension::Device => "device_class",
+        }
+    }
+}
+
+/// Normalize a country header value into an uppercase ISO alpha-2 code.
+///
+/// Cloudflare uses `XX` for unknown and `T1` for Tor, neither of which is a country.
+pub fn normalize_country(value: &str) -> Option<String> {
+    let code = value.trim().to_ascii_uppercase();
+
+    if code.len() != 2 || !code.chars().all(|c| c.is_ascii_uppercase()) || code == "XX" {
+        return None;
+    }
+
+    Some(code)
+}
+
+/// Hash a client IP with the configured salt.
+///
+/// Returns `None` when the IP could not be determined, so unknown clients
+/// don't all collapse into the same hash.
+pub fn hash_ip(salt: &str, client_ip: &str) -> Option<String> {
+    if client_ip.is_empty() || client_ip == "unknown" {
+        return None;
+    }
+
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(b":");
+    hasher.update(client_ip.as_bytes());
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+/// Hash a visitor identity (client IP plus user agent) for unique-visitor counting.
+///
+/// Returns `None` when the IP is unknown, since such visitors can't be told apart.
+pub fn hash_visitor(salt: &str, client_ip: &str, user_agent: Option<&str>) -> Option<String> {
+    let ip_hash = hash_ip(salt, client_ip)?;
+
+    let mut hasher = Sha256::new();
+    hasher.update(ip_hash.as_bytes());
+    hasher.update(b":");
+    hasher.update(user_agent.unwrap_or_default().as_bytes());
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_ip_is_deterministic() {
+        let first = hash_ip("salt", "192.168.1.1").unwrap();
+        let second = hash_ip("salt", "192.168.1.1").unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(first.len(), 64);
+        assert!(!first.contains("192.168.1.1"));
+    }
+
+    #[test]
+    fn test_hash_ip_depends_on_salt() {
+        assert_ne!(hash_ip("salt-a", "10.0.0.1"), hash_ip("salt-b", "10.0.0.1"));
+    }
+
+    #[test]
+    fn test_normalize_country() {
+        assert_eq!(normalize_country("us"), Some("US".to_string()));
+        assert_eq!(normalize_country(" DE "), Some("DE".to_string()));
+        assert_eq!(normalize_country("XX"), None);
+        assert_eq!(normalize_country("T1"), None);
+        assert_eq!(normalize_country("USA"), None);
+    }
+
+    #[test]
+    fn test_stats_interval_parsing() {
+        let interval: StatsInterval = serde_json::from_str("\"week\"").unwrap();
+        assert_eq!(interval, StatsInterval::Week);
+        assert_eq!(interval.as_str(), "week");
+        assert_eq!(StatsInterval::default(), StatsInterval::Day);
+    }
+
+    #[test]
+    fn test_stats_interval_truncate() {
+        // 2026-01-15 is a Thursday
+        let t = DateTime::parse_from_rfc3339("2026-01-15T10:42:17.5Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+
+        assert_eq!(StatsInterval::Hour.truncate(t), at("2026-01-15T10:00:00Z"));
+        assert_eq!(StatsInterval::Day.truncate(t), at("2026-01-15T00:00:00Z"));
+        assert_eq!(StatsInterval::Week.truncate(t), at("2026-01-12T00:00:00Z"));
+    }
+
+    #[test]
+    fn test_click_event_classifies_user_agent() {
+        let event = ClickEvent::new(
+            1,
+            None,
+            Some(
+                "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:121.0) Gecko/20100101 Firefox/121.0"
+                    .to_string(),
+            ),
+            None,
+            None,
+            false,
+        );
+
+        assert_eq!(event.browser_family.as_deref(), Some("Firefox"));
+        assert_eq!(event.browser_version.as_deref(), Some("121"));
+        assert_eq!(event.os_family.as_deref(), Some("Windows"));
+        assert_eq!(event.device_class, Some(DeviceClass::Desktop));
+    }
+
+    #[test]
+    fn test_click_event_without_user_agent() {
+        let event = ClickEvent::new(1, None, None, None, None, true);
+
+        assert!(event.browser_family.is_none());
+        assert!(event.device_class.is_none());
+        assert!(event.is_bot);
+    }
+
+    #[test]
+    fn test_hash_visitor_includes_user_agent() {
+        let chrome = hash_visitor("salt", "10.0.0.1", Some("Chrome"));
+        let firefox = hash_visitor("salt", "10.0.0.1", Some("Firefox"));
+
+        assert!(chrome.is_some());
+        assert_ne!(chrome, firefox);
+        assert_eq!(chrome, hash_visitor("salt", "10.0.0.1", Some("Chrome")));
+        assert!(hash_visitor("salt", "unknown", Some("Chrome")).is_none());
+    }
+
+    #[test]
+    fn test_hash_ip_unknown() {
+        assert!(hash_ip("salt", "unknown").is_none());
+        assert!(hash_ip("salt", "").is_none());
+    }
+}
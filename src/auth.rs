@@ -12,6 +12,17 @@ pub struct Claims {
     pub username: String,
     pub exp: i64, // Expiration time as Unix timestamp
     pub iat: i64, // Issued at time as Unix timestamp
+    #[serde(default)]
+    pub is_superuser: bool, // Superusers see and manage every user's URLs
+}
+
+impl Claims {
+    /// Parse the numeric user ID from the subject claim
+    pub fn user_id(&self) -> AppResult<i64> {
+        self.sub
+            .parse()
+            .map_err(|_| AppError::Unauthorized("Invalid subject in token".to_string()))
+    }
 }
 
 /// Login request
@@ -45,7 +56,12 @@ impl AuthService {
     }
 
     /// Generate a JWT token for a user
-    pub fn generate_token(&self, user_id: &str, username: &str) -> AppResult<String> {
+    pub fn generate_token(
+        &self,
+        user_id: &str,
+        username: &str,
+        is_superuser: bool,
+    ) -> AppResult<String> {
         let now = Utc::now();
         let exp = now + Duration::hours(self.expiration_hours);
 
@@ -54,6 +70,7 @@ impl AuthService {
             username: username.to_string(),
             exp: exp.timestamp(),
             iat: now.timestamp(),
+            is_superuser,
         };
 
         encode(
@@ -72,7 +89,7 @@ impl AuthService {
             &Validation::new(Algorithm::HS256),
         )
         .map(|data| data.claims)
-        .map_err(|e| AppError::Internal(format!("Token validation failed: {}", e)))
+        .map_err(|e| AppError::Unauthorized(format!("Token validation failed: {}", e)))
     }
 }
 
@@ -89,7 +106,7 @@ mod tests {
         let username = "testuser";
 
         let token = auth_service
-            .generate_token(user_id, username)
+            .generate_token(user_id, username, false)
             .expect("Failed to generate token");
 
         let claims = auth_service
@@ -98,6 +115,22 @@ mod tests {
 
         assert_eq!(claims.sub, user_id);
         assert_eq!(claims.username, username);
+        assert_eq!(claims.user_id().unwrap(), 123);
+        assert!(!claims.is_superuser);
+    }
+
+    #[test]
+    fn test_superuser_claim_round_trip() {
+        let auth_service = AuthService::new("test_secret_key".to_string(), 24);
+
+        let token = auth_service
+            .generate_token("1", "admin", true)
+            .expect("Failed to generate token");
+        let claims = auth_service
+            .validate_token(&token)
+            .expect("Failed to validate token");
+
+        assert!(claims.is_superuser);
     }
 
     #[test]
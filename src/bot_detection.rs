@@ -0,0 +1,138 @@
+//! Bot and crawler detection for click counting.
+//!
+//! Link unfurlers, uptime monitors and search crawlers still get redirected,
+//! but their hits are recorded as bot clicks so they don't inflate
+//! `click_count`. A request is treated as a bot when any of these hold:
+//! - it is a `HEAD` request (used by monitors and link checkers)
+//! - it carries a prefetch/preview purpose header (browser and chat previews)
+//! - its user agent is missing or classifies as a bot
+//! - its user agent contains one of the configured extra patterns
+
+use crate::user_agent::{classify, DeviceClass};
+use axum::http::{HeaderMap, Method};
+
+/// Headers used by browsers and preview fetchers to mark speculative requests
+const PREVIEW_HEADERS: &[(&str, &str)] = &[
+    ("purpose", "prefetch"),
+    ("sec-purpose", "prefetch"),
+    ("x-purpose", "preview"),
+    ("x-moz", "prefetch"),
+];
+
+/// Configurable bot detector
+#[derive(Debug, Clone)]
+pub struct BotDetector {
+    enabled: bool,
+    extra_patterns: Vec<String>,
+}
+
+impl BotDetector {
+    /// Create a detector with additional lowercase user-agent substrings to flag
+    pub fn new(enabled: bool, extra_patterns: Vec<String>) -> Self {
+        Self {
+            enabled,
+            extra_patterns: extra_patterns
+                .into_iter()
+                .map(|p| p.trim().to_ascii_lowercase())
+                .filter(|p| !p.is_empty())
+                .collect(),
+        }
+    }
+
+    /// Decide whether a redirect request comes from an automated client
+    pub fn is_bot(&self, method: &Method, headers: &HeaderMap, user_agent: Option<&str>) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        if method == Method::HEAD {
+            return true;
+        }
+
+        let is_preview = PREVIEW_HEADERS.iter().any(|(name, value)| {
+            headers
+                .get(*name)
+                .and_then(|h| h.to_str().ok())
+                .is_some_and(|h| h.to_ascii_lowercase().contains(value))
+        });
+        if is_preview {
+            return true;
+        }
+
+        let Some(user_agent) = user_agent else {
+            return true;
+        };
+
+        let lower = user_agent.to_ascii_lowercase();
+        if self
+            .extra_patterns
+            .iter()
+            .any(|p| lower.contains(p.as_str()))
+        {
+            return true;
+        }
+
+        classify(user_agent).device_class == DeviceClass::Bot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHROME: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+    fn detector() -> BotDetector {
+        BotDetector::new(true, vec!["StatusCake".to_string(), " ".to_string()])
+    }
+
+    #[test]
+    fn test_browser_is_not_bot() {
+        assert!(!detector().is_bot(&Method::GET, &HeaderMap::new(), Some(CHROME)));
+    }
+
+    #[test]
+    fn test_head_request_is_bot() {
+        assert!(detector().is_bot(&Method::HEAD, &HeaderMap::new(), Some(CHROME)));
+    }
+
+    #[test]
+    fn test_prefetch_header_is_bot() {
+        let mut headers = HeaderMap::new();
+        headers.insert("sec-purpose", "prefetch;prerender".parse().unwrap());
+
+        assert!(detector().is_bot(&Method::GET, &headers, Some(CHROME)));
+    }
+
+    #[test]
+    fn test_unfurlers_are_bots() {
+        let unfurlers = [
+            "Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)",
+            "Twitterbot/1.0",
+            "facebookexternalhit/1.1 (+http://www.facebook.com/externalhit_uatext.php)",
+            "Mozilla/5.0 (compatible; Discordbot/2.0; +https://discordapp.com)",
+        ];
+
+        for ua in unfurlers {
+            assert!(
+                detector().is_bot(&Method::GET, &HeaderMap::new(), Some(ua)),
+                "{}",
+                ua
+            );
+        }
+    }
+
+    #[test]
+    fn test_extra_patterns_and_missing_user_agent() {
+        let monitor = "Mozilla/5.0 (compatible; StatusCake)";
+
+        assert!(detector().is_bot(&Method::GET, &HeaderMap::new(), Some(monitor)));
+        assert!(detector().is_bot(&Method::GET, &HeaderMap::new(), None));
+    }
+
+    #[test]
+    fn test_disabled_detector() {
+        let detector = BotDetector::new(false, Vec::new());
+        assert!(!detector.is_bot(&Method::HEAD, &HeaderMap::new(), None));
+    }
+}
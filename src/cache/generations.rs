@@ -0,0 +1,63 @@
+//! Per-code invalidation counters that keep stale lookups out of the cache.
+//!
+//! A database lookup that started before a link was updated or deleted can
+//! finish after the cache entry was invalidated, and would otherwise put the
+//! old routing data back for a full TTL. Invalidations bump the code's
+//! generation; a lookup that sees the generation change while it runs drops
+//! its result instead of caching it.
+//!
+//! Codes share a fixed number of slots, so memory stays bounded. Two codes in
+//! the same slot only cost each other an occasional skipped cache write.
+
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of generation slots
+const SLOTS: usize = 4096;
+
+/// Generation counters for cached short codes
+pub struct CacheGenerations {
+    slots: Box<[AtomicU64]>,
+}
+
+impl Default for CacheGenerations {
+    fn default() -> Self {
+        Self {
+            slots: (0..SLOTS).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+}
+
+impl CacheGenerations {
+    /// Current generation of a code; read it before starting a lookup
+    pub fn current(&self, short_code: &str) -> u64 {
+        self.slot(short_code).load(Ordering::SeqCst)
+    }
+
+    /// Mark every lookup of `short_code` in flight as stale
+    pub fn bump(&self, short_code: &str) {
+        self.slot(short_code).fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn slot(&self, short_code: &str) -> &AtomicU64 {
+        let mut hasher = DefaultHasher::new();
+        short_code.hash(&mut hasher);
+        &self.slots[hasher.finish() as usize % SLOTS]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bump_changes_generation() {
+        let generations = CacheGenerations::default();
+        let before = generations.current("abc123");
+
+        generations.bump("abc123");
+        assert_ne!(generations.current("abc123"), before);
+        assert_eq!(generations.current("abc123"), generations.current("abc123"));
+    }
+}
@@ -0,0 +1,126 @@
+//! Bounded in-process cache tier checked before Redis.
+//!
+//! Entries live for a short TTL so that a link changed or deleted through
+//! another instance is only served stale for a bounded time. The in-memory
+//! backend uses the same structure as its only tier.
+
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Size-bounded LRU cache with a per-entry TTL
+pub struct LocalCache<V> {
+    entries: Mutex<LruCache<String, (V, Instant)>>,
+    ttl: Duration,
+}
+
+impl<V: Clone> LocalCache<V> {
+    /// Create a cache holding at most `capacity` entries for `ttl` each
+    pub fn new(capacity: NonZeroUsize, ttl: Duration) -> Self {
+        Self {
+            entries: Mutex::new(LruCache::new(capacity)),
+            ttl,
+        }
+    }
+
+    /// Get a live entry, evicting it if its TTL has passed
+    pub fn get(&self, short_code: &str) -> Option<V> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        match entries.get(short_code) {
+            Some((entry, expires)) if *expires > Instant::now() => Some(entry.clone()),
+            Some(_) => {
+                entries.pop(short_code);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Insert an entry, living for at most `max_ttl` (capped at the tier TTL)
+    pub fn insert(&self, short_code: &str, entry: V, max_ttl: Duration) {
+        let expires = Instant::now() + self.ttl.min(max_ttl);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.put(short_code.to_string(), (entry, expires));
+    }
+
+    /// Remove an entry
+    pub fn remove(&self, short_code: &str) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.pop(short_code);
+    }
+
+    /// Remove all entries
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::CachedUrl;
+
+    fn entry(code: &str) -> CachedUrl {
+        CachedUrl {
+            id: 1,
+            short_code: code.to_string(),
+            original_url: "https://example.com".to_string(),
+            expires_at: None,
+            owner_id: None,
+        }
+    }
+
+    fn cache(capacity: usize, ttl: Duration) -> LocalCache<CachedUrl> {
+        LocalCache::new(NonZeroUsize::new(capacity).unwrap(), ttl)
+    }
+
+    #[test]
+    fn test_get_insert_remove() {
+        let cache = cache(10, Duration::from_secs(60));
+
+        assert!(cache.get("abc").is_none());
+        cache.insert("abc", entry("abc"), Duration::MAX);
+        assert_eq!(cache.get("abc"), Some(entry("abc")));
+
+        cache.remove("abc");
+        assert!(cache.get("abc").is_none());
+
+        cache.insert("abc", entry("abc"), Duration::MAX);
+        cache.insert("xyz", entry("xyz"), Duration::MAX);
+        cache.clear();
+        assert!(cache.get("abc").is_none());
+        assert!(cache.get("xyz").is_none());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = cache(2, Duration::from_secs(60));
+
+        cache.insert("a", entry("a"), Duration::MAX);
+        cache.insert("b", entry("b"), Duration::MAX);
+        cache.get("a");
+        cache.insert("c", entry("c"), Duration::MAX);
+
+        assert_eq!(cache.entries.lock().unwrap().len(), 2);
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("b").is_none());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn test_entries_expire() {
+        let cache = cache(10, Duration::from_secs(60));
+
+        cache.insert("short", entry("short"), Duration::ZERO);
+        cache.insert("long", entry("long"), Duration::MAX);
+
+        assert!(cache.get("short").is_none());
+        assert_eq!(cache.entries.lock().unwrap().len(), 1);
+        assert!(cache.get("long").is_some());
+    }
+}
@@ -0,0 +1,348 @@
+//! In-process cache backend.
+//!
+//! Keeps URL routing data, tombstones and unique-visitor sets in this process
+//! only, so it suits single-instance deployments and tests without Redis.
+//! Updates made through other instances are not seen until entries expire.
+//!
+//! Unique visitors are counted exactly, up to [`MAX_VISITORS_PER_DAY`] per
+//! scope and day; past that, new visitors are not counted for the day, which
+//! keeps memory bounded however much anonymous traffic arrives.
+
+use super::local::LocalCache;
+use super::{entry_ttl, CacheBackend, CacheLookup, CacheStats, UniqueScope};
+use crate::config::CacheBackendKind;
+use crate::error::AppResult;
+use crate::models::CachedUrl;
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Most distinct visitors remembered per scope and day
+const MAX_VISITORS_PER_DAY: usize = 10_000;
+
+/// Visitors per scope key and day, kept as 64-bit digests of the visitor hashes
+#[derive(Default)]
+struct VisitorSets {
+    days: HashMap<(String, NaiveDate), HashSet<u64>>,
+    /// Day of the last pass dropping days past retention
+    pruned_on: Option<NaiveDate>,
+}
+
+/// Single-process cache for URL lookups
+pub struct MemoryCache {
+    /// Routing data, or `None` for a tombstone
+    entries: LocalCache<Option<CachedUrl>>,
+    negative_ttl: Duration,
+    default_ttl: Duration,
+    visitors: Mutex<VisitorSets>,
+    stats: CacheStats,
+}
+
+impl MemoryCache {
+    /// Create a cache holding at most `max_entries` URLs (at least one) for `default_ttl` each
+    ///
+    /// Tombstones are kept for `negative_ttl`; zero disables them.
+    pub fn new(max_entries: usize, default_ttl: Duration, negative_ttl: Duration) -> Self {
+        Self {
+            entries: LocalCache::new(
+                NonZeroUsize::new(max_entries).unwrap_or(NonZeroUsize::MIN),
+                default_ttl,
+            ),
+            negative_ttl,
+            default_ttl,
+            visitors: Mutex::new(VisitorSets::default()),
+            stats: CacheStats::default(),
+        }
+    }
+
+    fn visitors(&self) -> std::sync::MutexGuard<'_, VisitorSets> {
+        self.visitors.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCache {
+    fn kind(&self) -> CacheBackendKind {
+        CacheBackendKind::Memory
+    }
+
+    async fn ping(&self) -> AppResult<String> {
+        Ok("PONG".to_string())
+    }
+
+    fn stats(&self) -> &CacheStats {
+        &self.stats
+    }
+
+    async fn get_url(&self, short_code: &str) -> AppResult<CacheLookup> {
+        match self.entries.get(short_code) {
+            Some(Some(entry)) => {
+                self.stats.local.record_hit();
+                Ok(CacheLookup::Hit(entry))
+            }
+            Some(None) => {
+                self.stats.local.record_hit();
+                Ok(CacheLookup::Tombstone)
+            }
+            None => {
+                self.stats.local.record_miss();
+                Ok(CacheLookup::Miss)
+            }
+        }
+    }
+
+    async fn set_url(&self, entry: &CachedUrl) -> AppResult<()> {
+        if let Some(ttl) = entry_ttl(entry, self.default_ttl, Utc::now()) {
+            self.entries
+                .insert(&entry.short_code, Some(entry.clone()), ttl);
+        }
+        Ok(())
+    }
+
+    async fn set_missing(&self, short_code: &str) -> AppResult<()> {
+        if !self.negative_ttl.is_zero() {
+            self.entries.insert(short_code, None, self.negative_ttl);
+        }
+        Ok(())
+    }
+
+    async fn delete_url(&self, short_code: &str) -> AppResult<()> {
+        self.entries.remove(short_code);
+        Ok(())
+    }
+
+    /// Record a visitor; days older than `retention` are dropped once per day
+    async fn record_unique_visitor(
+        &self,
+        short_code: &str,
+        owner_id: Option<i64>,
+        visitor_hash: &str,
+        date: NaiveDate,
+        retention: Duration,
+    ) -> AppResult<()> {
+        let mut scopes = vec![UniqueScope::Url(short_code), UniqueScope::Global];
+        if let Some(owner_id) = owner_id {
+            scopes.push(UniqueScope::Owner(owner_id));
+        }
+
+        let retention_days = (retention.as_secs() / 86_400) as i64;
+        let oldest = date - chrono::Duration::days(retention_days);
+
+        let mut visitors = self.visitors();
+        if visitors.pruned_on.is_none_or(|day| day < date) {
+            visitors.days.retain(|(_, day), _| *day >= oldest);
+            visitors.pruned_on = Some(date);
+        }
+
+        let visitor = digest(visitor_hash);
+        for scope in &scopes {
+            let day = visitors.days.entry((scope.key_part(), date)).or_default();
+            if day.len() < MAX_VISITORS_PER_DAY {
+                day.insert(visitor);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Exact number of distinct visitors over `[from, to]` (inclusive days)
+    async fn count_unique_visitors(
+        &self,
+        scope: UniqueScope<'_>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> AppResult<i64> {
+        let key = scope.key_part();
+        let visitors = self.visitors();
+        let distinct: HashSet<&u64> = from
+            .iter_days()
+            .take_while(|d| *d <= to)
+            .filter_map(|d| visitors.days.get(&(key.clone(), d)))
+            .flatten()
+            .collect();
+
+        Ok(distinct.len() as i64)
+    }
+
+    /// Exact number of distinct visitors per day over `[from, to]` (inclusive days)
+    async fn count_daily_unique_visitors(
+        &self,
+        scope: UniqueScope<'_>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> AppResult<Vec<(NaiveDate, i64)>> {
+        let key = scope.key_part();
+        let visitors = self.visitors();
+
+        Ok(from
+            .iter_days()
+            .take_while(|d| *d <= to)
+            .map(|d| {
+                let count = visitors
+                    .days
+                    .get(&(key.clone(), d))
+                    .map_or(0, |set| set.len());
+                (d, count as i64)
+            })
+            .collect())
+    }
+}
+
+/// Compact digest of a visitor hash
+fn digest(visitor_hash: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    visitor_hash.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::test_cached_url as cached_url;
+
+    fn cache() -> MemoryCache {
+        MemoryCache::new(100, Duration::from_secs(60), Duration::from_secs(60))
+    }
+
+    #[tokio::test]
+    async fn test_get_set_delete() {
+        let cache = cache();
+        let entry = cached_url(None);
+
+        assert_eq!(cache.get_url("abc123").await.unwrap(), CacheLookup::Miss);
+        cache.set_url(&entry).await.unwrap();
+        assert_eq!(
+            cache.get_url("abc123").await.unwrap(),
+            CacheLookup::Hit(entry)
+        );
+
+        cache.delete_url("abc123").await.unwrap();
+        assert_eq!(cache.get_url("abc123").await.unwrap(), CacheLookup::Miss);
+        assert_eq!(cache.stats().local.hits(), 1);
+        assert_eq!(cache.stats().local.misses(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_tombstone_replaced_by_created_url() {
+        let cache = cache();
+        let entry = cached_url(None);
+
+        cache.set_missing("abc123").await.unwrap();
+        assert_eq!(
+            cache.get_url("abc123").await.unwrap(),
+            CacheLookup::Tombstone
+        );
+
+        cache.set_url(&entry).await.unwrap();
+        assert_eq!(
+            cache.get_url("abc123").await.unwrap(),
+            CacheLookup::Hit(entry)
+        );
+
+        let cache = MemoryCache::new(100, Duration::from_secs(60), Duration::ZERO);
+        cache.set_missing("abc123").await.unwrap();
+        assert_eq!(cache.get_url("abc123").await.unwrap(), CacheLookup::Miss);
+    }
+
+    #[tokio::test]
+    async fn test_expired_links_not_cached() {
+        let cache = cache();
+        let expired = cached_url(Some(Utc::now() - chrono::Duration::seconds(1)));
+
+        cache.set_url(&expired).await.unwrap();
+        assert_eq!(cache.get_url("abc123").await.unwrap(), CacheLookup::Miss);
+    }
+
+    #[tokio::test]
+    async fn test_unique_visitors() {
+        let cache = cache();
+        let day1 = NaiveDate::from_ymd_opt(2026, 1, 15).unwrap();
+        let day2 = day1.succ_opt().unwrap();
+        let retention = Duration::from_secs(90 * 86_400);
+
+        for (visitor, day) in [("a", day1), ("b", day1), ("a", day2), ("c", day2)] {
+            cache
+                .record_unique_visitor("abc123", Some(7), visitor, day, retention)
+                .await
+                .unwrap();
+        }
+        cache
+            .record_unique_visitor("xyz", None, "d", day2, retention)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            cache
+                .count_unique_visitors(UniqueScope::Url("abc123"), day1, day2)
+                .await
+                .unwrap(),
+            3
+        );
+        assert_eq!(
+            cache
+                .count_unique_visitors(UniqueScope::Owner(7), day2, day2)
+                .await
+                .unwrap(),
+            2
+        );
+        assert_eq!(
+            cache
+                .count_unique_visitors(UniqueScope::Global, day1, day2)
+                .await
+                .unwrap(),
+            4
+        );
+        assert_eq!(
+            cache
+                .count_daily_unique_visitors(UniqueScope::Url("abc123"), day1, day2)
+                .await
+                .unwrap(),
+            vec![(day1, 2), (day2, 2)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unique_visitors_bounded() {
+        let cache = cache();
+        let day = NaiveDate::from_ymd_opt(2026, 1, 15).unwrap();
+        let retention = Duration::from_secs(7 * 86_400);
+
+        for i in 0..MAX_VISITORS_PER_DAY + 10 {
+            cache
+                .record_unique_visitor("abc123", None, &format!("visitor-{}", i), day, retention)
+                .await
+                .unwrap();
+        }
+        assert_eq!(
+            cache
+                .count_unique_visitors(UniqueScope::Url("abc123"), day, day)
+                .await
+                .unwrap(),
+            MAX_VISITORS_PER_DAY as i64
+        );
+
+        // Days past retention are dropped on the first visit of a later day
+        let later = day + chrono::Duration::days(30);
+        cache
+            .record_unique_visitor("abc123", None, "visitor-0", later, retention)
+            .await
+            .unwrap();
+        assert_eq!(cache.visitors().days.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_click_counters_unsupported() {
+        let cache = cache();
+
+        assert!(cache
+            .increment_click_counter("abc123", false, Utc::now())
+            .await
+            .is_err());
+        assert!(cache.take_click_counters(10).await.is_err());
+    }
+}
@@ -0,0 +1,316 @@
+//! Cache backends for URL lookups and Redis-backed counters.
+//!
+//! Handlers talk to a [`CacheBackend`] trait object selected by `CACHE_BACKEND`:
+//! - `redis`: the shared Redis cache (with optional in-process LRU tier)
+//! - `memory`: a single-process in-memory cache, useful without Redis and in tests
+//! - `none`: a no-op backend; every lookup misses and writes are dropped
+
+mod generations;
+mod local;
+mod memory;
+mod noop;
+mod redis;
+
+use crate::config::{CacheBackendKind, CacheConfig};
+use crate::db::ClickCountDelta;
+use crate::error::{AppError, AppResult};
+use crate::models::CachedUrl;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use crate::circuit_breaker::CircuitState;
+pub use generations::CacheGenerations;
+pub use memory::MemoryCache;
+pub use noop::NoopCache;
+pub use redis::RedisCache;
+
+/// Shared handle to the configured cache backend
+pub type SharedCache = Arc<dyn CacheBackend>;
+
+/// Which set of clicks a unique-visitor count covers
+#[derive(Debug, Clone, Copy)]
+pub enum UniqueScope<'a> {
+    /// A single short URL
+    Url(&'a str),
+    /// All URLs owned by one user
+    Owner(i64),
+    /// Every URL
+    Global,
+}
+
+impl UniqueScope<'_> {
+    pub(crate) fn key_part(&self) -> String {
+        match self {
+            UniqueScope::Url(code) => format!("url:{}", code),
+            UniqueScope::Owner(id) => format!("owner:{}", id),
+            UniqueScope::Global => "all".to_string(),
+        }
+    }
+}
+
+/// Result of looking up a short code in the cache
+#[derive(Debug, Clone, PartialEq)]
+pub enum CacheLookup {
+    /// Routing data for a live link
+    Hit(CachedUrl),
+    /// Routing data that is close to its TTL and was picked for an early refresh
+    Stale(CachedUrl),
+    /// The code was recently found to be unknown or expired
+    Tombstone,
+    /// Nothing cached; the database must be consulted
+    Miss,
+}
+
+/// Hit/miss counters for URL lookups in one cache tier
+#[derive(Debug, Default)]
+pub struct TierStats {
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl TierStats {
+    pub(crate) fn record_hit(&self) {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_miss(&self) {
+        self.misses.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of lookups served from this tier
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Number of lookups that fell through to the next tier
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Fraction of lookups served from this tier (None before the first lookup)
+    pub fn hit_ratio(&self) -> Option<f64> {
+        let hits = self.hits();
+        let total = hits + self.misses();
+        (total > 0).then(|| hits as f64 / total as f64)
+    }
+}
+
+/// Per-tier lookup counters for a cache backend
+#[derive(Debug, Default)]
+pub struct CacheStats {
+    /// In-process tier (the Redis backend's LRU tier, or the in-memory backend)
+    pub local: TierStats,
+    /// Redis tier (only counted by the Redis backend)
+    pub redis: TierStats,
+}
+
+/// Operations the application needs from a cache
+///
+/// Capabilities a backend doesn't support (Redis-side click counters, unique
+/// visitors) fail with [`AppError::CacheUnavailable`] by default, and callers
+/// treat that like any other cache error.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// Which backend this is
+    fn kind(&self) -> CacheBackendKind;
+
+    /// Check connectivity
+    async fn ping(&self) -> AppResult<String>;
+
+    /// Lookup counters
+    fn stats(&self) -> &CacheStats;
+
+    /// State of the circuit breaker, for backends that have one
+    fn circuit_state(&self) -> Option<CircuitState> {
+        None
+    }
+
+    /// Get a URL's routing data (or tombstone); errors should be reported as `Miss`
+    async fn get_url(&self, short_code: &str) -> AppResult<CacheLookup>;
+
+    /// Cache a URL's routing data, replacing any tombstone
+    async fn set_url(&self, entry: &CachedUrl) -> AppResult<()>;
+
+    /// Remember that a short code is unknown or expired
+    async fn set_missing(&self, short_code: &str) -> AppResult<()>;
+
+    /// Drop a URL (or tombstone) from the cache
+    async fn delete_url(&self, short_code: &str) -> AppResult<()>;
+
+    /// Tell other instances to drop a URL from their in-process caches
+    async fn publish_invalidation(&self, _short_code: &str) -> AppResult<()> {
+        Ok(())
+    }
+
+    /// Apply invalidations published by other instances until the task is aborted
+    async fn listen_for_invalidations(&self) {}
+
+    /// Count a redirect in cache-side click counters
+    async fn increment_click_counter(
+        &self,
+        _short_code: &str,
+        _is_bot: bool,
+        _clicked_at: DateTime<Utc>,
+    ) -> AppResult<()> {
+        Err(AppError::CacheUnavailable)
+    }
+
+    /// Atomically read and reset the counters of up to `limit` pending short codes
+    async fn take_click_counters(&self, _limit: usize) -> AppResult<Vec<ClickCountDelta>> {
+        Err(AppError::CacheUnavailable)
+    }
+
+    /// Add counters back after a failed sync
+    async fn restore_click_counters(&self, _deltas: &[ClickCountDelta]) -> AppResult<()> {
+        Err(AppError::CacheUnavailable)
+    }
+
+    /// Record a visitor for a URL, its owner and globally on `date`
+    async fn record_unique_visitor(
+        &self,
+        _short_code: &str,
+        _owner_id: Option<i64>,
+        _visitor_hash: &str,
+        _date: NaiveDate,
+        _retention: Duration,
+    ) -> AppResult<()> {
+        Err(AppError::CacheUnavailable)
+    }
+
+    /// Number of distinct visitors over `[from, to]` (inclusive days)
+    async fn count_unique_visitors(
+        &self,
+        _scope: UniqueScope<'_>,
+        _from: NaiveDate,
+        _to: NaiveDate,
+    ) -> AppResult<i64> {
+        Err(AppError::CacheUnavailable)
+    }
+
+    /// Number of distinct visitors per day over `[from, to]` (inclusive days)
+    async fn count_daily_unique_visitors(
+        &self,
+        _scope: UniqueScope<'_>,
+        _from: NaiveDate,
+        _to: NaiveDate,
+    ) -> AppResult<Vec<(NaiveDate, i64)>> {
+        Err(AppError::CacheUnavailable)
+    }
+}
+
+/// Build the cache backend selected in the configuration
+pub async fn from_config(config: &CacheConfig) -> AppResult<SharedCache> {
+    let cache: SharedCache = match config.backend {
+        CacheBackendKind::Redis => Arc::new(
+            RedisCache::new(
+                &config.url,
+                config.max_connections,
+                config.default_ttl_seconds,
+                Duration::from_millis(config.command_timeout_ms),
+            )
+            .await?
+            .with_circuit_breaker(
+                config.breaker_failure_threshold,
+                Duration::from_secs(config.breaker_cooldown_seconds),
+            )
+            .with_negative_ttl(Duration::from_secs(config.negative_ttl_seconds))
+            .with_early_refresh(Duration::from_secs(config.early_refresh_seconds))
+            .with_local_cache(
+                config.local_max_entries,
+                Duration::from_secs(config.local_ttl_seconds),
+            ),
+        ),
+        CacheBackendKind::Memory => Arc::new(MemoryCache::new(
+            config.local_max_entries,
+            Duration::from_secs(config.default_ttl_seconds),
+            Duration::from_secs(config.negative_ttl_seconds),
+        )),
+        CacheBackendKind::None => Arc::new(NoopCache::default()),
+    };
+
+    Ok(cache)
+}
+
+/// TTL for a cached entry: the default TTL, or less if the link expires sooner
+pub(crate) fn entry_ttl(
+    entry: &CachedUrl,
+    default_ttl: Duration,
+    now: DateTime<Utc>,
+) -> Option<Duration> {
+    match entry.expires_at {
+        Some(expires_at) => {
+            let remaining = (expires_at - now).num_seconds();
+            (remaining > 0).then(|| default_ttl.min(Duration::from_secs(remaining as u64)))
+        }
+        None => Some(default_ttl),
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_cached_url(expires_at: Option<DateTime<Utc>>) -> CachedUrl {
+    CachedUrl {
+        id: 1,
+        short_code: "abc123".to_string(),
+        original_url: "https://example.com".to_string(),
+        expires_at,
+        owner_id: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_ttl_capped_at_expiry() {
+        let now = Utc::now();
+        let default_ttl = Duration::from_secs(3600);
+
+        assert_eq!(
+            entry_ttl(&test_cached_url(None), default_ttl, now),
+            Some(default_ttl)
+        );
+        assert_eq!(
+            entry_ttl(
+                &test_cached_url(Some(now + chrono::Duration::seconds(60))),
+                default_ttl,
+                now
+            ),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            entry_ttl(
+                &test_cached_url(Some(now + chrono::Duration::days(7))),
+                default_ttl,
+                now
+            ),
+            Some(default_ttl)
+        );
+        assert_eq!(
+            entry_ttl(
+                &test_cached_url(Some(now - chrono::Duration::seconds(1))),
+                default_ttl,
+                now
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_tier_stats_hit_ratio() {
+        let stats = TierStats::default();
+        assert_eq!(stats.hit_ratio(), None);
+
+        stats.record_miss();
+        for _ in 0..3 {
+            stats.record_hit();
+        }
+
+        assert_eq!(stats.hits(), 3);
+        assert_eq!(stats.misses(), 1);
+        assert_eq!(stats.hit_ratio(), Some(0.75));
+    }
+}
@@ -0,0 +1,65 @@
+//! Cache backend that caches nothing.
+//!
+//! Every lookup misses and every write is dropped, so all reads go to the
+//! database. Used when `CACHE_BACKEND=none`.
+
+use super::{CacheBackend, CacheLookup, CacheStats};
+use crate::config::CacheBackendKind;
+use crate::error::AppResult;
+use crate::models::CachedUrl;
+use async_trait::async_trait;
+
+/// No-op cache backend
+#[derive(Default)]
+pub struct NoopCache {
+    stats: CacheStats,
+}
+
+#[async_trait]
+impl CacheBackend for NoopCache {
+    fn kind(&self) -> CacheBackendKind {
+        CacheBackendKind::None
+    }
+
+    async fn ping(&self) -> AppResult<String> {
+        Ok("disabled".to_string())
+    }
+
+    fn stats(&self) -> &CacheStats {
+        &self.stats
+    }
+
+    async fn get_url(&self, _short_code: &str) -> AppResult<CacheLookup> {
+        Ok(CacheLookup::Miss)
+    }
+
+    async fn set_url(&self, _entry: &CachedUrl) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn set_missing(&self, _short_code: &str) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn delete_url(&self, _short_code: &str) -> AppResult<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::test_cached_url as cached_url;
+
+    #[tokio::test]
+    async fn test_everything_misses() {
+        let cache = NoopCache::default();
+
+        cache.set_url(&cached_url(None)).await.unwrap();
+        cache.set_missing("xyz").await.unwrap();
+
+        assert_eq!(cache.get_url("abc123").await.unwrap(), CacheLookup::Miss);
+        assert_eq!(cache.get_url("xyz").await.unwrap(), CacheLookup::Miss);
+        assert!(cache.publish_invalidation("abc123").await.is_ok());
+    }
+}
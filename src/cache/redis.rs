@@ -0,0 +1,860 @@
+//! Redis cache backend with an optional in-process LRU tier.
+
+use super::local::LocalCache;
+use super::{entry_ttl, CacheBackend, CacheLookup, CacheStats, CircuitState, UniqueScope};
+use crate::circuit_breaker::{CircuitBreaker, Permit};
+use crate::config::CacheBackendKind;
+use crate::db::ClickCountDelta;
+use crate::error::{AppError, AppResult};
+use crate::models::CachedUrl;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use deadpool_redis::{redis::AsyncCommands, Connection, Manager, Pool, Runtime, Timeouts};
+use futures_util::StreamExt;
+use std::future::Future;
+use std::num::NonZeroUsize;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Atomically pops up to `ARGV[1]` pending short codes from the set in `KEYS[1]`
+/// and reads and deletes each code's counter hash (prefixed by `ARGV[2]`).
+/// Returns a flat list of `code, clicks, bot_clicks, last_clicked_at` values.
+const TAKE_CLICK_COUNTERS_SCRIPT: &str = r#"
+local codes = redis.call('SPOP', KEYS[1], ARGV[1])
+local out = {}
+for _, code in ipairs(codes) do
+    local key = ARGV[2] .. code
+    local v = redis.call('HMGET', key, 'clicks', 'bot_clicks', 'last_clicked_at')
+    redis.call('DEL', key)
+    table.insert(out, code)
+    table.insert(out, v[1] or '0')
+    table.insert(out, v[2] or '0')
+    table.insert(out, v[3] or '')
+end
+return out
+"#;
+
+/// Redis-backed cache for URL lookups
+///
+/// Lookups go through an optional in-process LRU tier before Redis.
+#[derive(Clone)]
+pub struct RedisCache {
+    pool: Pool,
+    client: redis::Client,
+    default_ttl: Duration,
+    negative_ttl: Duration,
+    early_refresh: Duration,
+    local: Option<Arc<LocalCache<CachedUrl>>>,
+    stats: Arc<CacheStats>,
+    breaker: Arc<CircuitBreaker>,
+    command_timeout: Duration,
+}
+
+impl RedisCache {
+    /// Create a new cache connection pool
+    ///
+    /// Getting a connection and each command give up after `command_timeout`, so
+    /// a hung Redis counts as failing instead of stalling requests.
+    pub async fn new(
+        redis_url: &str,
+        max_connections: u32,
+        default_ttl_seconds: u64,
+        command_timeout: Duration,
+    ) -> AppResult<Self> {
+        let manager = Manager::new(redis_url)
+            .map_err(|e| AppError::Configuration(format!("Invalid Redis URL: {}", e)))?;
+        let client = redis::Client::open(redis_url)
+            .map_err(|e| AppError::Configuration(format!("Invalid Redis URL: {}", e)))?;
+
+        let pool = Pool::builder(manager)
+            .max_size(max_connections as usize)
+            .runtime(Runtime::Tokio1)
+            .timeouts(Timeouts {
+                wait: Some(command_timeout),
+                create: Some(command_timeout),
+                recycle: Some(command_timeout),
+            })
+            .build()
+            .map_err(|e| AppError::Configuration(format!("Failed to create Redis pool: {}", e)))?;
+
+        Ok(Self {
+            pool,
+            client,
+            default_ttl: Duration::from_secs(default_ttl_seconds),
+            negative_ttl: Duration::ZERO,
+            early_refresh: Duration::ZERO,
+            local: None,
+            stats: Arc::new(CacheStats::default()),
+            breaker: Arc::new(CircuitBreaker::new(
+                "Redis",
+                Self::DEFAULT_BREAKER_THRESHOLD,
+                Self::DEFAULT_BREAKER_COOLDOWN,
+            )),
+            command_timeout,
+        })
+    }
+
+    /// Open the circuit after `failure_threshold` consecutive Redis failures, for `cooldown`
+    pub fn with_circuit_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        self.breaker = Arc::new(CircuitBreaker::new("Redis", failure_threshold, cooldown));
+        self
+    }
+
+    /// Cache tombstones for unknown or expired codes for `ttl` (zero disables them)
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = ttl;
+        self
+    }
+
+    /// Probabilistically refresh entries in the last `window` of their TTL (zero disables it)
+    ///
+    /// The chance of a lookup returning [`CacheLookup::Stale`] grows linearly from
+    /// 0 at the start of the window to 1 at expiry, so a hot entry is usually
+    /// refreshed by a single request before it expires for everyone.
+    pub fn with_early_refresh(mut self, window: Duration) -> Self {
+        self.early_refresh = window;
+        self
+    }
+
+    /// Enable the in-process tier with at most `max_entries` entries, each kept for `ttl`
+    ///
+    /// A `max_entries` or `ttl` of zero leaves the tier disabled.
+    pub fn with_local_cache(mut self, max_entries: usize, ttl: Duration) -> Self {
+        self.local = NonZeroUsize::new(max_entries)
+            .filter(|_| !ttl.is_zero())
+            .map(|capacity| Arc::new(LocalCache::new(capacity, ttl)));
+        self
+    }
+
+    /// Get a pooled connection, unless the circuit breaker is skipping Redis
+    ///
+    /// Returns [`AppError::CacheUnavailable`] while the circuit is open. When the
+    /// cool-down has ended, a background `PING` probes Redis to close it again.
+    async fn conn(&self) -> AppResult<Connection> {
+        match self.breaker.acquire() {
+            Permit::Allowed => self.track(self.pool.get().await),
+            Permit::Probe => {
+                let cache = self.clone();
+                tokio::spawn(async move {
+                    match cache.ping().await {
+                        Ok(_) => tracing::info!("Redis is reachable again, circuit closed"),
+                        Err(e) => tracing::warn!("Redis probe failed, circuit stays open: {}", e),
+                    }
+                });
+                Err(AppError::CacheUnavailable)
+            }
+            Permit::Rejected => Err(AppError::CacheUnavailable),
+        }
+    }
+
+    /// Like [`RedisCache::conn`], but `None` while the circuit is open so writes can be skipped
+    async fn conn_or_skip(&self) -> AppResult<Option<Connection>> {
+        match self.conn().await {
+            Ok(conn) => Ok(Some(conn)),
+            Err(AppError::CacheUnavailable) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Feed the outcome of a Redis call into the circuit breaker
+    fn track<T, E: Into<AppError>>(&self, result: Result<T, E>) -> AppResult<T> {
+        match result {
+            Ok(value) => {
+                self.breaker.record_success();
+                Ok(value)
+            }
+            Err(e) => {
+                self.breaker.record_failure();
+                Err(e.into())
+            }
+        }
+    }
+
+    /// Run a Redis command, feeding its outcome into the circuit breaker
+    ///
+    /// A command still pending after `command_timeout` is dropped and counts as a failure.
+    async fn timed<T, E: Into<AppError>>(
+        &self,
+        command: impl Future<Output = Result<T, E>>,
+    ) -> AppResult<T> {
+        match tokio::time::timeout(self.command_timeout, command).await {
+            Ok(result) => self.track(result),
+            Err(_) => {
+                self.breaker.record_failure();
+                Err(AppError::Redis(
+                    std::io::Error::from(std::io::ErrorKind::TimedOut).into(),
+                ))
+            }
+        }
+    }
+
+    /// Decide whether an entry with `remaining` TTL should be refreshed now
+    ///
+    /// `roll` is a uniform random number in `[0, 1)`. Entries whose TTL was capped
+    /// by the link's own expiry are never refreshed, as the database has nothing newer.
+    fn should_refresh_early(&self, entry: &CachedUrl, remaining: Duration, roll: f64) -> bool {
+        if self.early_refresh.is_zero() || remaining >= self.early_refresh {
+            return false;
+        }
+
+        let capped_by_expiry = entry.expires_at.is_some_and(|expires_at| {
+            (expires_at - Utc::now()).num_seconds() <= remaining.as_secs() as i64 + 1
+        });
+        if capped_by_expiry {
+            return false;
+        }
+
+        roll >= remaining.as_secs_f64() / self.early_refresh.as_secs_f64()
+    }
+
+    /// Store an entry in the in-process tier, if enabled and not yet expired
+    fn set_local(&self, entry: &CachedUrl) {
+        if let Some(local) = &self.local {
+            if let Some(ttl) = entry_ttl(entry, self.default_ttl, Utc::now()) {
+                local.insert(&entry.short_code, entry.clone(), ttl);
+            }
+        }
+    }
+
+    /// Generate cache key for a URL
+    fn url_key(short_code: &str) -> String {
+        format!("{}:{}", Self::KEY_PREFIX, short_code)
+    }
+
+    /// Generate Redis-side click counter key for a URL
+    fn click_counter_key(short_code: &str) -> String {
+        format!("{}:{}", Self::CLICK_COUNTER_PREFIX, short_code)
+    }
+
+    /// Generate HyperLogLog key for one day of unique visitors
+    fn unique_visitor_key(scope: &UniqueScope<'_>, date: NaiveDate) -> String {
+        format!(
+            "{}:{}:{}",
+            Self::UNIQUE_VISITOR_PREFIX,
+            scope.key_part(),
+            date.format("%Y-%m-%d")
+        )
+    }
+
+    const KEY_PREFIX: &'static str = "url";
+
+    const DEFAULT_BREAKER_THRESHOLD: u32 = 5;
+
+    const DEFAULT_BREAKER_COOLDOWN: Duration = Duration::from_secs(10);
+
+    /// Value stored under a URL key for unknown or expired codes (never valid JSON)
+    const TOMBSTONE: &'static str = "-";
+
+    const UNIQUE_VISITOR_PREFIX: &'static str = "uv";
+
+    const CLICK_COUNTER_PREFIX: &'static str = "clicks";
+
+    /// Pub/sub channel carrying short codes to evict from in-process tiers
+    const INVALIDATION_CHANNEL: &'static str = "invalidate:url";
+
+    const INVALIDATION_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+    const INVALIDATION_MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+    /// Set of short codes with unsynced click counters
+    const PENDING_CLICKS_KEY: &'static str = "pending:clicks";
+}
+
+#[async_trait]
+impl CacheBackend for RedisCache {
+    fn kind(&self) -> CacheBackendKind {
+        CacheBackendKind::Redis
+    }
+
+    /// Ping the Redis server to check connectivity
+    ///
+    /// Bypasses the circuit breaker (so health checks always reach Redis) but
+    /// feeds the result into it.
+    async fn ping(&self) -> AppResult<String> {
+        let mut conn = self.track(self.pool.get().await)?;
+        let response: String = self
+            .timed(redis::cmd("PING").query_async(&mut *conn))
+            .await?;
+        Ok(response)
+    }
+
+    /// Current state of the Redis circuit breaker
+    fn circuit_state(&self) -> Option<CircuitState> {
+        Some(self.breaker.state())
+    }
+
+    /// Lookup counters for this cache
+    fn stats(&self) -> &CacheStats {
+        &self.stats
+    }
+
+    /// Get a URL's routing data (or tombstone) from cache by short code
+    /// Returns `Miss` if cache fails or nothing is cached
+    async fn get_url(&self, short_code: &str) -> AppResult<CacheLookup> {
+        if let Some(local) = &self.local {
+            if let Some(entry) = local.get(short_code) {
+                self.stats.local.record_hit();
+                return Ok(CacheLookup::Hit(entry));
+            }
+            self.stats.local.record_miss();
+        }
+
+        let key = Self::url_key(short_code);
+
+        // Treat an unavailable Redis (or open circuit) as a miss
+        let mut conn = match self.conn().await {
+            Ok(c) => c,
+            Err(AppError::CacheUnavailable) => {
+                self.stats.redis.record_miss();
+                return Ok(CacheLookup::Miss);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to get Redis connection for {}: {}", short_code, e);
+                self.stats.redis.record_miss();
+                return Ok(CacheLookup::Miss);
+            }
+        };
+
+        let mut pipe = redis::pipe();
+        pipe.get(&key);
+        pipe.pttl(&key);
+        let result = self
+            .timed(pipe.query_async::<(Option<String>, i64)>(&mut *conn))
+            .await;
+        let (value, ttl_ms) = match result {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!("Redis GET failed for {}: {}", key, e);
+                self.stats.redis.record_miss();
+                return Ok(CacheLookup::Miss); // Cache error treated as miss
+            }
+        };
+
+        match value {
+            Some(v) if v == Self::TOMBSTONE => {
+                self.stats.redis.record_hit();
+                Ok(CacheLookup::Tombstone)
+            }
+            Some(v) => {
+                let entry: CachedUrl = match serde_json::from_str(&v) {
+                    Ok(entry) => entry,
+                    Err(e) => {
+                        // A corrupt entry is a miss; the database lookup will overwrite it
+                        tracing::warn!("Invalid cache entry for {}: {}", key, e);
+                        self.stats.redis.record_miss();
+                        return Ok(CacheLookup::Miss);
+                    }
+                };
+                self.stats.redis.record_hit();
+                self.set_local(&entry);
+
+                let remaining = Duration::from_millis(ttl_ms.max(0) as u64);
+                if self.should_refresh_early(&entry, remaining, rand::random()) {
+                    Ok(CacheLookup::Stale(entry))
+                } else {
+                    Ok(CacheLookup::Hit(entry))
+                }
+            }
+            None => {
+                self.stats.redis.record_miss();
+                Ok(CacheLookup::Miss)
+            }
+        }
+    }
+
+    /// Remember that a short code is unknown or expired
+    ///
+    /// Tombstones share the URL's key, so creating or caching the URL replaces them.
+    async fn set_missing(&self, short_code: &str) -> AppResult<()> {
+        if self.negative_ttl.is_zero() {
+            return Ok(());
+        }
+
+        if let Some(local) = &self.local {
+            local.remove(short_code);
+        }
+
+        let key = Self::url_key(short_code);
+        let Some(mut conn) = self.conn_or_skip().await? else {
+            return Ok(());
+        };
+
+        let _: () = self
+            .timed(conn.set_ex(&key, Self::TOMBSTONE, self.negative_ttl.as_secs()))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Set a URL's routing data in cache, replacing any tombstone
+    ///
+    /// The TTL is capped at the link's expiry so expired links drop out on
+    /// their own; already-expired links are not cached at all.
+    async fn set_url(&self, entry: &CachedUrl) -> AppResult<()> {
+        let Some(ttl) = entry_ttl(entry, self.default_ttl, Utc::now()) else {
+            return Ok(());
+        };
+        self.set_local(entry);
+
+        let key = Self::url_key(&entry.short_code);
+        let value = serde_json::to_string(entry)?;
+        let ttl = ttl.as_secs();
+        let Some(mut conn) = self.conn_or_skip().await? else {
+            return Ok(());
+        };
+
+        // Type annotation needed for return type
+        let _: () = self.timed(conn.set_ex(&key, value, ttl)).await?;
+
+        Ok(())
+    }
+
+    /// Delete a URL from cache
+    async fn delete_url(&self, short_code: &str) -> AppResult<()> {
+        if let Some(local) = &self.local {
+            local.remove(short_code);
+        }
+
+        let key = Self::url_key(short_code);
+        let Some(mut conn) = self.conn_or_skip().await? else {
+            return Ok(());
+        };
+
+        let _: () = self.timed(conn.del(&key)).await?;
+
+        Ok(())
+    }
+
+    /// Tell every instance (including this one) to evict a URL from its in-process tier
+    async fn publish_invalidation(&self, short_code: &str) -> AppResult<()> {
+        let mut conn = self.conn().await?;
+        let _: () = self
+            .timed(conn.publish(Self::INVALIDATION_CHANNEL, short_code))
+            .await?;
+
+        Ok(())
+    }
+
+    /// Evict published short codes from the in-process tier until the task is aborted
+    ///
+    /// Reconnects after errors. Invalidations missed while disconnected are covered
+    /// by clearing the in-process tier on every (re)subscribe.
+    async fn listen_for_invalidations(&self) {
+        let Some(local) = self.local.clone() else {
+            return;
+        };
+
+        let mut delay = Self::INVALIDATION_RECONNECT_DELAY;
+        loop {
+            match self.client.get_async_pubsub().await {
+                Ok(mut pubsub) => match pubsub.subscribe(Self::INVALIDATION_CHANNEL).await {
+                    Ok(()) => {
+                        tracing::info!("Subscribed to cache invalidations");
+                        local.clear();
+                        delay = Self::INVALIDATION_RECONNECT_DELAY;
+
+                        let mut messages = pubsub.on_message();
+                        while let Some(msg) = messages.next().await {
+                            match msg.get_payload::<String>() {
+                                Ok(short_code) => local.remove(&short_code),
+                                Err(e) => {
+                                    tracing::warn!("Invalid cache invalidation message: {}", e)
+                                }
+                            }
+                        }
+                        tracing::warn!("Cache invalidation subscription closed, reconnecting");
+                    }
+                    Err(e) => tracing::warn!("Failed to subscribe to cache invalidations: {}", e),
+                },
+                Err(e) => tracing::warn!("Failed to connect for cache invalidations: {}", e),
+            }
+
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(Self::INVALIDATION_MAX_RECONNECT_DELAY);
+        }
+    }
+
+    /// Count a redirect in the Redis-side click counters
+    ///
+    /// The counter hash and the pending-code set are updated in one transaction
+    /// so a concurrent [`RedisCache::take_click_counters`] never sees one without the other.
+    async fn increment_click_counter(
+        &self,
+        short_code: &str,
+        is_bot: bool,
+        clicked_at: DateTime<Utc>,
+    ) -> AppResult<()> {
+        let key = Self::click_counter_key(short_code);
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+
+        if is_bot {
+            pipe.hincr(&key, "bot_clicks", 1).ignore();
+        } else {
+            pipe.hincr(&key, "clicks", 1).ignore();
+            pipe.hset(&key, "last_clicked_at", clicked_at.timestamp_millis())
+                .ignore();
+        }
+        pipe.sadd(Self::PENDING_CLICKS_KEY, short_code).ignore();
+
+        let mut conn = self.conn().await?;
+        let _: () = self.timed(pipe.query_async(&mut *conn)).await?;
+
+        Ok(())
+    }
+
+    /// Atomically read and reset the counters of up to `limit` pending short codes
+    async fn take_click_counters(&self, limit: usize) -> AppResult<Vec<ClickCountDelta>> {
+        let mut conn = self.conn().await?;
+        let values: Vec<String> = self
+            .timed(
+                redis::Script::new(TAKE_CLICK_COUNTERS_SCRIPT)
+                    .key(Self::PENDING_CLICKS_KEY)
+                    .arg(limit)
+                    .arg(format!("{}:", Self::CLICK_COUNTER_PREFIX))
+                    .invoke_async(&mut *conn),
+            )
+            .await?;
+
+        Ok(values
+            .chunks_exact(4)
+            .map(|v| ClickCountDelta {
+                short_code: v[0].clone(),
+                clicks: v[1].parse().unwrap_or(0),
+                bot_clicks: v[2].parse().unwrap_or(0),
+                last_clicked_at: v[3].parse().ok().and_then(DateTime::from_timestamp_millis),
+            })
+            .filter(|d| d.clicks > 0 || d.bot_clicks > 0)
+            .collect())
+    }
+
+    /// Add counters back after a failed sync so they are applied on the next pass
+    async fn restore_click_counters(&self, deltas: &[ClickCountDelta]) -> AppResult<()> {
+        if deltas.is_empty() {
+            return Ok(());
+        }
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        for delta in deltas {
+            let key = Self::click_counter_key(&delta.short_code);
+            pipe.hincr(&key, "clicks", delta.clicks).ignore();
+            pipe.hincr(&key, "bot_clicks", delta.bot_clicks).ignore();
+            if let Some(last_clicked_at) = delta.last_clicked_at {
+                pipe.hset(&key, "last_clicked_at", last_clicked_at.timestamp_millis())
+                    .ignore();
+            }
+            pipe.sadd(Self::PENDING_CLICKS_KEY, &delta.short_code)
+                .ignore();
+        }
+
+        let mut conn = self.conn().await?;
+        let _: () = self.timed(pipe.query_async(&mut *conn)).await?;
+
+        Ok(())
+    }
+
+    /// Record a visitor in the daily HyperLogLogs for a URL, its owner and globally
+    async fn record_unique_visitor(
+        &self,
+        short_code: &str,
+        owner_id: Option<i64>,
+        visitor_hash: &str,
+        date: NaiveDate,
+        retention: Duration,
+    ) -> AppResult<()> {
+        let mut scopes = vec![UniqueScope::Url(short_code), UniqueScope::Global];
+        if let Some(owner_id) = owner_id {
+            scopes.push(UniqueScope::Owner(owner_id));
+        }
+
+        let ttl = retention.as_secs() as i64;
+        let mut pipe = redis::pipe();
+        for scope in &scopes {
+            let key = Self::unique_visitor_key(scope, date);
+            pipe.pfadd(&key, visitor_hash).ignore();
+            pipe.expire(&key, ttl).ignore();
+        }
+
+        let mut conn = self.conn().await?;
+        let _: () = self.timed(pipe.query_async(&mut *conn)).await?;
+
+        Ok(())
+    }
+
+    /// Approximate number of distinct visitors over `[from, to]` (inclusive days)
+    async fn count_unique_visitors(
+        &self,
+        scope: UniqueScope<'_>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> AppResult<i64> {
+        let keys: Vec<String> = from
+            .iter_days()
+            .take_while(|d| *d <= to)
+            .map(|d| Self::unique_visitor_key(&scope, d))
+            .collect();
+
+        if keys.is_empty() {
+            return Ok(0);
+        }
+
+        let mut conn = self.conn().await?;
+        let count: i64 = self.timed(conn.pfcount(keys)).await?;
+
+        Ok(count)
+    }
+
+    /// Approximate number of distinct visitors per day over `[from, to]` (inclusive days)
+    async fn count_daily_unique_visitors(
+        &self,
+        scope: UniqueScope<'_>,
+        from: NaiveDate,
+        to: NaiveDate,
+    ) -> AppResult<Vec<(NaiveDate, i64)>> {
+        let days: Vec<NaiveDate> = from.iter_days().take_while(|d| *d <= to).collect();
+
+        if days.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut pipe = redis::pipe();
+        for day in &days {
+            pipe.pfcount(Self::unique_visitor_key(&scope, *day));
+        }
+
+        let mut conn = self.conn().await?;
+        let counts: Vec<i64> = self.timed(pipe.query_async(&mut *conn)).await?;
+
+        Ok(days.into_iter().zip(counts).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::test_cached_url as cached_url;
+
+    const TEST_TIMEOUT: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn test_url_key_generation() {
+        assert_eq!(RedisCache::url_key("abc123"), "url:abc123");
+        assert_eq!(RedisCache::url_key("test"), "url:test");
+    }
+
+    #[tokio::test]
+    async fn test_local_tier_serves_without_redis() {
+        // Nothing listens on port 1, so every Redis call fails
+        let cache = RedisCache::new("redis://127.0.0.1:1", 1, 60, TEST_TIMEOUT)
+            .await
+            .unwrap()
+            .with_local_cache(100, Duration::from_secs(5));
+        let entry = cached_url(None);
+
+        assert!(cache.set_url(&entry).await.is_err());
+        assert_eq!(
+            cache.get_url("abc123").await.unwrap(),
+            CacheLookup::Hit(entry)
+        );
+        assert_eq!(cache.stats().local.hits(), 1);
+        assert_eq!(cache.stats().redis.hits() + cache.stats().redis.misses(), 0);
+
+        let _ = cache.delete_url("abc123").await;
+        assert_eq!(cache.get_url("abc123").await.unwrap(), CacheLookup::Miss);
+        assert_eq!(cache.stats().local.misses(), 1);
+        assert_eq!(cache.stats().redis.misses(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_circuit_opens_and_skips_redis() {
+        let cache = RedisCache::new("redis://127.0.0.1:1", 1, 60, TEST_TIMEOUT)
+            .await
+            .unwrap()
+            .with_circuit_breaker(2, Duration::from_secs(60));
+
+        assert_eq!(cache.get_url("abc123").await.unwrap(), CacheLookup::Miss);
+        assert_eq!(cache.circuit_state(), Some(CircuitState::Closed));
+        assert_eq!(cache.get_url("abc123").await.unwrap(), CacheLookup::Miss);
+        assert_eq!(cache.circuit_state(), Some(CircuitState::Open));
+
+        // Writes are skipped and other calls fail fast while the circuit is open
+        assert!(cache.set_url(&cached_url(None)).await.is_ok());
+        assert!(cache.delete_url("abc123").await.is_ok());
+        assert!(matches!(
+            cache.publish_invalidation("abc123").await,
+            Err(AppError::CacheUnavailable)
+        ));
+        assert_eq!(cache.get_url("abc123").await.unwrap(), CacheLookup::Miss);
+        assert_eq!(cache.stats().redis.misses(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_hanging_redis_opens_circuit() {
+        // Accepts connections but never answers, like a hung or blackholed Redis
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut sockets = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                sockets.push(socket);
+            }
+        });
+
+        let redis_url = format!("redis://{}", addr);
+        let cache = RedisCache::new(&redis_url, 1, 60, Duration::from_millis(100))
+            .await
+            .unwrap()
+            .with_circuit_breaker(2, Duration::from_secs(60));
+        let started = std::time::Instant::now();
+
+        assert_eq!(cache.get_url("abc123").await.unwrap(), CacheLookup::Miss);
+        assert_eq!(cache.circuit_state(), Some(CircuitState::Closed));
+        assert_eq!(cache.get_url("abc123").await.unwrap(), CacheLookup::Miss);
+        assert_eq!(cache.circuit_state(), Some(CircuitState::Open));
+
+        // Once open, Redis is skipped without waiting
+        assert!(cache.set_url(&cached_url(None)).await.is_ok());
+        assert_eq!(cache.get_url("abc123").await.unwrap(), CacheLookup::Miss);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn test_local_tier_disabled_by_zero_settings() {
+        let cache = RedisCache::new("redis://127.0.0.1:1", 1, 60, TEST_TIMEOUT)
+            .await
+            .unwrap();
+
+        assert!(cache
+            .clone()
+            .with_local_cache(0, Duration::from_secs(5))
+            .local
+            .is_none());
+        assert!(cache.with_local_cache(100, Duration::ZERO).local.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_should_refresh_early() {
+        let cache = RedisCache::new("redis://127.0.0.1:1", 1, 3600, TEST_TIMEOUT)
+            .await
+            .unwrap();
+        let entry = cached_url(None);
+        let window = Duration::from_secs(60);
+
+        assert!(!cache.should_refresh_early(&entry, Duration::from_secs(1), 0.99));
+
+        let cache = cache.with_early_refresh(window);
+        assert!(!cache.should_refresh_early(&entry, Duration::from_secs(120), 0.99));
+        assert!(!cache.should_refresh_early(&entry, Duration::from_secs(45), 0.5));
+        assert!(cache.should_refresh_early(&entry, Duration::from_secs(15), 0.5));
+        assert!(cache.should_refresh_early(&entry, Duration::ZERO, 0.0));
+
+        let expiring = cached_url(Some(Utc::now() + chrono::Duration::seconds(10)));
+        assert!(!cache.should_refresh_early(&expiring, Duration::from_secs(10), 0.99));
+    }
+
+    /// Repeated resolves of a hot link must be served from the cache.
+    ///
+    /// Needs a Redis server; set `REDIS_TEST_URL` (e.g. `redis://127.0.0.1:6379/15`) to run it.
+    #[tokio::test]
+    async fn test_repeated_resolves_hit_cache() {
+        let Ok(redis_url) = std::env::var("REDIS_TEST_URL") else {
+            return;
+        };
+
+        let cache = RedisCache::new(&redis_url, 4, 60, TEST_TIMEOUT)
+            .await
+            .unwrap();
+        let entry = CachedUrl {
+            short_code: format!("hit-ratio-{}", std::process::id()),
+            ..cached_url(None)
+        };
+
+        // First resolve misses and populates the cache, like `resolve_url` does
+        assert_eq!(
+            cache.get_url(&entry.short_code).await.unwrap(),
+            CacheLookup::Miss
+        );
+        cache.set_url(&entry).await.unwrap();
+
+        for _ in 0..1000 {
+            let cached = cache.get_url(&entry.short_code).await.unwrap();
+            assert_eq!(cached, CacheLookup::Hit(entry.clone()));
+        }
+
+        assert_eq!(cache.stats().redis.hits(), 1000);
+        assert_eq!(cache.stats().redis.misses(), 1);
+        assert!(cache.stats().redis.hit_ratio().unwrap() > 0.99);
+
+        cache.delete_url(&entry.short_code).await.unwrap();
+    }
+
+    /// Tombstones answer repeated lookups of unknown codes until the code is created.
+    ///
+    /// Needs a Redis server; set `REDIS_TEST_URL` to run it.
+    #[tokio::test]
+    async fn test_tombstone_replaced_by_created_url() {
+        let Ok(redis_url) = std::env::var("REDIS_TEST_URL") else {
+            return;
+        };
+
+        let cache = RedisCache::new(&redis_url, 4, 60, TEST_TIMEOUT)
+            .await
+            .unwrap()
+            .with_negative_ttl(Duration::from_secs(10));
+        let entry = CachedUrl {
+            short_code: format!("tombstone-{}", std::process::id()),
+            ..cached_url(None)
+        };
+
+        cache.set_missing(&entry.short_code).await.unwrap();
+        assert_eq!(
+            cache.get_url(&entry.short_code).await.unwrap(),
+            CacheLookup::Tombstone
+        );
+
+        cache.set_url(&entry).await.unwrap();
+        assert_eq!(
+            cache.get_url(&entry.short_code).await.unwrap(),
+            CacheLookup::Hit(entry.clone())
+        );
+
+        cache.delete_url(&entry.short_code).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tombstones_disabled_by_default() {
+        // No Redis call is made when negative caching is off
+        let cache = RedisCache::new("redis://127.0.0.1:1", 1, 60, TEST_TIMEOUT)
+            .await
+            .unwrap();
+        assert!(cache.set_missing("abc123").await.is_ok());
+    }
+
+    #[test]
+    fn test_click_counter_key_generation() {
+        assert_eq!(RedisCache::click_counter_key("abc123"), "clicks:abc123");
+        assert_ne!(
+            RedisCache::click_counter_key("pending"),
+            RedisCache::PENDING_CLICKS_KEY
+        );
+    }
+
+    #[test]
+    fn test_unique_visitor_key_generation() {
+        let date = NaiveDate::from_ymd_opt(2026, 1, 15).unwrap();
+
+        assert_eq!(
+            RedisCache::unique_visitor_key(&UniqueScope::Url("abc123"), date),
+            "uv:url:abc123:2026-01-15"
+        );
+        assert_eq!(
+            RedisCache::unique_visitor_key(&UniqueScope::Owner(7), date),
+            "uv:owner:7:2026-01-15"
+        );
+        assert_eq!(
+            RedisCache::unique_visitor_key(&UniqueScope::Global, date),
+            "uv:all:2026-01-15"
+        );
+    }
+}
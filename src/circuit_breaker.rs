@@ -0,0 +1,203 @@
+//! Circuit breaker guarding calls to an optional backend (Redis, database replicas).
+//!
+//! After `failure_threshold` consecutive failures the circuit opens and the
+//! backend is skipped entirely. Once `cooldown` has passed, a single caller is
+//! allowed to probe it; success closes the circuit, failure reopens it for
+//! another cool-down.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Externally visible breaker state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through
+    Closed,
+    /// The backend is skipped until the cool-down ends
+    Open,
+    /// A probe is checking whether the backend is back
+    HalfOpen,
+}
+
+impl CircuitState {
+    /// Lowercase name used in health output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::Open => "open",
+            CircuitState::HalfOpen => "half_open",
+        }
+    }
+}
+
+/// What a caller may do right now
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permit {
+    /// Call the backend normally
+    Allowed,
+    /// The cool-down has ended; this caller should probe the backend
+    Probe,
+    /// Skip the backend
+    Rejected,
+}
+
+#[derive(Debug)]
+enum Inner {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    HalfOpen,
+}
+
+/// Consecutive-failure circuit breaker
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    /// Backend name for logs
+    name: &'static str,
+    inner: Mutex<Inner>,
+    failure_threshold: u32,
+    cooldown: Duration,
+}
+
+impl CircuitBreaker {
+    /// Create a closed breaker
+    pub fn new(name: &'static str, failure_threshold: u32, cooldown: Duration) -> Self {
+        Self {
+            name,
+            inner: Mutex::new(Inner::Closed { failures: 0 }),
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+        }
+    }
+
+    /// Ask whether a call may be made
+    ///
+    /// At most one caller per cool-down gets [`Permit::Probe`].
+    pub fn acquire(&self) -> Permit {
+        let mut inner = self.lock();
+        match *inner {
+            Inner::Closed { .. } => Permit::Allowed,
+            Inner::Open { until } if Instant::now() >= until => {
+                *inner = Inner::HalfOpen;
+                Permit::Probe
+            }
+            Inner::Open { .. } | Inner::HalfOpen => Permit::Rejected,
+        }
+    }
+
+    /// Record a successful call
+    ///
+    /// A successful probe closes the circuit and a success while closed resets the
+    /// failure count. An open circuit stays open: only a probe may close it, not a
+    /// slow call that started before the circuit opened.
+    pub fn record_success(&self) {
+        let mut inner = self.lock();
+        if matches!(*inner, Inner::Closed { .. } | Inner::HalfOpen) {
+            *inner = Inner::Closed { failures: 0 };
+        }
+    }
+
+    /// Record a failed call, opening the circuit at the threshold
+    pub fn record_failure(&self) {
+        let mut inner = self.lock();
+        let failures = match *inner {
+            Inner::Closed { failures } => failures + 1,
+            // A failed probe (or straggler) reopens immediately
+            Inner::Open { .. } | Inner::HalfOpen => self.failure_threshold,
+        };
+
+        *inner = if failures >= self.failure_threshold {
+            if matches!(*inner, Inner::Closed { .. }) {
+                tracing::warn!(
+                    "{} circuit opened after {} consecutive failures",
+                    self.name,
+                    failures
+                );
+            }
+            Inner::Open {
+                until: Instant::now() + self.cooldown,
+            }
+        } else {
+            Inner::Closed { failures }
+        };
+    }
+
+    /// Current state
+    pub fn state(&self) -> CircuitState {
+        match *self.lock() {
+            Inner::Closed { .. } => CircuitState::Closed,
+            Inner::Open { .. } => CircuitState::Open,
+            Inner::HalfOpen => CircuitState::HalfOpen,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold() {
+        let breaker = CircuitBreaker::new("test", 3, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.acquire(), Permit::Allowed);
+
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_eq!(breaker.acquire(), Permit::Rejected);
+    }
+
+    #[test]
+    fn test_success_resets_failure_count() {
+        let breaker = CircuitBreaker::new("test", 2, Duration::from_secs(60));
+
+        breaker.record_failure();
+        breaker.record_success();
+        breaker.record_failure();
+
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn test_single_probe_after_cooldown() {
+        let breaker = CircuitBreaker::new("test", 1, Duration::ZERO);
+        breaker.record_failure();
+
+        assert_eq!(breaker.acquire(), Permit::Probe);
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert_eq!(breaker.acquire(), Permit::Rejected);
+
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.acquire(), Permit::Allowed);
+    }
+
+    #[test]
+    fn test_failed_probe_reopens() {
+        let breaker = CircuitBreaker::new("test", 5, Duration::from_secs(60));
+        *breaker.lock() = Inner::HalfOpen;
+
+        breaker.record_failure();
+
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_eq!(breaker.acquire(), Permit::Rejected);
+    }
+
+    #[test]
+    fn test_late_success_keeps_circuit_open() {
+        let breaker = CircuitBreaker::new("test", 1, Duration::from_secs(60));
+        breaker.record_failure();
+
+        // A call that started before the circuit opened succeeds afterwards
+        breaker.record_success();
+
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_eq!(breaker.acquire(), Permit::Rejected);
+    }
+}
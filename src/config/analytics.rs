@@ -0,0 +1,77 @@
+use serde::Deserialize;
+
+/// Click analytics configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnalyticsConfig {
+    /// Whether per-click events (referrer, user agent, hashed IP) are recorded
+    pub click_events_enabled: bool,
+
+    /// Salt mixed into client IPs before hashing so raw addresses are never stored
+    pub ip_hash_salt: String,
+
+    /// Request header carrying the client's ISO country code (set by a CDN or proxy)
+    pub country_header: String,
+
+    /// Whether bot/crawler hits are excluded from click counts
+    pub bot_filter_enabled: bool,
+
+    /// Extra case-insensitive user-agent substrings to treat as bots
+    pub bot_user_agent_patterns: Vec<String>,
+
+    /// Whether unique visitors are tracked in Redis HyperLogLogs
+    pub unique_visitors_enabled: bool,
+
+    /// Number of days daily unique-visitor keys are kept (also the window reported by info/stats)
+    pub unique_visitor_retention_days: i64,
+}
+
+impl AnalyticsConfig {
+    /// Validate analytics configuration values
+    pub fn validate(&self) -> Result<(), String> {
+        // Both hash client IPs with the salt
+        if (self.click_events_enabled || self.unique_visitors_enabled)
+            && self.ip_hash_salt.len() < 16
+        {
+            return Err("ANALYTICS_IP_SALT must be set to at least 16 characters".to_string());
+        }
+
+        if self.unique_visitor_retention_days < 1 || self.unique_visitor_retention_days > 400 {
+            return Err("UNIQUE_VISITOR_RETENTION_DAYS must be between 1 and 400".to_string());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn analytics_config() -> AnalyticsConfig {
+        AnalyticsConfig {
+            click_events_enabled: true,
+            ip_hash_salt: String::new(),
+            country_header: "cf-ipcountry".to_string(),
+            bot_filter_enabled: true,
+            bot_user_agent_patterns: Vec::new(),
+            unique_visitors_enabled: true,
+            unique_visitor_retention_days: 90,
+        }
+    }
+
+    #[test]
+    fn test_ip_hash_salt_required_when_hashing() {
+        let mut config = analytics_config();
+        assert!(config.validate().is_err());
+
+        config.click_events_enabled = false;
+        assert!(config.validate().is_err());
+
+        config.unique_visitors_enabled = false;
+        assert!(config.validate().is_ok());
+
+        config.click_events_enabled = true;
+        config.ip_hash_salt = "a-separate-secret-salt".to_string();
+        assert!(config.validate().is_ok());
+    }
+}
@@ -1,8 +1,49 @@
 use serde::Deserialize;
+use std::str::FromStr;
 
-/// Redis cache configuration
+/// Which cache backend serves URL lookups
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CacheBackendKind {
+    /// Shared Redis cache, with an optional in-process tier in front of it
+    #[default]
+    Redis,
+    /// In-process cache only; not shared between instances
+    Memory,
+    /// No caching; every lookup goes to the database
+    None,
+}
+
+impl CacheBackendKind {
+    /// Lowercase name used in configuration and health output
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CacheBackendKind::Redis => "redis",
+            CacheBackendKind::Memory => "memory",
+            CacheBackendKind::None => "none",
+        }
+    }
+}
+
+impl FromStr for CacheBackendKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "redis" => Ok(CacheBackendKind::Redis),
+            "memory" => Ok(CacheBackendKind::Memory),
+            "none" => Ok(CacheBackendKind::None),
+            other => Err(format!("unknown cache backend '{}'", other)),
+        }
+    }
+}
+
+/// Cache configuration
 #[derive(Debug, Clone, Deserialize)]
 pub struct CacheConfig {
+    /// Cache backend to use
+    pub backend: CacheBackendKind,
+
     /// Redis connection URL
     pub url: String,
 
@@ -11,6 +52,29 @@ pub struct CacheConfig {
 
     /// Default TTL for cached entries in seconds
     pub default_ttl_seconds: u64,
+
+    /// Maximum entries in the in-process cache (in front of Redis, or the whole
+    /// `memory` backend); 0 disables the tier in front of Redis
+    pub local_max_entries: usize,
+
+    /// TTL for in-process entries in seconds; bounds staleness across instances
+    pub local_ttl_seconds: u64,
+
+    /// TTL for tombstones of unknown or expired short codes in seconds (0 disables them)
+    pub negative_ttl_seconds: u64,
+
+    /// Window before TTL expiry in which entries may be refreshed early, in seconds (0 disables it)
+    pub early_refresh_seconds: u64,
+
+    /// Consecutive Redis failures that open the circuit breaker
+    pub breaker_failure_threshold: u32,
+
+    /// How long Redis is skipped once the circuit opens, in seconds
+    pub breaker_cooldown_seconds: u64,
+
+    /// How long a Redis command (or getting a connection) may take before it
+    /// counts as a failure, in milliseconds
+    pub command_timeout_ms: u64,
 }
 
 impl CacheConfig {
@@ -20,6 +84,50 @@ impl CacheConfig {
             return Err("CACHE_DEFAULT_TTL_SECONDS must be greater than 0".to_string());
         }
 
+        if self.local_ttl_seconds > self.default_ttl_seconds {
+            return Err(
+                "CACHE_LOCAL_TTL_SECONDS must not exceed CACHE_DEFAULT_TTL_SECONDS".to_string(),
+            );
+        }
+
+        if self.negative_ttl_seconds > self.default_ttl_seconds {
+            return Err(
+                "CACHE_NEGATIVE_TTL_SECONDS must not exceed CACHE_DEFAULT_TTL_SECONDS".to_string(),
+            );
+        }
+
+        if self.early_refresh_seconds >= self.default_ttl_seconds {
+            return Err(
+                "CACHE_EARLY_REFRESH_SECONDS must be less than CACHE_DEFAULT_TTL_SECONDS"
+                    .to_string(),
+            );
+        }
+
+        if self.breaker_failure_threshold == 0 {
+            return Err("CACHE_BREAKER_FAILURE_THRESHOLD must be greater than 0".to_string());
+        }
+
+        if self.breaker_cooldown_seconds == 0 {
+            return Err("CACHE_BREAKER_COOLDOWN_SECONDS must be greater than 0".to_string());
+        }
+
+        if self.command_timeout_ms == 0 {
+            return Err("CACHE_COMMAND_TIMEOUT_MS must be greater than 0".to_string());
+        }
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_backend_parsing() {
+        assert_eq!("redis".parse(), Ok(CacheBackendKind::Redis));
+        assert_eq!(" Memory ".parse(), Ok(CacheBackendKind::Memory));
+        assert_eq!("none".parse(), Ok(CacheBackendKind::None));
+        assert!("memcached".parse::<CacheBackendKind>().is_err());
+    }
+}
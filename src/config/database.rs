@@ -1,9 +1,40 @@
 use serde::Deserialize;
 
+/// Storage backend, chosen by the scheme of `DATABASE_URL`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DatabaseBackend {
+    /// `postgres://` or `postgresql://`
+    Postgres,
+    /// `sqlite:` (a file path, or `sqlite::memory:`)
+    Sqlite,
+    /// `memory:` (nothing persisted; for tests and demos)
+    Memory,
+}
+
+impl DatabaseBackend {
+    /// Detect the backend from a connection URL
+    pub fn from_url(url: &str) -> Result<Self, String> {
+        let scheme = url
+            .split(':')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        match scheme.as_str() {
+            "postgres" | "postgresql" => Ok(DatabaseBackend::Postgres),
+            "sqlite" => Ok(DatabaseBackend::Sqlite),
+            "memory" => Ok(DatabaseBackend::Memory),
+            _ => Err(
+                "DATABASE_URL must start with postgres://, postgresql://, sqlite: or memory:"
+                    .to_string(),
+            ),
+        }
+    }
+}
+
 /// Database configuration
 #[derive(Debug, Clone, Deserialize)]
 pub struct DatabaseConfig {
-    /// PostgreSQL connection URL
+    /// Database connection URL; the scheme selects PostgreSQL or SQLite
     pub url: String,
 
     /// Maximum number of database connections in the pool
@@ -14,11 +45,30 @@ pub struct DatabaseConfig {
 
     /// Timeout in seconds for acquiring a connection from the pool
     pub acquire_timeout_seconds: u64,
+
+    /// PostgreSQL read replicas for redirect lookups, listings and stats (empty: none)
+    pub replica_urls: Vec<String>,
+
+    /// Seconds after a write during which reads of that link (or owner) use the primary
+    pub read_your_writes_seconds: u64,
+
+    /// Consecutive failures before a replica is skipped
+    pub replica_failure_threshold: u32,
+
+    /// Seconds a failing replica is skipped before it is tried again
+    pub replica_cooldown_seconds: u64,
 }
 
 impl DatabaseConfig {
+    /// Storage backend selected by the URL scheme
+    pub fn backend(&self) -> Result<DatabaseBackend, String> {
+        DatabaseBackend::from_url(&self.url)
+    }
+
     /// Validate database configuration values
     pub fn validate(&self) -> Result<(), String> {
+        let backend = self.backend()?;
+
         if self.min_connections > self.max_connections {
             return Err("DB_MIN_CONNECTIONS cannot be greater than DB_MAX_CONNECTIONS".to_string());
         }
@@ -27,6 +77,76 @@ impl DatabaseConfig {
             return Err("DB_ACQUIRE_TIMEOUT_SECONDS must be greater than 0".to_string());
         }
 
+        if !self.replica_urls.is_empty() {
+            if backend != DatabaseBackend::Postgres {
+                return Err("DATABASE_REPLICA_URLS requires a PostgreSQL DATABASE_URL".to_string());
+            }
+
+            if self
+                .replica_urls
+                .iter()
+                .any(|url| DatabaseBackend::from_url(url) != Ok(DatabaseBackend::Postgres))
+            {
+                return Err("DATABASE_REPLICA_URLS must be postgres:// URLs".to_string());
+            }
+        }
+
+        if self.replica_failure_threshold == 0 {
+            return Err("DB_REPLICA_FAILURE_THRESHOLD must be greater than 0".to_string());
+        }
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backend_from_url() {
+        assert_eq!(
+            DatabaseBackend::from_url("postgresql://user@localhost/rustlink"),
+            Ok(DatabaseBackend::Postgres)
+        );
+        assert_eq!(
+            DatabaseBackend::from_url("postgres://localhost/rustlink"),
+            Ok(DatabaseBackend::Postgres)
+        );
+        assert_eq!(
+            DatabaseBackend::from_url("sqlite://rustlink.db"),
+            Ok(DatabaseBackend::Sqlite)
+        );
+        assert_eq!(
+            DatabaseBackend::from_url("sqlite::memory:"),
+            Ok(DatabaseBackend::Sqlite)
+        );
+        assert_eq!(
+            DatabaseBackend::from_url("memory:"),
+            Ok(DatabaseBackend::Memory)
+        );
+        assert!(DatabaseBackend::from_url("mysql://localhost/rustlink").is_err());
+    }
+
+    #[test]
+    fn test_replicas_require_postgres() {
+        let mut config = DatabaseConfig {
+            url: "postgresql://primary/rustlink".to_string(),
+            max_connections: 10,
+            min_connections: 1,
+            acquire_timeout_seconds: 30,
+            replica_urls: vec!["postgresql://replica/rustlink".to_string()],
+            read_your_writes_seconds: 5,
+            replica_failure_threshold: 3,
+            replica_cooldown_seconds: 30,
+        };
+        assert!(config.validate().is_ok());
+
+        config.replica_urls.push("sqlite://replica.db".to_string());
+        assert!(config.validate().is_err());
+
+        config.url = "sqlite://rustlink.db".to_string();
+        config.replica_urls.truncate(1);
+        assert!(config.validate().is_err());
+    }
+}
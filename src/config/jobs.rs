@@ -0,0 +1,34 @@
+use serde::Deserialize;
+
+/// Background job worker configuration
+#[derive(Debug, Clone, Deserialize)]
+pub struct JobsConfig {
+    /// How often coalesced click counts are flushed to the database (milliseconds)
+    pub click_flush_interval_ms: u64,
+
+    /// Distinct short codes with pending clicks (or click events) that trigger an early flush
+    pub click_batch_size: usize,
+
+    /// How often the short code pool is checked against its low-water mark (milliseconds)
+    pub code_pool_refill_interval_ms: u64,
+}
+
+impl JobsConfig {
+    /// Validate worker configuration values
+    pub fn validate(&self) -> Result<(), String> {
+        if self.click_flush_interval_ms == 0 {
+            return Err("CLICK_FLUSH_INTERVAL_MS must be greater than 0".to_string());
+        }
+
+        // Each row binds 4 parameters and Postgres allows at most 65535 per statement
+        if self.click_batch_size == 0 || self.click_batch_size > 10_000 {
+            return Err("CLICK_BATCH_SIZE must be between 1 and 10000".to_string());
+        }
+
+        if self.code_pool_refill_interval_ms == 0 {
+            return Err("CODE_POOL_REFILL_INTERVAL_MS must be greater than 0".to_string());
+        }
+
+        Ok(())
+    }
+}
@@ -2,22 +2,26 @@ use crate::error::{AppError, AppResult};
 use serde::Deserialize;
 use std::env;
 
+pub mod analytics;
 pub mod auth;
 pub mod cache;
 pub mod cors;
 pub mod database;
+pub mod jobs;
 pub mod rate_limit;
 pub mod server;
 pub mod url;
 
 // Re-export all configuration types
+pub use analytics::AnalyticsConfig;
 pub use auth::AuthConfig;
-pub use cache::CacheConfig;
+pub use cache::{CacheBackendKind, CacheConfig};
 pub use cors::CorsConfig;
-pub use database::DatabaseConfig;
+pub use database::{DatabaseBackend, DatabaseConfig};
+pub use jobs::JobsConfig;
 pub use rate_limit::RateLimitConfig;
 pub use server::ServerConfig;
-pub use url::UrlConfig;
+pub use url::{ClickCounterMode, ShortCodeAlphabet, ShortCodeStrategy, UrlConfig};
 
 /// Unified configuration struct containing all application settings
 #[derive(Debug, Clone, Deserialize)]
@@ -42,6 +46,12 @@ pub struct Config {
 
     /// CORS configuration
     pub cors: CorsConfig,
+
+    /// Click analytics configuration
+    pub analytics: AnalyticsConfig,
+
+    /// Background job worker configuration
+    pub jobs: JobsConfig,
 }
 
 impl Config {
@@ -71,7 +81,44 @@ impl Config {
             .map_err(|_| {
                 AppError::Configuration("Invalid DB_ACQUIRE_TIMEOUT_SECONDS".to_string())
             })?;
+        let db_replica_urls: Vec<String> = env::var("DATABASE_REPLICA_URLS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let db_read_your_writes = env::var("DB_READ_YOUR_WRITES_SECONDS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .map_err(|_| {
+                AppError::Configuration("Invalid DB_READ_YOUR_WRITES_SECONDS".to_string())
+            })?;
+        let db_replica_failure_threshold = env::var("DB_REPLICA_FAILURE_THRESHOLD")
+            .unwrap_or_else(|_| "3".to_string())
+            .parse()
+            .map_err(|_| {
+                AppError::Configuration("Invalid DB_REPLICA_FAILURE_THRESHOLD".to_string())
+            })?;
+        let db_replica_cooldown = env::var("DB_REPLICA_COOLDOWN_SECONDS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()
+            .map_err(|_| {
+                AppError::Configuration("Invalid DB_REPLICA_COOLDOWN_SECONDS".to_string())
+            })?;
 
+        // CACHE_ENABLED=false is kept as an alias for CACHE_BACKEND=none
+        let cache_enabled: bool = env::var("CACHE_ENABLED")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid CACHE_ENABLED".to_string()))?;
+        let cache_backend = if cache_enabled {
+            env::var("CACHE_BACKEND")
+                .unwrap_or_else(|_| "redis".to_string())
+                .parse()
+                .map_err(|_| AppError::Configuration("Invalid CACHE_BACKEND".to_string()))?
+        } else {
+            CacheBackendKind::None
+        };
         let redis_url =
             env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
         let cache_max_connections = env::var("CACHE_MAX_CONNECTIONS")
@@ -84,6 +131,42 @@ impl Config {
             .map_err(|_| {
                 AppError::Configuration("Invalid CACHE_DEFAULT_TTL_SECONDS".to_string())
             })?;
+        let cache_local_max_entries = env::var("CACHE_LOCAL_MAX_ENTRIES")
+            .unwrap_or_else(|_| "10000".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid CACHE_LOCAL_MAX_ENTRIES".to_string()))?;
+        let cache_local_ttl = env::var("CACHE_LOCAL_TTL_SECONDS")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid CACHE_LOCAL_TTL_SECONDS".to_string()))?;
+        let cache_negative_ttl = env::var("CACHE_NEGATIVE_TTL_SECONDS")
+            .unwrap_or_else(|_| "60".to_string())
+            .parse()
+            .map_err(|_| {
+                AppError::Configuration("Invalid CACHE_NEGATIVE_TTL_SECONDS".to_string())
+            })?;
+        let cache_early_refresh = env::var("CACHE_EARLY_REFRESH_SECONDS")
+            .unwrap_or_else(|_| "0".to_string())
+            .parse()
+            .map_err(|_| {
+                AppError::Configuration("Invalid CACHE_EARLY_REFRESH_SECONDS".to_string())
+            })?;
+        let cache_breaker_threshold = env::var("CACHE_BREAKER_FAILURE_THRESHOLD")
+            .unwrap_or_else(|_| "5".to_string())
+            .parse()
+            .map_err(|_| {
+                AppError::Configuration("Invalid CACHE_BREAKER_FAILURE_THRESHOLD".to_string())
+            })?;
+        let cache_breaker_cooldown = env::var("CACHE_BREAKER_COOLDOWN_SECONDS")
+            .unwrap_or_else(|_| "10".to_string())
+            .parse()
+            .map_err(|_| {
+                AppError::Configuration("Invalid CACHE_BREAKER_COOLDOWN_SECONDS".to_string())
+            })?;
+        let cache_command_timeout = env::var("CACHE_COMMAND_TIMEOUT_MS")
+            .unwrap_or_else(|_| "500".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid CACHE_COMMAND_TIMEOUT_MS".to_string()))?;
 
         let short_code_length = env::var("SHORT_CODE_LENGTH")
             .unwrap_or_else(|_| "8".to_string())
@@ -99,14 +182,55 @@ impl Config {
             .unwrap_or_else(|_| "10".to_string())
             .parse()
             .map_err(|_| AppError::Configuration("Invalid SHORT_CODE_MAX_ATTEMPTS".to_string()))?;
-        let cache_enabled = env::var("CACHE_ENABLED")
+        let short_code_strategy = env::var("SHORT_CODE_STRATEGY")
+            .unwrap_or_else(|_| "random".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid SHORT_CODE_STRATEGY".to_string()))?;
+        let short_code_salt = env::var("SHORT_CODE_SALT").unwrap_or_default();
+        let short_code_pool_size = env::var("SHORT_CODE_POOL_SIZE")
+            .unwrap_or_else(|_| "10000".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid SHORT_CODE_POOL_SIZE".to_string()))?;
+        let short_code_pool_low_water = env::var("SHORT_CODE_POOL_LOW_WATER")
+            .unwrap_or_else(|_| "2000".to_string())
+            .parse()
+            .map_err(|_| {
+                AppError::Configuration("Invalid SHORT_CODE_POOL_LOW_WATER".to_string())
+            })?;
+        let short_code_alphabet = env::var("SHORT_CODE_ALPHABET")
+            .unwrap_or_else(|_| "base62".to_string())
+            .parse()
+            .map_err(|e| AppError::Configuration(format!("Invalid SHORT_CODE_ALPHABET: {}", e)))?;
+        let short_code_lowercase = env::var("SHORT_CODE_LOWERCASE")
+            .unwrap_or_else(|_| "false".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid SHORT_CODE_LOWERCASE".to_string()))?;
+        let short_code_word_filter_enabled = env::var("SHORT_CODE_WORD_FILTER_ENABLED")
             .unwrap_or_else(|_| "true".to_string())
             .parse()
-            .map_err(|_| AppError::Configuration("Invalid CACHE_ENABLED".to_string()))?;
+            .map_err(|_| {
+                AppError::Configuration("Invalid SHORT_CODE_WORD_FILTER_ENABLED".to_string())
+            })?;
+        let short_code_blocked_words: Vec<String> = env::var("SHORT_CODE_BLOCKED_WORDS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let reserved_codes: Vec<String> = env::var("RESERVED_CODES")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
         let strict_url_validation = env::var("STRICT_URL_VALIDATION")
             .unwrap_or_else(|_| "true".to_string())
             .parse()
             .map_err(|_| AppError::Configuration("Invalid STRICT_URL_VALIDATION".to_string()))?;
+        let click_counter_mode = env::var("CLICK_COUNTER_MODE")
+            .unwrap_or_else(|_| "worker".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid CLICK_COUNTER_MODE".to_string()))?;
 
         // Authentication config
         let jwt_secret = env::var("JWT_SECRET")
@@ -126,6 +250,52 @@ impl Config {
             .parse()
             .map_err(|_| AppError::Configuration("Invalid RATE_LIMIT_BURST".to_string()))?;
 
+        // Analytics config
+        let click_events_enabled = env::var("CLICK_EVENTS_ENABLED")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid CLICK_EVENTS_ENABLED".to_string()))?;
+        let ip_hash_salt = env::var("ANALYTICS_IP_SALT").unwrap_or_default();
+        let country_header = env::var("ANALYTICS_COUNTRY_HEADER")
+            .unwrap_or_else(|_| "cf-ipcountry".to_string())
+            .to_lowercase();
+        let bot_filter_enabled = env::var("BOT_FILTER_ENABLED")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid BOT_FILTER_ENABLED".to_string()))?;
+        let bot_user_agent_patterns: Vec<String> = env::var("BOT_USER_AGENT_PATTERNS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        let unique_visitors_enabled = env::var("UNIQUE_VISITORS_ENABLED")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid UNIQUE_VISITORS_ENABLED".to_string()))?;
+        let unique_visitor_retention_days = env::var("UNIQUE_VISITOR_RETENTION_DAYS")
+            .unwrap_or_else(|_| "90".to_string())
+            .parse()
+            .map_err(|_| {
+                AppError::Configuration("Invalid UNIQUE_VISITOR_RETENTION_DAYS".to_string())
+            })?;
+
+        // Background job config
+        let click_flush_interval_ms = env::var("CLICK_FLUSH_INTERVAL_MS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid CLICK_FLUSH_INTERVAL_MS".to_string()))?;
+        let click_batch_size = env::var("CLICK_BATCH_SIZE")
+            .unwrap_or_else(|_| "500".to_string())
+            .parse()
+            .map_err(|_| AppError::Configuration("Invalid CLICK_BATCH_SIZE".to_string()))?;
+        let code_pool_refill_interval_ms = env::var("CODE_POOL_REFILL_INTERVAL_MS")
+            .unwrap_or_else(|_| "1000".to_string())
+            .parse()
+            .map_err(|_| {
+                AppError::Configuration("Invalid CODE_POOL_REFILL_INTERVAL_MS".to_string())
+            })?;
+
         // CORS config
         let allowed_origins_str = env::var("ALLOWED_ORIGINS").unwrap_or_else(|_| "*".to_string());
         let allowed_origins: Vec<String> = if allowed_origins_str == "*" {
@@ -147,19 +317,40 @@ impl Config {
                 max_connections: db_max_connections,
                 min_connections: db_min_connections,
                 acquire_timeout_seconds: db_acquire_timeout,
+                replica_urls: db_replica_urls,
+                read_your_writes_seconds: db_read_your_writes,
+                replica_failure_threshold: db_replica_failure_threshold,
+                replica_cooldown_seconds: db_replica_cooldown,
             },
             cache: CacheConfig {
+                backend: cache_backend,
                 url: redis_url,
                 max_connections: cache_max_connections,
                 default_ttl_seconds: cache_default_ttl,
+                local_max_entries: cache_local_max_entries,
+                local_ttl_seconds: cache_local_ttl,
+                negative_ttl_seconds: cache_negative_ttl,
+                early_refresh_seconds: cache_early_refresh,
+                breaker_failure_threshold: cache_breaker_threshold,
+                breaker_cooldown_seconds: cache_breaker_cooldown,
+                command_timeout_ms: cache_command_timeout,
             },
             url: UrlConfig {
                 short_code_length,
                 base_url,
                 default_expiry_hours,
                 short_code_max_attempts,
-                cache_enabled,
+                short_code_strategy,
+                short_code_salt,
+                short_code_pool_size,
+                short_code_pool_low_water,
+                short_code_alphabet,
+                short_code_lowercase,
+                short_code_word_filter_enabled,
+                short_code_blocked_words,
+                reserved_codes,
                 strict_url_validation,
+                click_counter_mode,
             },
             auth: AuthConfig {
                 jwt_secret,
@@ -170,6 +361,20 @@ impl Config {
                 burst_size,
             },
             cors: CorsConfig { allowed_origins },
+            analytics: AnalyticsConfig {
+                click_events_enabled,
+                ip_hash_salt,
+                country_header,
+                bot_filter_enabled,
+                bot_user_agent_patterns,
+                unique_visitors_enabled,
+                unique_visitor_retention_days,
+            },
+            jobs: JobsConfig {
+                click_flush_interval_ms,
+                click_batch_size,
+                code_pool_refill_interval_ms,
+            },
         };
 
         // Validate configuration
@@ -186,6 +391,23 @@ impl Config {
         self.url.validate().map_err(AppError::Configuration)?;
         self.auth.validate().map_err(AppError::Configuration)?;
         self.rate_limit.validate().map_err(AppError::Configuration)?;
+        self.analytics.validate().map_err(AppError::Configuration)?;
+        self.jobs.validate().map_err(AppError::Configuration)?;
+
+        // A salt that is also the signing key would leak into hashes anyone can brute-force
+        if self.analytics.ip_hash_salt == self.auth.jwt_secret {
+            return Err(AppError::Configuration(
+                "ANALYTICS_IP_SALT must not reuse JWT_SECRET".to_string(),
+            ));
+        }
+
+        if self.url.click_counter_mode == ClickCounterMode::Redis
+            && self.cache.backend != CacheBackendKind::Redis
+        {
+            return Err(AppError::Configuration(
+                "CLICK_COUNTER_MODE=redis requires CACHE_BACKEND=redis".to_string(),
+            ));
+        }
 
         Ok(())
     }
@@ -207,19 +429,40 @@ mod tests {
                 max_connections: 10,
                 min_connections: 1,
                 acquire_timeout_seconds: 30,
+                replica_urls: Vec::new(),
+                read_your_writes_seconds: 5,
+                replica_failure_threshold: 3,
+                replica_cooldown_seconds: 30,
             },
             cache: CacheConfig {
+                backend: CacheBackendKind::Redis,
                 url: "redis://127.0.0.1".to_string(),
                 max_connections: 10,
                 default_ttl_seconds: 3600,
+                local_max_entries: 10000,
+                local_ttl_seconds: 5,
+                negative_ttl_seconds: 60,
+                early_refresh_seconds: 0,
+                breaker_failure_threshold: 5,
+                breaker_cooldown_seconds: 10,
+                command_timeout_ms: 500,
             },
             url: UrlConfig {
                 short_code_length: 8,
                 base_url: "http://localhost:3000".to_string(),
                 default_expiry_hours: 720,
                 short_code_max_attempts: 10,
-                cache_enabled: true,
+                short_code_strategy: ShortCodeStrategy::Random,
+                short_code_salt: String::new(),
+                short_code_pool_size: 10000,
+                short_code_pool_low_water: 2000,
+                short_code_alphabet: ShortCodeAlphabet::Base62,
+                short_code_lowercase: false,
+                short_code_word_filter_enabled: true,
+                short_code_blocked_words: Vec::new(),
+                reserved_codes: Vec::new(),
                 strict_url_validation: true,
+                click_counter_mode: ClickCounterMode::Worker,
             },
             auth: AuthConfig {
                 jwt_secret: "test_secret".to_string(),
@@ -232,6 +475,20 @@ mod tests {
             cors: CorsConfig {
                 allowed_origins: vec!["*".to_string()],
             },
+            analytics: AnalyticsConfig {
+                click_events_enabled: true,
+                ip_hash_salt: "test_salt_value_1234".to_string(),
+                country_header: "cf-ipcountry".to_string(),
+                bot_filter_enabled: true,
+                bot_user_agent_patterns: Vec::new(),
+                unique_visitors_enabled: true,
+                unique_visitor_retention_days: 90,
+            },
+            jobs: JobsConfig {
+                click_flush_interval_ms: 1000,
+                click_batch_size: 500,
+                code_pool_refill_interval_ms: 1000,
+            },
         };
 
         assert_eq!(config.server.port, 3000);
@@ -1,4 +1,125 @@
 use serde::Deserialize;
+use std::str::FromStr;
+
+/// Where redirect click counts are accumulated before reaching Postgres
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClickCounterMode {
+    /// Coalesce increments in the in-process background worker
+    #[default]
+    Worker,
+    /// `INCR` per-code counters in Redis and sync them to Postgres periodically
+    Redis,
+}
+
+impl FromStr for ClickCounterMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "worker" => Ok(ClickCounterMode::Worker),
+            "redis" => Ok(ClickCounterMode::Redis),
+            other => Err(format!("unknown click counter mode '{}'", other)),
+        }
+    }
+}
+
+/// How short codes are generated when no custom code is given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShortCodeStrategy {
+    /// Random codes, retried while they collide with existing ones
+    #[default]
+    Random,
+    /// Codes derived from a database sequence, so they never collide
+    Sequence,
+    /// Codes claimed from a pre-generated pool that is refilled in the background
+    Pool,
+}
+
+impl FromStr for ShortCodeStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "random" => Ok(ShortCodeStrategy::Random),
+            "sequence" => Ok(ShortCodeStrategy::Sequence),
+            "pool" => Ok(ShortCodeStrategy::Pool),
+            other => Err(format!("unknown short code strategy '{}'", other)),
+        }
+    }
+}
+
+/// Characters that are easily confused with one another in print
+const AMBIGUOUS_CHARS: &[char] = &['0', 'O', 'o', '1', 'I', 'l'];
+
+/// Characters generated short codes are made of
+#[derive(Debug, Clone, PartialEq, Eq, Default, Deserialize)]
+#[serde(try_from = "String")]
+pub enum ShortCodeAlphabet {
+    /// `0-9`, `A-Z` and `a-z`
+    #[default]
+    Base62,
+    /// Base62 without `0`, `O`, `o`, `1`, `I` and `l`
+    Unambiguous,
+    /// An explicit set of URL-safe characters
+    Custom(String),
+}
+
+impl ShortCodeAlphabet {
+    /// Resolve the alphabet to its characters, folded to lowercase if requested
+    pub fn chars(&self, lowercase: bool) -> Vec<char> {
+        let base: Vec<char> = match self {
+            ShortCodeAlphabet::Base62 | ShortCodeAlphabet::Unambiguous => {
+                ('0'..='9').chain('A'..='Z').chain('a'..='z').collect()
+            }
+            ShortCodeAlphabet::Custom(chars) => chars.chars().collect(),
+        };
+
+        let mut chars: Vec<char> = Vec::with_capacity(base.len());
+        for c in base {
+            let c = if lowercase { c.to_ascii_lowercase() } else { c };
+            if !chars.contains(&c) {
+                chars.push(c);
+            }
+        }
+
+        // Filter after folding, so lowercase mode also drops `l` from `L`
+        if *self == ShortCodeAlphabet::Unambiguous {
+            chars.retain(|c| !AMBIGUOUS_CHARS.contains(c));
+        }
+        chars
+    }
+}
+
+impl FromStr for ShortCodeAlphabet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.to_ascii_lowercase().as_str() {
+            "" | "base62" => Ok(ShortCodeAlphabet::Base62),
+            "unambiguous" => Ok(ShortCodeAlphabet::Unambiguous),
+            _ => {
+                if let Some(c) = s
+                    .chars()
+                    .find(|c| !c.is_ascii_alphanumeric() && *c != '-' && *c != '_')
+                {
+                    return Err(format!("alphabet character '{}' is not URL-safe", c));
+                }
+                Ok(ShortCodeAlphabet::Custom(s.to_string()))
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for ShortCodeAlphabet {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
 
 /// URL shortening configuration
 #[derive(Debug, Clone, Deserialize)]
@@ -15,11 +136,38 @@ pub struct UrlConfig {
     /// Maximum number of attempts to generate a unique short code
     pub short_code_max_attempts: u32,
 
-    /// Whether caching is enabled for URL lookups
-    pub cache_enabled: bool,
+    /// How short codes are generated
+    pub short_code_strategy: ShortCodeStrategy,
+
+    /// Secret that shuffles sequence-derived codes; changing it changes every future code
+    pub short_code_salt: String,
+
+    /// Number of unused codes the pool is refilled up to
+    pub short_code_pool_size: usize,
+
+    /// Pool size below which the background task refills it
+    pub short_code_pool_low_water: usize,
+
+    /// Characters generated short codes are made of
+    pub short_code_alphabet: ShortCodeAlphabet,
+
+    /// Generate lowercase-only codes (the alphabet is folded to lowercase)
+    pub short_code_lowercase: bool,
+
+    /// Whether generated codes are checked against the built-in blocked word list
+    pub short_code_word_filter_enabled: bool,
+
+    /// Additional substrings generated codes must not contain (case-insensitive)
+    pub short_code_blocked_words: Vec<String>,
+
+    /// Codes that can't be used for links, on top of route names and CLI reservations
+    pub reserved_codes: Vec<String>,
 
     /// Whether strict URL validation is enabled (requires http:// or https://)
     pub strict_url_validation: bool,
+
+    /// How redirect click counts are accumulated
+    pub click_counter_mode: ClickCounterMode,
 }
 
 impl UrlConfig {
@@ -37,6 +185,122 @@ impl UrlConfig {
             return Err("SHORT_CODE_MAX_ATTEMPTS must be between 1 and 100".to_string());
         }
 
+        // Without a secret salt, anyone with the source can decode codes back to sequence values
+        if self.short_code_strategy == ShortCodeStrategy::Sequence
+            && self.short_code_salt.len() < 16
+        {
+            return Err(
+                "SHORT_CODE_SALT must be at least 16 characters with SHORT_CODE_STRATEGY=sequence"
+                    .to_string(),
+            );
+        }
+
+        if self.short_code_pool_size == 0 || self.short_code_pool_size > 1_000_000 {
+            return Err("SHORT_CODE_POOL_SIZE must be between 1 and 1000000".to_string());
+        }
+
+        if self
+            .short_code_alphabet
+            .chars(self.short_code_lowercase)
+            .len()
+            < 16
+        {
+            return Err(
+                "SHORT_CODE_ALPHABET must have at least 16 distinct characters".to_string(),
+            );
+        }
+
+        if self.short_code_pool_low_water >= self.short_code_pool_size {
+            return Err(
+                "SHORT_CODE_POOL_LOW_WATER must be less than SHORT_CODE_POOL_SIZE".to_string(),
+            );
+        }
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url_config() -> UrlConfig {
+        UrlConfig {
+            short_code_length: 8,
+            base_url: "http://localhost:3000".to_string(),
+            default_expiry_hours: 720,
+            short_code_max_attempts: 10,
+            short_code_strategy: ShortCodeStrategy::Random,
+            short_code_salt: String::new(),
+            short_code_pool_size: 10000,
+            short_code_pool_low_water: 2000,
+            short_code_alphabet: ShortCodeAlphabet::Base62,
+            short_code_lowercase: false,
+            short_code_word_filter_enabled: true,
+            short_code_blocked_words: Vec::new(),
+            reserved_codes: Vec::new(),
+            strict_url_validation: true,
+            click_counter_mode: ClickCounterMode::Worker,
+        }
+    }
+
+    #[test]
+    fn test_sequence_strategy_requires_salt() {
+        let mut config = url_config();
+        assert!(config.validate().is_ok());
+
+        config.short_code_strategy = ShortCodeStrategy::Sequence;
+        assert!(config.validate().is_err());
+        config.short_code_salt = "short".to_string();
+        assert!(config.validate().is_err());
+        config.short_code_salt = "a-long-secret-salt".to_string();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_click_counter_mode_parsing() {
+        assert_eq!("worker".parse(), Ok(ClickCounterMode::Worker));
+        assert_eq!(" Redis ".parse(), Ok(ClickCounterMode::Redis));
+        assert!("kafka".parse::<ClickCounterMode>().is_err());
+    }
+
+    #[test]
+    fn test_short_code_strategy_parsing() {
+        assert_eq!("random".parse(), Ok(ShortCodeStrategy::Random));
+        assert_eq!(" Sequence ".parse(), Ok(ShortCodeStrategy::Sequence));
+        assert_eq!("POOL".parse(), Ok(ShortCodeStrategy::Pool));
+        assert!("uuid".parse::<ShortCodeStrategy>().is_err());
+    }
+
+    #[test]
+    fn test_alphabet_presets() {
+        assert_eq!(ShortCodeAlphabet::Base62.chars(false).len(), 62);
+        assert_eq!(ShortCodeAlphabet::Base62.chars(true).len(), 36);
+
+        let unambiguous = ShortCodeAlphabet::Unambiguous.chars(false);
+        assert_eq!(unambiguous.len(), 56);
+        assert!(!unambiguous.contains(&'O') && unambiguous.contains(&'L'));
+
+        // Folding `L` to lowercase must not bring back `l`
+        let lowercase = ShortCodeAlphabet::Unambiguous.chars(true);
+        assert_eq!(lowercase.len(), 32);
+        assert!(lowercase
+            .iter()
+            .all(|c| !c.is_ascii_uppercase() && *c != 'l'));
+    }
+
+    #[test]
+    fn test_alphabet_parsing() {
+        assert_eq!("Unambiguous".parse(), Ok(ShortCodeAlphabet::Unambiguous));
+        assert_eq!("base62".parse(), Ok(ShortCodeAlphabet::Base62));
+        assert_eq!(
+            "abcdef0123456789".parse(),
+            Ok(ShortCodeAlphabet::Custom("abcdef0123456789".to_string()))
+        );
+        assert!("abc/def".parse::<ShortCodeAlphabet>().is_err());
+        assert_eq!(
+            ShortCodeAlphabet::Custom("aAbB".to_string()).chars(true),
+            vec!['a', 'b']
+        );
+    }
+}
@@ -0,0 +1,473 @@
+//! Filtering, sorting and keyset pagination for URL listings.
+//!
+//! Pages are ordered by a sort key plus the URL id as a tie-breaker, and the
+//! next page starts strictly after the last row of the previous one. The
+//! position is handed to clients as an opaque cursor, so deep pages cost the
+//! same as the first and rows inserted meanwhile don't shift results.
+
+use crate::models::UrlEntry;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{Database, Encode, QueryBuilder, Type};
+use std::cmp::Ordering;
+
+/// Expiry state of a URL at query time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlStatus {
+    /// No expiry, or an expiry in the future
+    Active,
+    /// Expiry in the past
+    Expired,
+}
+
+/// Column a listing is ordered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlSort {
+    #[default]
+    CreatedAt,
+    ClickCount,
+    /// Never-clicked URLs sort as the oldest
+    LastClickedAt,
+}
+
+/// Sort direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Conditions a listed URL must meet (all optional)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UrlFilter {
+    /// Only URLs created by this user
+    pub owner_id: Option<i64>,
+    pub status: Option<UrlStatus>,
+    /// Created at or after this time
+    pub created_from: Option<DateTime<Utc>>,
+    /// Created before this time
+    pub created_to: Option<DateTime<Utc>>,
+    /// Destination host, or any subdomain of it (lowercase)
+    pub domain: Option<String>,
+    /// Case-insensitive substring of the short code or destination
+    pub search: Option<String>,
+}
+
+impl UrlFilter {
+    /// Regex matching destinations on `domain` or its subdomains
+    ///
+    /// Written so PostgreSQL (`~`), SQLite (`REGEXP`) and the `regex` crate
+    /// all accept it. `domain` must be a plain hostname (see [`is_valid_domain`]).
+    pub fn domain_pattern(&self) -> Option<String> {
+        self.domain.as_ref().map(|domain| {
+            format!(
+                "(?i)^[a-z][a-z0-9+.-]*://([^/?#@]*@)?([^/?#@:]*\\.)?{}(:[0-9]+)?([/?#]|$)",
+                domain.replace('.', "\\.")
+            )
+        })
+    }
+
+    /// `LIKE` pattern for the search term, with wildcards escaped by `\`
+    pub fn search_pattern(&self) -> Option<String> {
+        self.search.as_ref().map(|search| {
+            let escaped = search
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            format!("%{}%", escaped.to_lowercase())
+        })
+    }
+
+    /// Whether an entry passes the filter (for backends that filter in Rust)
+    pub fn matches(&self, entry: &UrlEntry, now: DateTime<Utc>) -> bool {
+        if self.owner_id.is_some() && entry.owner_id != self.owner_id {
+            return false;
+        }
+
+        let expired = entry.expires_at.is_some_and(|t| t <= now);
+        match self.status {
+            Some(UrlStatus::Active) if expired => return false,
+            Some(UrlStatus::Expired) if !expired => return false,
+            _ => {}
+        }
+
+        if self
+            .created_from
+            .is_some_and(|from| entry.created_at < from)
+            || self.created_to.is_some_and(|to| entry.created_at >= to)
+        {
+            return false;
+        }
+
+        if let Some(search) = &self.search {
+            let search = search.to_lowercase();
+            if !entry.short_code.to_lowercase().contains(&search)
+                && !entry.original_url.to_lowercase().contains(&search)
+            {
+                return false;
+            }
+        }
+
+        match self.domain_pattern() {
+            Some(pattern) => regex::Regex::new(&pattern)
+                .map(|re| re.is_match(&entry.original_url))
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+/// Whether `domain` is a hostname usable as a listing filter
+pub fn is_valid_domain(domain: &str) -> bool {
+    !domain.is_empty()
+        && domain.len() <= 253
+        && domain.split('.').all(|label| {
+            !label.is_empty() && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+}
+
+/// Sort key value of one row
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SortKey {
+    Count(i64),
+    /// `None` (never clicked) orders before every time
+    Time(Option<DateTime<Utc>>),
+}
+
+impl SortKey {
+    /// Key of `entry` under `sort`
+    pub fn of(entry: &UrlEntry, sort: UrlSort) -> Self {
+        match sort {
+            UrlSort::CreatedAt => SortKey::Time(Some(entry.created_at)),
+            UrlSort::ClickCount => SortKey::Count(entry.click_count),
+            UrlSort::LastClickedAt => SortKey::Time(entry.last_clicked_at),
+        }
+    }
+}
+
+/// Position after the last row of a page
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UrlCursor {
+    pub sort: UrlSort,
+    pub order: SortOrder,
+    pub key: SortKey,
+    pub id: i64,
+}
+
+impl UrlCursor {
+    /// Cursor positioned after `entry`
+    pub fn after(entry: &UrlEntry, sort: UrlSort, order: SortOrder) -> Self {
+        Self {
+            sort,
+            order,
+            key: SortKey::of(entry, sort),
+            id: entry.id,
+        }
+    }
+
+    /// Opaque string form handed to clients
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).unwrap_or_default();
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Parse a cursor produced by [`UrlCursor::encode`]
+    pub fn decode(cursor: &str) -> Result<Self, String> {
+        URL_SAFE_NO_PAD
+            .decode(cursor)
+            .ok()
+            .and_then(|json| serde_json::from_slice::<Self>(&json).ok())
+            .filter(|cursor| {
+                matches!(
+                    (cursor.sort, cursor.key),
+                    (UrlSort::CreatedAt, SortKey::Time(Some(_)))
+                        | (UrlSort::ClickCount, SortKey::Count(_))
+                        | (UrlSort::LastClickedAt, SortKey::Time(_))
+                )
+            })
+            .ok_or_else(|| "Invalid cursor".to_string())
+    }
+}
+
+/// One page of a URL listing
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UrlListQuery {
+    pub filter: UrlFilter,
+    pub sort: UrlSort,
+    pub order: SortOrder,
+    /// Start after this row; takes precedence over `offset`
+    pub after: Option<UrlCursor>,
+    /// Rows to skip when no cursor is given (kept for older clients)
+    pub offset: i64,
+    pub limit: i64,
+}
+
+impl UrlListQuery {
+    /// Order rows the way the SQL backends do
+    pub fn compare(&self, a: &UrlEntry, b: &UrlEntry) -> Ordering {
+        let ordering = SortKey::of(a, self.sort)
+            .cmp(&SortKey::of(b, self.sort))
+            .then(a.id.cmp(&b.id));
+        match self.order {
+            SortOrder::Asc => ordering,
+            SortOrder::Desc => ordering.reverse(),
+        }
+    }
+
+    /// Whether `entry` comes after the cursor
+    pub fn is_after_cursor(&self, entry: &UrlEntry) -> bool {
+        let Some(cursor) = &self.after else {
+            return true;
+        };
+        let ordering = SortKey::of(entry, self.sort)
+            .cmp(&cursor.key)
+            .then(entry.id.cmp(&cursor.id));
+        match self.order {
+            SortOrder::Asc => ordering == Ordering::Greater,
+            SortOrder::Desc => ordering == Ordering::Less,
+        }
+    }
+}
+
+/// Append the `WHERE` clause for `filter` to a query on `urls`
+///
+/// `regex_op` is the dialect's regex match operator (`~` or `REGEXP`).
+pub(crate) fn push_filter<'a, DB>(
+    builder: &mut QueryBuilder<'a, DB>,
+    filter: &UrlFilter,
+    regex_op: &str,
+    now: DateTime<Utc>,
+) where
+    DB: Database,
+    i64: Encode<'a, DB> + Type<DB>,
+    String: Encode<'a, DB> + Type<DB>,
+    DateTime<Utc>: Encode<'a, DB> + Type<DB>,
+{
+    builder.push(" WHERE TRUE");
+
+    if let Some(owner_id) = filter.owner_id {
+        builder.push(" AND owner_id = ").push_bind(owner_id);
+    }
+    match filter.status {
+        Some(UrlStatus::Active) => {
+            builder
+                .push(" AND (expires_at IS NULL OR expires_at > ")
+                .push_bind(now)
+                .push(")");
+        }
+        Some(UrlStatus::Expired) => {
+            builder.push(" AND expires_at <= ").push_bind(now);
+        }
+        None => {}
+    }
+    if let Some(from) = filter.created_from {
+        builder.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = filter.created_to {
+        builder.push(" AND created_at < ").push_bind(to);
+    }
+    if let Some(pattern) = filter.domain_pattern() {
+        builder
+            .push(format!(" AND original_url {} ", regex_op))
+            .push_bind(pattern);
+    }
+    if let Some(pattern) = filter.search_pattern() {
+        builder
+            .push(" AND (LOWER(short_code) LIKE ")
+            .push_bind(pattern.clone())
+            .push(" ESCAPE '\\' OR LOWER(original_url) LIKE ")
+            .push_bind(pattern)
+            .push(" ESCAPE '\\')");
+    }
+}
+
+/// Append filter, keyset condition, ordering and limit for one page
+pub(crate) fn push_page<'a, DB>(
+    builder: &mut QueryBuilder<'a, DB>,
+    query: &UrlListQuery,
+    regex_op: &str,
+    now: DateTime<Utc>,
+) where
+    DB: Database,
+    i64: Encode<'a, DB> + Type<DB>,
+    String: Encode<'a, DB> + Type<DB>,
+    DateTime<Utc>: Encode<'a, DB> + Type<DB>,
+{
+    push_filter(builder, &query.filter, regex_op, now);
+
+    let column = match query.sort {
+        UrlSort::CreatedAt => "created_at",
+        UrlSort::ClickCount => "click_count",
+        UrlSort::LastClickedAt => "last_clicked_at",
+    };
+    let (op, direction, nulls) = match query.order {
+        SortOrder::Asc => (">", "ASC", "NULLS FIRST"),
+        SortOrder::Desc => ("<", "DESC", "NULLS LAST"),
+    };
+
+    // Rows strictly after the cursor; NULL keys (never clicked) sort lowest
+    if let Some(cursor) = &query.after {
+        match (cursor.key, query.order) {
+            (SortKey::Count(key), _) => {
+                builder.push(format!(" AND ({}, id) {} (", column, op));
+                builder
+                    .push_bind(key)
+                    .push(", ")
+                    .push_bind(cursor.id)
+                    .push(")");
+            }
+            (SortKey::Time(Some(key)), order) => {
+                builder.push(format!(" AND (({}, id) {} (", column, op));
+                builder
+                    .push_bind(key)
+                    .push(", ")
+                    .push_bind(cursor.id)
+                    .push(")");
+                if order == SortOrder::Desc {
+                    builder.push(format!(" OR {} IS NULL", column));
+                }
+                builder.push(")");
+            }
+            (SortKey::Time(None), SortOrder::Desc) => {
+                builder.push(format!(" AND {} IS NULL AND id < ", column));
+                builder.push_bind(cursor.id);
+            }
+            (SortKey::Time(None), SortOrder::Asc) => {
+                builder.push(format!(" AND ({} IS NOT NULL OR id > ", column));
+                builder.push_bind(cursor.id).push(")");
+            }
+        }
+    }
+
+    builder.push(format!(
+        " ORDER BY {} {} {}, id {} LIMIT ",
+        column, direction, nulls, direction
+    ));
+    builder.push_bind(query.limit);
+    if query.after.is_none() && query.offset > 0 {
+        builder.push(" OFFSET ").push_bind(query.offset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(original_url: &str) -> UrlEntry {
+        UrlEntry {
+            id: 1,
+            short_code: "Abc123".to_string(),
+            original_url: original_url.to_string(),
+            created_at: Utc::now(),
+            expires_at: None,
+            click_count: 0,
+            last_clicked_at: None,
+            bot_click_count: 0,
+            owner_id: Some(7),
+        }
+    }
+
+    #[test]
+    fn test_domain_filter() {
+        let filter = UrlFilter {
+            domain: Some("example.com".to_string()),
+            ..UrlFilter::default()
+        };
+        let now = Utc::now();
+
+        for url in [
+            "https://example.com",
+            "https://EXAMPLE.com/path",
+            "http://www.example.com:8080/x?y",
+            "https://user@docs.example.com#top",
+        ] {
+            assert!(filter.matches(&entry(url), now), "{} should match", url);
+        }
+        for url in [
+            "https://notexample.com/",
+            "https://example.com.evil.net/",
+            "https://evil.net/example.com",
+            "https://evil.net/?next=https://example.com",
+        ] {
+            assert!(
+                !filter.matches(&entry(url), now),
+                "{} should not match",
+                url
+            );
+        }
+
+        assert!(is_valid_domain("sub.example.co.uk"));
+        assert!(!is_valid_domain("example..com"));
+        assert!(!is_valid_domain("exa(mple).com"));
+    }
+
+    #[test]
+    fn test_search_and_status_filters() {
+        let now = Utc::now();
+        let mut url = entry("https://example.com/Spring_Sale");
+        let search = |term: &str| UrlFilter {
+            search: Some(term.to_string()),
+            ..UrlFilter::default()
+        };
+
+        assert!(search("spring_sale").matches(&url, now));
+        assert!(search("abc1").matches(&url, now));
+        assert!(!search("winter").matches(&url, now));
+        assert_eq!(
+            search("50%_off").search_pattern().as_deref(),
+            Some("%50\\%\\_off%")
+        );
+
+        url.expires_at = Some(now - chrono::Duration::hours(1));
+        let status = |status| UrlFilter {
+            status: Some(status),
+            ..UrlFilter::default()
+        };
+        assert!(status(UrlStatus::Expired).matches(&url, now));
+        assert!(!status(UrlStatus::Active).matches(&url, now));
+    }
+
+    #[test]
+    fn test_cursor_round_trip() {
+        let mut url = entry("https://example.com");
+        url.id = 42;
+
+        for sort in [
+            UrlSort::CreatedAt,
+            UrlSort::ClickCount,
+            UrlSort::LastClickedAt,
+        ] {
+            let cursor = UrlCursor::after(&url, sort, SortOrder::Desc);
+            assert_eq!(UrlCursor::decode(&cursor.encode()), Ok(cursor));
+        }
+        assert!(UrlCursor::decode("not a cursor").is_err());
+    }
+
+    #[test]
+    fn test_is_after_cursor() {
+        let mut older = entry("https://example.com");
+        older.id = 1;
+        older.created_at = Utc::now() - chrono::Duration::hours(1);
+        let mut newer = entry("https://example.com");
+        newer.id = 2;
+
+        let query = UrlListQuery {
+            after: Some(UrlCursor::after(
+                &newer,
+                UrlSort::CreatedAt,
+                SortOrder::Desc,
+            )),
+            ..UrlListQuery::default()
+        };
+        assert!(query.is_after_cursor(&older));
+        assert!(!query.is_after_cursor(&newer));
+        assert_eq!(query.compare(&newer, &older), Ordering::Less);
+    }
+}
@@ -0,0 +1,492 @@
+//! In-memory storage backend.
+//!
+//! Keeps everything in process memory behind a mutex, so handler-level tests
+//! can run the full router without a database. Nothing is persisted.
+
+use super::{bucket_clicks, ClickCountDelta, Stats, Storage, UrlFilter, UrlListQuery};
+use crate::analytics::{ClickDimension, ClickEvent, StatsInterval};
+use crate::error::{AppError, AppResult};
+use crate::middleware::User;
+use crate::models::UrlEntry;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{Mutex, MutexGuard};
+
+#[derive(Default)]
+struct Tables {
+    urls: HashMap<String, UrlEntry>,
+    users: Vec<User>,
+    click_events: Vec<ClickEvent>,
+    next_url_id: i64,
+    short_code_sequence: i64,
+    code_pool: BTreeSet<String>,
+    reserved_codes: BTreeSet<String>,
+}
+
+/// Storage kept entirely in process memory
+#[derive(Default)]
+pub struct MemoryStorage {
+    tables: Mutex<Tables>,
+}
+
+impl MemoryStorage {
+    fn tables(&self) -> MutexGuard<'_, Tables> {
+        self.tables.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Statistics over the URLs matching `filter`
+    fn stats(&self, filter: impl Fn(&UrlEntry) -> bool) -> Stats {
+        let now = Utc::now();
+        let tables = self.tables();
+        let urls: Vec<&UrlEntry> = tables.urls.values().filter(|u| filter(u)).collect();
+        let expired = urls
+            .iter()
+            .filter(|u| u.expires_at.is_some_and(|t| t <= now))
+            .count() as i64;
+
+        Stats {
+            total_urls: urls.len() as i64,
+            total_clicks: urls.iter().map(|u| u.click_count).sum(),
+            bot_clicks: urls.iter().map(|u| u.bot_click_count).sum(),
+            active_urls: urls.len() as i64 - expired,
+            expired_urls: expired,
+        }
+    }
+
+    /// Remove a URL and its click events if `filter` accepts it
+    fn remove_url(&self, short_code: &str, filter: impl Fn(&UrlEntry) -> bool) -> bool {
+        let mut tables = self.tables();
+        let Some(id) = tables
+            .urls
+            .get(short_code)
+            .filter(|u| filter(u))
+            .map(|u| u.id)
+        else {
+            return false;
+        };
+        tables.urls.remove(short_code);
+        tables.click_events.retain(|e| e.url_id != id);
+        true
+    }
+}
+
+/// Value of the grouped click event column, as the SQL backends would return it
+fn dimension_value(event: &ClickEvent, dimension: ClickDimension) -> Option<String> {
+    match dimension {
+        ClickDimension::Referrer => event.referrer.clone(),
+        ClickDimension::Country => event.country.clone(),
+        ClickDimension::Browser => event.browser_family.clone(),
+        ClickDimension::Os => event.os_family.clone(),
+        ClickDimension::Device => event.device_class.map(|d| d.as_str().to_string()),
+    }
+}
+
+#[async_trait]
+impl Storage for MemoryStorage {
+    fn backend(&self) -> &'static str {
+        "memory"
+    }
+
+    async fn ping(&self) -> AppResult<()> {
+        Ok(())
+    }
+
+    /// Nothing to migrate
+    async fn run_migrations(&self) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn create_url(
+        &self,
+        short_code: &str,
+        original_url: &str,
+        expires_at: Option<DateTime<Utc>>,
+        owner_id: Option<i64>,
+    ) -> AppResult<UrlEntry> {
+        let mut tables = self.tables();
+        if tables.urls.contains_key(short_code) {
+            return Err(AppError::ShortCodeExists(short_code.to_string()));
+        }
+
+        tables.next_url_id += 1;
+        let entry = UrlEntry {
+            id: tables.next_url_id,
+            short_code: short_code.to_string(),
+            original_url: original_url.to_string(),
+            created_at: Utc::now(),
+            expires_at,
+            click_count: 0,
+            last_clicked_at: None,
+            bot_click_count: 0,
+            owner_id,
+        };
+        tables.urls.insert(entry.short_code.clone(), entry.clone());
+
+        Ok(entry)
+    }
+
+    async fn get_url_by_short_code(&self, short_code: &str) -> AppResult<Option<UrlEntry>> {
+        Ok(self.tables().urls.get(short_code).cloned())
+    }
+
+    async fn apply_click_counts(&self, deltas: &[ClickCountDelta]) -> AppResult<u64> {
+        let mut tables = self.tables();
+        let mut updated = 0;
+        for delta in deltas {
+            if let Some(entry) = tables.urls.get_mut(&delta.short_code) {
+                entry.click_count += delta.clicks;
+                entry.bot_click_count += delta.bot_clicks;
+                entry.last_clicked_at = entry.last_clicked_at.max(delta.last_clicked_at);
+                updated += 1;
+            }
+        }
+
+        Ok(updated)
+    }
+
+    async fn insert_click_events(&self, events: &[ClickEvent]) -> AppResult<u64> {
+        let mut tables = self.tables();
+        let known: HashSet<i64> = tables.urls.values().map(|u| u.id).collect();
+        let before = tables.click_events.len();
+        tables
+            .click_events
+            .extend(events.iter().filter(|e| known.contains(&e.url_id)).cloned());
+
+        Ok((tables.click_events.len() - before) as u64)
+    }
+
+    async fn get_click_timeseries(
+        &self,
+        url_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        interval: StatsInterval,
+    ) -> AppResult<Vec<(DateTime<Utc>, i64)>> {
+        let tables = self.tables();
+        let clicks = tables
+            .click_events
+            .iter()
+            .filter(|e| e.url_id == url_id && !e.is_bot)
+            .map(|e| e.clicked_at);
+
+        Ok(bucket_clicks(clicks, from, to, interval))
+    }
+
+    async fn get_top_click_values(
+        &self,
+        url_id: i64,
+        dimension: ClickDimension,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<(Option<String>, i64)>> {
+        let mut counts: HashMap<Option<String>, i64> = HashMap::new();
+        for event in self.tables().click_events.iter().filter(|e| {
+            e.url_id == url_id && !e.is_bot && e.clicked_at >= from && e.clicked_at < to
+        }) {
+            *counts.entry(dimension_value(event, dimension)).or_default() += 1;
+        }
+
+        // Most clicks first, then by value with missing values last
+        let mut counts: Vec<(Option<String>, i64)> = counts.into_iter().collect();
+        counts.sort_by(|(a, a_clicks), (b, b_clicks)| {
+            b_clicks
+                .cmp(a_clicks)
+                .then(a.is_none().cmp(&b.is_none()))
+                .then(a.cmp(b))
+        });
+        counts.truncate(limit.max(0) as usize);
+
+        Ok(counts)
+    }
+
+    async fn count_bot_clicks(
+        &self,
+        url_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> AppResult<i64> {
+        let count = self
+            .tables()
+            .click_events
+            .iter()
+            .filter(|e| e.url_id == url_id && e.is_bot && e.clicked_at >= from && e.clicked_at < to)
+            .count();
+
+        Ok(count as i64)
+    }
+
+    async fn short_code_exists(&self, short_code: &str) -> AppResult<bool> {
+        Ok(self.tables().urls.contains_key(short_code))
+    }
+
+    async fn existing_short_codes(&self, short_codes: &[String]) -> AppResult<Vec<String>> {
+        let tables = self.tables();
+        Ok(short_codes
+            .iter()
+            .filter(|code| tables.urls.contains_key(code.as_str()))
+            .cloned()
+            .collect())
+    }
+
+    async fn next_short_code_sequence(&self) -> AppResult<i64> {
+        let mut tables = self.tables();
+        tables.short_code_sequence += 1;
+        Ok(tables.short_code_sequence)
+    }
+
+    async fn claim_pooled_code(&self) -> AppResult<Option<String>> {
+        Ok(self.tables().code_pool.pop_first())
+    }
+
+    async fn add_pooled_codes(&self, codes: &[String]) -> AppResult<u64> {
+        let mut tables = self.tables();
+        let mut added = 0;
+        for code in codes {
+            if !tables.urls.contains_key(code) && tables.code_pool.insert(code.clone()) {
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    async fn count_pooled_codes(&self) -> AppResult<i64> {
+        Ok(self.tables().code_pool.len() as i64)
+    }
+
+    async fn is_code_reserved(&self, code: &str) -> AppResult<bool> {
+        Ok(self.tables().reserved_codes.contains(code))
+    }
+
+    async fn reserve_code(&self, code: &str) -> AppResult<bool> {
+        Ok(self.tables().reserved_codes.insert(code.to_string()))
+    }
+
+    async fn release_reserved_code(&self, code: &str) -> AppResult<bool> {
+        Ok(self.tables().reserved_codes.remove(code))
+    }
+
+    async fn list_reserved_codes(&self) -> AppResult<Vec<String>> {
+        Ok(self.tables().reserved_codes.iter().cloned().collect())
+    }
+
+    async fn delete_url(&self, short_code: &str) -> AppResult<bool> {
+        Ok(self.remove_url(short_code, |_| true))
+    }
+
+    async fn delete_url_for_owner(&self, short_code: &str, owner_id: i64) -> AppResult<bool> {
+        Ok(self.remove_url(short_code, |u| u.owner_id == Some(owner_id)))
+    }
+
+    async fn update_url(
+        &self,
+        short_code: &str,
+        original_url: Option<&str>,
+        expires_at: Option<Option<DateTime<Utc>>>,
+    ) -> AppResult<Option<UrlEntry>> {
+        let mut tables = self.tables();
+        let Some(entry) = tables.urls.get_mut(short_code) else {
+            return Ok(None);
+        };
+        if let Some(original_url) = original_url {
+            entry.original_url = original_url.to_string();
+        }
+        if let Some(expires_at) = expires_at {
+            entry.expires_at = expires_at;
+        }
+        Ok(Some(entry.clone()))
+    }
+
+    async fn delete_expired_urls(&self) -> AppResult<u64> {
+        let now = Utc::now();
+        let expired: Vec<String> = self
+            .tables()
+            .urls
+            .values()
+            .filter(|u| u.expires_at.is_some_and(|t| t < now))
+            .map(|u| u.short_code.clone())
+            .collect();
+
+        for short_code in &expired {
+            self.remove_url(short_code, |_| true);
+        }
+
+        Ok(expired.len() as u64)
+    }
+
+    async fn get_stats(&self) -> AppResult<Stats> {
+        Ok(self.stats(|_| true))
+    }
+
+    async fn get_stats_for_owner(&self, owner_id: i64) -> AppResult<Stats> {
+        Ok(self.stats(|u| u.owner_id == Some(owner_id)))
+    }
+
+    async fn list_urls(&self, query: &UrlListQuery) -> AppResult<Vec<UrlEntry>> {
+        let now = Utc::now();
+        let mut urls: Vec<UrlEntry> = self
+            .tables()
+            .urls
+            .values()
+            .filter(|u| query.filter.matches(u, now) && query.is_after_cursor(u))
+            .cloned()
+            .collect();
+        urls.sort_by(|a, b| query.compare(a, b));
+
+        let offset = if query.after.is_none() {
+            query.offset
+        } else {
+            0
+        };
+        Ok(urls
+            .into_iter()
+            .skip(offset.max(0) as usize)
+            .take(query.limit.max(0) as usize)
+            .collect())
+    }
+
+    async fn count_urls(&self, filter: &UrlFilter) -> AppResult<i64> {
+        let now = Utc::now();
+        let count = self
+            .tables()
+            .urls
+            .values()
+            .filter(|u| filter.matches(u, now))
+            .count();
+
+        Ok(count as i64)
+    }
+
+    async fn create_user(&self, username: &str, password_hash: &str) -> AppResult<User> {
+        let mut tables = self.tables();
+        if tables.users.iter().any(|u| u.username == username) {
+            return Err(AppError::Internal(format!(
+                "User {} already exists",
+                username
+            )));
+        }
+
+        let user = User {
+            id: tables.users.len() as i64 + 1,
+            username: username.to_string(),
+            password_hash: password_hash.to_string(),
+            is_active: true,
+            is_superuser: false,
+        };
+        tables.users.push(user.clone());
+
+        Ok(user)
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> AppResult<Option<User>> {
+        Ok(self
+            .tables()
+            .users
+            .iter()
+            .find(|u| u.username == username)
+            .cloned())
+    }
+
+    async fn get_user_by_id(&self, user_id: i64) -> AppResult<Option<User>> {
+        Ok(self
+            .tables()
+            .users
+            .iter()
+            .find(|u| u.id == user_id)
+            .cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_url_lifecycle() {
+        let storage = MemoryStorage::default();
+        let user = storage.create_user("alice", "hash").await.unwrap();
+
+        let entry = storage
+            .create_url("abc123", "https://example.com", None, Some(user.id))
+            .await
+            .unwrap();
+        assert!(storage.short_code_exists("abc123").await.unwrap());
+        assert!(matches!(
+            storage
+                .create_url("abc123", "https://other.example", None, None)
+                .await,
+            Err(AppError::ShortCodeExists(_))
+        ));
+
+        let delta = ClickCountDelta {
+            short_code: "abc123".to_string(),
+            clicks: 2,
+            bot_clicks: 1,
+            last_clicked_at: Some(Utc::now()),
+        };
+        assert_eq!(storage.apply_click_counts(&[delta]).await.unwrap(), 1);
+        let stats = storage.get_stats_for_owner(user.id).await.unwrap();
+        assert_eq!(
+            (stats.total_urls, stats.total_clicks, stats.bot_clicks),
+            (1, 2, 1)
+        );
+
+        let event = ClickEvent::new(entry.id, None, None, None, None, false);
+        assert_eq!(
+            storage
+                .insert_click_events(std::slice::from_ref(&event))
+                .await
+                .unwrap(),
+            1
+        );
+        assert!(!storage
+            .delete_url_for_owner("abc123", user.id + 1)
+            .await
+            .unwrap());
+        assert!(storage
+            .delete_url_for_owner("abc123", user.id)
+            .await
+            .unwrap());
+        assert!(storage.tables().click_events.is_empty());
+
+        // Events that arrive after their URL was deleted are skipped
+        assert_eq!(storage.insert_click_events(&[event]).await.unwrap(), 0);
+        assert!(storage.tables().click_events.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_top_click_values_order() {
+        let storage = MemoryStorage::default();
+        let entry = storage
+            .create_url("abc123", "https://example.com", None, None)
+            .await
+            .unwrap();
+        let now = Utc::now();
+
+        for country in [Some("US"), Some("US"), None, Some("DE"), Some("FR")] {
+            let country = country.map(str::to_string);
+            let event = ClickEvent::new(entry.id, None, None, None, country, false);
+            storage.insert_click_events(&[event]).await.unwrap();
+        }
+
+        let top = storage
+            .get_top_click_values(
+                entry.id,
+                ClickDimension::Country,
+                now - chrono::Duration::minutes(1),
+                now + chrono::Duration::minutes(1),
+                3,
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            top,
+            vec![
+                (Some("US".to_string()), 2),
+                (Some("DE".to_string()), 1),
+                (Some("FR".to_string()), 1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,331 @@
+//! Persistent storage for URLs, click events and users.
+//!
+//! Handlers talk to a [`Storage`] trait object; the backend is picked from the
+//! `DATABASE_URL` scheme:
+//! - `postgres://` / `postgresql://`: PostgreSQL ([`PostgresStorage`])
+//! - `sqlite:`: an embedded SQLite file ([`SqliteStorage`]), for single-binary deployments
+//! - `memory:`: process memory ([`MemoryStorage`]), for tests and throwaway demos
+//!
+//! The SQL backends have their own migration sets (`migrations/` and `migrations/sqlite/`).
+//! With `DATABASE_REPLICA_URLS` set, PostgreSQL reads are spread over replicas
+//! by [`ReplicatedStorage`].
+
+mod list;
+mod memory;
+mod postgres;
+mod replicated;
+mod sqlite;
+
+use crate::analytics::{ClickDimension, ClickEvent, StatsInterval};
+use crate::config::{DatabaseBackend, DatabaseConfig};
+use crate::error::{AppError, AppResult};
+use crate::middleware::User;
+use crate::models::UrlEntry;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use list::{
+    is_valid_domain, SortKey, SortOrder, UrlCursor, UrlFilter, UrlListQuery, UrlSort, UrlStatus,
+};
+pub use memory::MemoryStorage;
+pub use postgres::PostgresStorage;
+pub use replicated::ReplicatedStorage;
+pub use sqlite::SqliteStorage;
+
+/// Shared handle to the configured storage backend
+pub type Repository = Arc<dyn Storage>;
+
+/// Operations the application needs from its database
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Short backend name for logs
+    fn backend(&self) -> &'static str;
+
+    /// Check that the database is reachable
+    async fn ping(&self) -> AppResult<()>;
+
+    /// Run database migrations
+    async fn run_migrations(&self) -> AppResult<()>;
+
+    /// Create a new URL entry
+    async fn create_url(
+        &self,
+        short_code: &str,
+        original_url: &str,
+        expires_at: Option<DateTime<Utc>>,
+        owner_id: Option<i64>,
+    ) -> AppResult<UrlEntry>;
+
+    /// Get a URL entry by short code
+    async fn get_url_by_short_code(&self, short_code: &str) -> AppResult<Option<UrlEntry>>;
+
+    /// Get a URL entry by short code, never from a lagging read replica
+    ///
+    /// Used when the result is cached for longer than replicas may lag.
+    /// Backends without replicas read as [`get_url_by_short_code`](Self::get_url_by_short_code).
+    async fn get_url_by_short_code_from_primary(
+        &self,
+        short_code: &str,
+    ) -> AppResult<Option<UrlEntry>> {
+        self.get_url_by_short_code(short_code).await
+    }
+
+    /// Apply coalesced click count increments for many URLs at once
+    async fn apply_click_counts(&self, deltas: &[ClickCountDelta]) -> AppResult<u64>;
+
+    /// Record click events in one transaction, returning how many were stored
+    ///
+    /// Events for URLs deleted since the click are skipped.
+    async fn insert_click_events(&self, events: &[ClickEvent]) -> AppResult<u64>;
+
+    /// Get click counts (excluding bots) per time bucket in `[from, to)`, including empty buckets
+    async fn get_click_timeseries(
+        &self,
+        url_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        interval: StatsInterval,
+    ) -> AppResult<Vec<(DateTime<Utc>, i64)>>;
+
+    /// Get the most frequent values of a click event column in `[from, to)`
+    ///
+    /// Bot hits are excluded and missing values are grouped under `None`.
+    async fn get_top_click_values(
+        &self,
+        url_id: i64,
+        dimension: ClickDimension,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<(Option<String>, i64)>>;
+
+    /// Count bot hits on a URL in `[from, to)`
+    async fn count_bot_clicks(
+        &self,
+        url_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> AppResult<i64>;
+
+    /// Check if a short code exists
+    async fn short_code_exists(&self, short_code: &str) -> AppResult<bool>;
+
+    /// The subset of `short_codes` already used by links
+    async fn existing_short_codes(&self, short_codes: &[String]) -> AppResult<Vec<String>>;
+
+    /// Draw the next value of the short code sequence (starts at 1, never repeats)
+    async fn next_short_code_sequence(&self) -> AppResult<i64>;
+
+    /// Remove and return one code from the pre-generated pool, if any are left
+    async fn claim_pooled_code(&self) -> AppResult<Option<String>>;
+
+    /// Add codes to the pool, skipping codes already pooled or in use; returns how many were added
+    async fn add_pooled_codes(&self, codes: &[String]) -> AppResult<u64>;
+
+    /// Number of unused codes in the pool
+    async fn count_pooled_codes(&self) -> AppResult<i64>;
+
+    /// Check if a (lowercase) code was reserved by an administrator
+    async fn is_code_reserved(&self, code: &str) -> AppResult<bool>;
+
+    /// Reserve a (lowercase) code; returns false if it was already reserved
+    async fn reserve_code(&self, code: &str) -> AppResult<bool>;
+
+    /// Release a reserved code; returns false if it wasn't reserved
+    async fn release_reserved_code(&self, code: &str) -> AppResult<bool>;
+
+    /// All administrator-reserved codes, sorted
+    async fn list_reserved_codes(&self) -> AppResult<Vec<String>>;
+
+    /// Delete a URL by short code
+    async fn delete_url(&self, short_code: &str) -> AppResult<bool>;
+
+    /// Delete a URL by short code, only if it belongs to the given owner
+    async fn delete_url_for_owner(&self, short_code: &str, owner_id: i64) -> AppResult<bool>;
+
+    /// Update the destination and/or expiry of a URL in one statement
+    ///
+    /// `None` leaves a field unchanged; `Some(None)` clears the expiry.
+    async fn update_url(
+        &self,
+        short_code: &str,
+        original_url: Option<&str>,
+        expires_at: Option<Option<DateTime<Utc>>>,
+    ) -> AppResult<Option<UrlEntry>>;
+
+    /// Delete all expired URLs
+    async fn delete_expired_urls(&self) -> AppResult<u64>;
+
+    /// Get statistics
+    async fn get_stats(&self) -> AppResult<Stats>;
+
+    /// Get statistics for URLs belonging to a single owner
+    async fn get_stats_for_owner(&self, owner_id: i64) -> AppResult<Stats>;
+
+    /// Get one page of URLs matching a filter, in the requested order
+    async fn list_urls(&self, query: &UrlListQuery) -> AppResult<Vec<UrlEntry>>;
+
+    /// Count URLs matching a filter
+    async fn count_urls(&self, filter: &UrlFilter) -> AppResult<i64>;
+
+    /// Create a new user
+    async fn create_user(&self, username: &str, password_hash: &str) -> AppResult<User>;
+
+    /// Get a user by username
+    async fn get_user_by_username(&self, username: &str) -> AppResult<Option<User>>;
+
+    /// Get a user by ID
+    async fn get_user_by_id(&self, user_id: i64) -> AppResult<Option<User>>;
+}
+
+/// Connect to the storage backend selected by the database URL
+pub async fn connect(config: &DatabaseConfig) -> AppResult<Repository> {
+    let backend = config.backend().map_err(AppError::Configuration)?;
+
+    let repository: Repository = match backend {
+        DatabaseBackend::Postgres => {
+            let primary: Repository = Arc::new(
+                PostgresStorage::new(
+                    &config.url,
+                    config.max_connections,
+                    config.min_connections,
+                    config.acquire_timeout_seconds,
+                )
+                .await?,
+            );
+            if config.replica_urls.is_empty() {
+                primary
+            } else {
+                let replicas = config
+                    .replica_urls
+                    .iter()
+                    .map(|url| {
+                        PostgresStorage::new_lazy(
+                            url,
+                            config.max_connections,
+                            config.min_connections,
+                            config.acquire_timeout_seconds,
+                        )
+                        .map(|replica| Arc::new(replica) as Repository)
+                    })
+                    .collect::<AppResult<Vec<_>>>()?;
+                tracing::info!("Routing reads to {} database replica(s)", replicas.len());
+
+                Arc::new(
+                    ReplicatedStorage::new(
+                        primary,
+                        replicas,
+                        Duration::from_secs(config.read_your_writes_seconds),
+                    )
+                    .with_circuit_breaker(
+                        config.replica_failure_threshold,
+                        Duration::from_secs(config.replica_cooldown_seconds),
+                    ),
+                )
+            }
+        }
+        DatabaseBackend::Memory => Arc::new(MemoryStorage::default()),
+        DatabaseBackend::Sqlite => Arc::new(
+            SqliteStorage::new(
+                &config.url,
+                config.max_connections,
+                config.acquire_timeout_seconds,
+            )
+            .await?,
+        ),
+    };
+
+    Ok(repository)
+}
+
+/// Map an insert error on `urls` to `AppError::ShortCodeExists` when the
+/// code was taken by a concurrent create, so SQL backends match memory
+pub(crate) fn short_code_conflict(error: sqlx::Error, short_code: &str) -> AppError {
+    match &error {
+        sqlx::Error::Database(e) if e.is_unique_violation() => {
+            AppError::ShortCodeExists(short_code.to_string())
+        }
+        _ => AppError::Database(error),
+    }
+}
+
+/// Click events per multi-row insert; 11 binds per row stays under SQLite's and
+/// PostgreSQL's parameter limits
+pub(crate) const CLICK_EVENT_INSERT_CHUNK: usize = 1000;
+
+/// Count click times per bucket in `[from, to)`, including empty buckets
+///
+/// Matches the PostgreSQL `generate_series`/`date_trunc` query for backends
+/// that bucket in Rust.
+pub(crate) fn bucket_clicks(
+    clicks: impl IntoIterator<Item = DateTime<Utc>>,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    interval: StatsInterval,
+) -> Vec<(DateTime<Utc>, i64)> {
+    let first = interval.truncate(from);
+    let last = interval.truncate(to - chrono::Duration::microseconds(1));
+    let mut buckets = Vec::new();
+    let mut bucket = first;
+    while bucket <= last {
+        buckets.push((bucket, 0));
+        bucket += interval.duration();
+    }
+
+    let step = interval.duration().num_seconds();
+    for clicked_at in clicks {
+        if clicked_at < from || clicked_at >= to {
+            continue;
+        }
+        let index = (interval.truncate(clicked_at) - first).num_seconds() / step;
+        if let Some((_, count)) = buckets.get_mut(index as usize) {
+            *count += 1;
+        }
+    }
+
+    buckets
+}
+
+/// Pending click count increments for a single URL
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickCountDelta {
+    pub short_code: String,
+    pub clicks: i64,
+    pub bot_clicks: i64,
+    /// Time of the latest human click (None if only bots clicked)
+    pub last_clicked_at: Option<DateTime<Utc>>,
+}
+
+/// Statistics struct
+#[derive(Debug)]
+pub struct Stats {
+    pub total_urls: i64,
+    pub total_clicks: i64,
+    pub bot_clicks: i64,
+    pub active_urls: i64,
+    pub expired_urls: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats_creation() {
+        let stats = Stats {
+            total_urls: 100,
+            total_clicks: 1000,
+            bot_clicks: 50,
+            active_urls: 80,
+            expired_urls: 20,
+        };
+
+        assert_eq!(stats.total_urls, 100);
+        assert_eq!(stats.total_clicks, 1000);
+        assert_eq!(stats.bot_clicks, 50);
+        assert_eq!(stats.active_urls, 80);
+        assert_eq!(stats.expired_urls, 20);
+    }
+}
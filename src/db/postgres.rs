@@ -0,0 +1,623 @@
+//! PostgreSQL storage backend.
+
+use super::list::{push_filter, push_page};
+use super::{
+    short_code_conflict, ClickCountDelta, Stats, Storage, UrlFilter, UrlListQuery,
+    CLICK_EVENT_INSERT_CHUNK,
+};
+use crate::analytics::{ClickDimension, ClickEvent, StatsInterval};
+use crate::error::{AppError, AppResult};
+use crate::middleware::User;
+use crate::models::UrlEntry;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    ConnectOptions, PgPool, Postgres, QueryBuilder,
+};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Storage backed by a PostgreSQL connection pool
+#[derive(Clone)]
+pub struct PostgresStorage {
+    pool: PgPool,
+}
+
+impl PostgresStorage {
+    /// Connect to PostgreSQL with a connection pool
+    pub async fn new(
+        database_url: &str,
+        max_connections: u32,
+        min_connections: u32,
+        acquire_timeout_seconds: u64,
+    ) -> AppResult<Self> {
+        let pool = Self::pool_options(max_connections, min_connections, acquire_timeout_seconds)
+            .connect_with(Self::connect_options(database_url)?)
+            .await?;
+
+        Ok(Self { pool })
+    }
+
+    /// Create a pool that connects on first use
+    ///
+    /// Used for read replicas, so an unreachable replica does not stop startup.
+    pub fn new_lazy(
+        database_url: &str,
+        max_connections: u32,
+        min_connections: u32,
+        acquire_timeout_seconds: u64,
+    ) -> AppResult<Self> {
+        let pool = Self::pool_options(max_connections, min_connections, acquire_timeout_seconds)
+            .connect_lazy_with(Self::connect_options(database_url)?);
+
+        Ok(Self { pool })
+    }
+
+    fn connect_options(database_url: &str) -> AppResult<PgConnectOptions> {
+        Ok(PgConnectOptions::from_str(database_url)
+            .map_err(|e| AppError::Configuration(format!("Invalid database URL: {}", e)))?
+            .disable_statement_logging())
+    }
+
+    fn pool_options(
+        max_connections: u32,
+        min_connections: u32,
+        acquire_timeout_seconds: u64,
+    ) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(max_connections)
+            .min_connections(min_connections)
+            .acquire_timeout(Duration::from_secs(acquire_timeout_seconds))
+    }
+}
+
+#[async_trait]
+impl Storage for PostgresStorage {
+    fn backend(&self) -> &'static str {
+        "postgres"
+    }
+
+    /// Check that a connection can be acquired
+    async fn ping(&self) -> AppResult<()> {
+        self.pool.acquire().await?;
+        Ok(())
+    }
+
+    /// Run database migrations
+    async fn run_migrations(&self) -> AppResult<()> {
+        sqlx::migrate!("./migrations").run(&self.pool).await?;
+        Ok(())
+    }
+
+    /// Create a new URL entry
+    async fn create_url(
+        &self,
+        short_code: &str,
+        original_url: &str,
+        expires_at: Option<DateTime<Utc>>,
+        owner_id: Option<i64>,
+    ) -> AppResult<UrlEntry> {
+        let now = Utc::now();
+
+        let result = sqlx::query_as::<_, UrlEntry>(
+            r#"
+            INSERT INTO urls (short_code, original_url, created_at, expires_at, click_count, owner_id)
+            VALUES ($1, $2, $3, $4, 0, $5)
+            RETURNING *
+            "#,
+        )
+        .bind(short_code)
+        .bind(original_url)
+        .bind(now)
+        .bind(expires_at)
+        .bind(owner_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| short_code_conflict(e, short_code))?;
+
+        Ok(result)
+    }
+
+    /// Get a URL entry by short code
+    async fn get_url_by_short_code(&self, short_code: &str) -> AppResult<Option<UrlEntry>> {
+        let result = sqlx::query_as::<_, UrlEntry>(
+            r#"
+            SELECT * FROM urls
+            WHERE short_code = $1
+            "#,
+        )
+        .bind(short_code)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Apply coalesced click count increments for many URLs in one statement
+    async fn apply_click_counts(&self, deltas: &[ClickCountDelta]) -> AppResult<u64> {
+        if deltas.is_empty() {
+            return Ok(0);
+        }
+
+        let mut builder = QueryBuilder::<Postgres>::new(
+            r#"
+            UPDATE urls AS u
+            SET click_count = u.click_count + v.clicks,
+                bot_click_count = u.bot_click_count + v.bot_clicks,
+                last_clicked_at = GREATEST(u.last_clicked_at, v.last_clicked_at)
+            FROM (
+            "#,
+        );
+        builder.push_values(deltas, |mut row, delta| {
+            row.push_bind(&delta.short_code)
+                .push_bind(delta.clicks)
+                .push_bind(delta.bot_clicks)
+                .push_bind(delta.last_clicked_at);
+        });
+        builder.push(
+            r#"
+            ) AS v(short_code, clicks, bot_clicks, last_clicked_at)
+            WHERE u.short_code = v.short_code
+            "#,
+        );
+
+        let result = builder.build().execute(&self.pool).await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Record click events with multi-row inserts in one transaction
+    async fn insert_click_events(&self, events: &[ClickEvent]) -> AppResult<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut inserted = 0;
+        for chunk in events.chunks(CLICK_EVENT_INSERT_CHUNK) {
+            let mut builder = QueryBuilder::<Postgres>::new(
+                r#"
+                INSERT INTO click_events (
+                    url_id, clicked_at, referrer, user_agent, ip_hash, country,
+                    browser_family, browser_version, os_family, device_class, is_bot
+                )
+                SELECT v.* FROM (
+                "#,
+            );
+            builder.push_values(chunk, |mut row, event| {
+                row.push_bind(event.url_id)
+                    .push_bind(event.clicked_at)
+                    .push_bind(&event.referrer)
+                    .push_bind(&event.user_agent)
+                    .push_bind(&event.ip_hash)
+                    .push_bind(&event.country)
+                    .push_bind(&event.browser_family)
+                    .push_bind(&event.browser_version)
+                    .push_bind(&event.os_family)
+                    .push_bind(event.device_class.map(|d| d.as_str()))
+                    .push_bind(event.is_bot);
+            });
+            builder.push(
+                r#"
+                ) AS v(
+                    url_id, clicked_at, referrer, user_agent, ip_hash, country,
+                    browser_family, browser_version, os_family, device_class, is_bot
+                )
+                WHERE EXISTS (SELECT 1 FROM urls WHERE urls.id = v.url_id)
+                "#,
+            );
+            inserted += builder.build().execute(&mut *tx).await?.rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(inserted)
+    }
+
+    /// Get click counts (excluding bots) per time bucket in `[from, to)`, including empty buckets
+    async fn get_click_timeseries(
+        &self,
+        url_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        interval: StatsInterval,
+    ) -> AppResult<Vec<(DateTime<Utc>, i64)>> {
+        let results = sqlx::query_as::<_, (DateTime<Utc>, i64)>(
+            r#"
+            SELECT b.bucket, COUNT(e.id)
+            FROM generate_series(
+                date_trunc($1, $3::timestamptz),
+                date_trunc($1, $4::timestamptz - INTERVAL '1 microsecond'),
+                ('1 ' || $1)::interval
+            ) AS b(bucket)
+            LEFT JOIN click_events e
+                ON e.url_id = $2
+                AND e.clicked_at >= $3
+                AND e.clicked_at < $4
+                AND NOT e.is_bot
+                AND date_trunc($1, e.clicked_at) = b.bucket
+            GROUP BY b.bucket
+            ORDER BY b.bucket
+            "#,
+        )
+        .bind(interval.as_str())
+        .bind(url_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(results)
+    }
+
+    /// Get the most frequent values of a click event column in `[from, to)`
+    ///
+    /// Bot hits are excluded and missing values are grouped under `None`.
+    async fn get_top_click_values(
+        &self,
+        url_id: i64,
+        dimension: ClickDimension,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<(Option<String>, i64)>> {
+        // Column name comes from a fixed whitelist, so interpolation is safe
+        let query = format!(
+            r#"
+            SELECT {column}::TEXT, COUNT(*) AS clicks
+            FROM click_events
+            WHERE url_id = $1 AND clicked_at >= $2 AND clicked_at < $3 AND NOT is_bot
+            GROUP BY {column}
+            ORDER BY clicks DESC, {column} ASC
+            LIMIT $4
+            "#,
+            column = dimension.column()
+        );
+
+        let results = sqlx::query_as::<_, (Option<String>, i64)>(&query)
+            .bind(url_id)
+            .bind(from)
+            .bind(to)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results)
+    }
+
+    /// Count bot hits on a URL in `[from, to)`
+    async fn count_bot_clicks(
+        &self,
+        url_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> AppResult<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM click_events
+            WHERE url_id = $1 AND clicked_at >= $2 AND clicked_at < $3 AND is_bot
+            "#,
+        )
+        .bind(url_id)
+        .bind(from)
+        .bind(to)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Check if a short code exists
+    async fn short_code_exists(&self, short_code: &str) -> AppResult<bool> {
+        let result = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM urls WHERE short_code = $1
+            "#,
+        )
+        .bind(short_code)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result > 0)
+    }
+
+    /// Find which of the given short codes are taken
+    async fn existing_short_codes(&self, short_codes: &[String]) -> AppResult<Vec<String>> {
+        let codes = sqlx::query_scalar::<_, String>(
+            "SELECT short_code FROM urls WHERE short_code = ANY($1)",
+        )
+        .bind(short_codes)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(codes)
+    }
+
+    /// Draw the next value of the short code sequence
+    async fn next_short_code_sequence(&self) -> AppResult<i64> {
+        let value = sqlx::query_scalar::<_, i64>("SELECT nextval('short_code_seq')")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(value)
+    }
+
+    /// Claim a pooled code; SKIP LOCKED lets concurrent claims take different rows
+    async fn claim_pooled_code(&self) -> AppResult<Option<String>> {
+        let code = sqlx::query_scalar::<_, String>(
+            r#"
+            DELETE FROM short_code_pool
+            WHERE code = (
+                SELECT code FROM short_code_pool
+                LIMIT 1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING code
+            "#,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(code)
+    }
+
+    /// Add codes to the pool
+    async fn add_pooled_codes(&self, codes: &[String]) -> AppResult<u64> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO short_code_pool (code)
+            SELECT c FROM UNNEST($1::text[]) AS c
+            WHERE NOT EXISTS (SELECT 1 FROM urls WHERE short_code = c)
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(codes)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Count unused pooled codes
+    async fn count_pooled_codes(&self) -> AppResult<i64> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM short_code_pool")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Check if a code was reserved by an administrator
+    async fn is_code_reserved(&self, code: &str) -> AppResult<bool> {
+        let reserved = sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM reserved_codes WHERE code = $1)",
+        )
+        .bind(code)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(reserved)
+    }
+
+    /// Reserve a code
+    async fn reserve_code(&self, code: &str) -> AppResult<bool> {
+        let result =
+            sqlx::query("INSERT INTO reserved_codes (code) VALUES ($1) ON CONFLICT DO NOTHING")
+                .bind(code)
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Release a reserved code
+    async fn release_reserved_code(&self, code: &str) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM reserved_codes WHERE code = $1")
+            .bind(code)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// List reserved codes
+    async fn list_reserved_codes(&self) -> AppResult<Vec<String>> {
+        let codes =
+            sqlx::query_scalar::<_, String>("SELECT code FROM reserved_codes ORDER BY code")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(codes)
+    }
+
+    /// Delete a URL by short code
+    async fn delete_url(&self, short_code: &str) -> AppResult<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM urls WHERE short_code = $1
+            "#,
+        )
+        .bind(short_code)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete a URL by short code, only if it belongs to the given owner
+    async fn delete_url_for_owner(&self, short_code: &str, owner_id: i64) -> AppResult<bool> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM urls WHERE short_code = $1 AND owner_id = $2
+            "#,
+        )
+        .bind(short_code)
+        .bind(owner_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Update the destination and/or expiry of a URL in one statement
+    async fn update_url(
+        &self,
+        short_code: &str,
+        original_url: Option<&str>,
+        expires_at: Option<Option<DateTime<Utc>>>,
+    ) -> AppResult<Option<UrlEntry>> {
+        let result = sqlx::query_as::<_, UrlEntry>(
+            r#"
+            UPDATE urls
+            SET original_url = COALESCE($1, original_url),
+                expires_at = CASE WHEN $2 THEN $3 ELSE expires_at END
+            WHERE short_code = $4
+            RETURNING *
+            "#,
+        )
+        .bind(original_url)
+        .bind(expires_at.is_some())
+        .bind(expires_at.flatten())
+        .bind(short_code)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Delete all expired URLs
+    async fn delete_expired_urls(&self) -> AppResult<u64> {
+        let now = Utc::now();
+
+        let result = sqlx::query(
+            r#"
+            DELETE FROM urls WHERE expires_at IS NOT NULL AND expires_at < $1
+            "#,
+        )
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Get statistics
+    async fn get_stats(&self) -> AppResult<Stats> {
+        let row = sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(
+            r#"
+            SELECT
+                COUNT(*) as total_urls,
+                COALESCE(CAST(SUM(click_count) AS BIGINT), 0) as total_clicks,
+                COALESCE(CAST(SUM(bot_click_count) AS BIGINT), 0) as bot_clicks,
+                COUNT(*) FILTER (WHERE expires_at IS NULL OR expires_at > NOW()) as active_urls,
+                COUNT(*) FILTER (WHERE expires_at IS NOT NULL AND expires_at <= NOW()) as expired_urls
+            FROM urls
+            WHERE NOW() IS NOT NULL
+            "#,
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Stats {
+            total_urls: row.0,
+            total_clicks: row.1,
+            bot_clicks: row.2,
+            active_urls: row.3,
+            expired_urls: row.4,
+        })
+    }
+
+    /// Get statistics for URLs belonging to a single owner
+    async fn get_stats_for_owner(&self, owner_id: i64) -> AppResult<Stats> {
+        let row = sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(
+            r#"
+            SELECT
+                COUNT(*) as total_urls,
+                COALESCE(CAST(SUM(click_count) AS BIGINT), 0) as total_clicks,
+                COALESCE(CAST(SUM(bot_click_count) AS BIGINT), 0) as bot_clicks,
+                COUNT(*) FILTER (WHERE expires_at IS NULL OR expires_at > NOW()) as active_urls,
+                COUNT(*) FILTER (WHERE expires_at IS NOT NULL AND expires_at <= NOW()) as expired_urls
+            FROM urls
+            WHERE owner_id = $1
+            "#,
+        )
+        .bind(owner_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Stats {
+            total_urls: row.0,
+            total_clicks: row.1,
+            bot_clicks: row.2,
+            active_urls: row.3,
+            expired_urls: row.4,
+        })
+    }
+
+    /// Get one page of URLs matching a filter, in the requested order
+    async fn list_urls(&self, query: &UrlListQuery) -> AppResult<Vec<UrlEntry>> {
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT * FROM urls");
+        push_page(&mut builder, query, "~", Utc::now());
+
+        let results = builder
+            .build_query_as::<UrlEntry>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results)
+    }
+
+    /// Count URLs matching a filter
+    async fn count_urls(&self, filter: &UrlFilter) -> AppResult<i64> {
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT COUNT(*) FROM urls");
+        push_filter(&mut builder, filter, "~", Utc::now());
+
+        let count = builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    /// Create a new user
+    async fn create_user(&self, username: &str, password_hash: &str) -> AppResult<User> {
+        let result = sqlx::query_as::<_, User>(
+            r#"
+            INSERT INTO users (username, password_hash)
+            VALUES ($1, $2)
+            RETURNING *
+            "#,
+        )
+        .bind(username)
+        .bind(password_hash)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Get a user by username
+    async fn get_user_by_username(&self, username: &str) -> AppResult<Option<User>> {
+        let result = sqlx::query_as::<_, User>(
+            r#"
+            SELECT * FROM users WHERE username = $1
+            "#,
+        )
+        .bind(username)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Get a user by ID
+    async fn get_user_by_id(&self, user_id: i64) -> AppResult<Option<User>> {
+        let result = sqlx::query_as::<_, User>(
+            r#"
+            SELECT * FROM users WHERE id = $1
+            "#,
+        )
+        .bind(user_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,470 @@
+//! Read-replica routing.
+//!
+//! Wraps a primary [`Storage`] and one or more replicas. Writes, and anything
+//! that must see them immediately (uniqueness checks, users), go to the
+//! primary. Redirect lookups, listings, stats and click analytics go to the
+//! replicas in turn.
+//!
+//! Replicas lag behind the primary, so links and owners written through this
+//! instance are read from the primary for a short window afterwards
+//! (read-your-writes). A replica that keeps failing is skipped by a circuit
+//! breaker and its reads fall back to the primary until a probe succeeds.
+
+use super::{ClickCountDelta, Repository, Stats, Storage, UrlFilter, UrlListQuery};
+use crate::analytics::{ClickDimension, ClickEvent, StatsInterval};
+use crate::circuit_breaker::{CircuitBreaker, Permit};
+use crate::error::{AppError, AppResult};
+use crate::middleware::User;
+use crate::models::UrlEntry;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+struct Replica {
+    storage: Repository,
+    breaker: CircuitBreaker,
+}
+
+/// Links and owners written recently, with the end of their primary-read window
+#[derive(Default)]
+struct RecentWrites {
+    codes: HashMap<String, Instant>,
+    owners: HashMap<i64, Instant>,
+}
+
+/// Storage that sends reads to replicas and writes to the primary
+pub struct ReplicatedStorage {
+    primary: Repository,
+    replicas: Vec<Replica>,
+    next_replica: AtomicUsize,
+    read_your_writes: Duration,
+    recent_writes: Mutex<RecentWrites>,
+}
+
+impl ReplicatedStorage {
+    const DEFAULT_FAILURE_THRESHOLD: u32 = 3;
+    const DEFAULT_COOLDOWN: Duration = Duration::from_secs(30);
+
+    /// Route reads across `replicas`, using the primary for `read_your_writes` after a write
+    pub fn new(primary: Repository, replicas: Vec<Repository>, read_your_writes: Duration) -> Self {
+        Self {
+            primary,
+            replicas: replicas
+                .into_iter()
+                .map(|storage| Replica {
+                    storage,
+                    breaker: Self::breaker(Self::DEFAULT_FAILURE_THRESHOLD, Self::DEFAULT_COOLDOWN),
+                })
+                .collect(),
+            next_replica: AtomicUsize::new(0),
+            read_your_writes,
+            recent_writes: Mutex::new(RecentWrites::default()),
+        }
+    }
+
+    /// Skip a replica for `cooldown` after `failure_threshold` consecutive failures
+    pub fn with_circuit_breaker(mut self, failure_threshold: u32, cooldown: Duration) -> Self {
+        for replica in &mut self.replicas {
+            replica.breaker = Self::breaker(failure_threshold, cooldown);
+        }
+        self
+    }
+
+    fn breaker(failure_threshold: u32, cooldown: Duration) -> CircuitBreaker {
+        CircuitBreaker::new("Database replica", failure_threshold, cooldown)
+    }
+
+    /// Run a read on the next available replica, falling back to the primary
+    ///
+    /// Only database errors count against a replica; the read is then retried
+    /// on the primary so callers never see a replica outage.
+    async fn read<T, F, Fut>(&self, query: F) -> AppResult<T>
+    where
+        F: Fn(Repository) -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        let start = self.next_replica.fetch_add(1, Ordering::Relaxed);
+        for offset in 0..self.replicas.len() {
+            let replica = &self.replicas[(start + offset) % self.replicas.len()];
+            if replica.breaker.acquire() == Permit::Rejected {
+                continue;
+            }
+
+            match query(replica.storage.clone()).await {
+                Err(AppError::Database(e)) => {
+                    tracing::warn!("Replica read failed, using the primary: {}", e);
+                    replica.breaker.record_failure();
+                    break;
+                }
+                result => {
+                    replica.breaker.record_success();
+                    return result;
+                }
+            }
+        }
+
+        query(self.primary.clone()).await
+    }
+
+    /// Run a read on the primary if `recent` holds, otherwise on a replica
+    async fn read_unless<T, F, Fut>(&self, recent: bool, query: F) -> AppResult<T>
+    where
+        F: Fn(Repository) -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        if recent {
+            query(self.primary.clone()).await
+        } else {
+            self.read(query).await
+        }
+    }
+
+    /// Start the read-your-writes window for a link and its owner
+    fn record_write(&self, short_code: &str, owner_id: Option<i64>) {
+        if self.read_your_writes.is_zero() {
+            return;
+        }
+
+        let now = Instant::now();
+        let until = now + self.read_your_writes;
+        let mut recent = self.recent_writes();
+        recent.codes.retain(|_, t| *t > now);
+        recent.owners.retain(|_, t| *t > now);
+        recent.codes.insert(short_code.to_string(), until);
+        if let Some(owner_id) = owner_id {
+            recent.owners.insert(owner_id, until);
+        }
+    }
+
+    fn code_recently_written(&self, short_code: &str) -> bool {
+        self.recent_writes()
+            .codes
+            .get(short_code)
+            .is_some_and(|t| *t > Instant::now())
+    }
+
+    fn owner_recently_written(&self, owner_id: i64) -> bool {
+        self.recent_writes()
+            .owners
+            .get(&owner_id)
+            .is_some_and(|t| *t > Instant::now())
+    }
+
+    fn recent_writes(&self) -> std::sync::MutexGuard<'_, RecentWrites> {
+        self.recent_writes.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[async_trait]
+impl Storage for ReplicatedStorage {
+    fn backend(&self) -> &'static str {
+        self.primary.backend()
+    }
+
+    /// Check the primary; replica outages only degrade to primary reads
+    async fn ping(&self) -> AppResult<()> {
+        self.primary.ping().await
+    }
+
+    async fn run_migrations(&self) -> AppResult<()> {
+        self.primary.run_migrations().await
+    }
+
+    async fn create_url(
+        &self,
+        short_code: &str,
+        original_url: &str,
+        expires_at: Option<DateTime<Utc>>,
+        owner_id: Option<i64>,
+    ) -> AppResult<UrlEntry> {
+        let entry = self
+            .primary
+            .create_url(short_code, original_url, expires_at, owner_id)
+            .await?;
+        self.record_write(short_code, owner_id);
+        Ok(entry)
+    }
+
+    async fn get_url_by_short_code(&self, short_code: &str) -> AppResult<Option<UrlEntry>> {
+        self.read_unless(self.code_recently_written(short_code), |db| async move {
+            db.get_url_by_short_code(short_code).await
+        })
+        .await
+    }
+
+    async fn get_url_by_short_code_from_primary(
+        &self,
+        short_code: &str,
+    ) -> AppResult<Option<UrlEntry>> {
+        self.primary.get_url_by_short_code(short_code).await
+    }
+
+    async fn apply_click_counts(&self, deltas: &[ClickCountDelta]) -> AppResult<u64> {
+        self.primary.apply_click_counts(deltas).await
+    }
+
+    async fn insert_click_events(&self, events: &[ClickEvent]) -> AppResult<u64> {
+        self.primary.insert_click_events(events).await
+    }
+
+    async fn get_click_timeseries(
+        &self,
+        url_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        interval: StatsInterval,
+    ) -> AppResult<Vec<(DateTime<Utc>, i64)>> {
+        self.read(|db| async move { db.get_click_timeseries(url_id, from, to, interval).await })
+            .await
+    }
+
+    async fn get_top_click_values(
+        &self,
+        url_id: i64,
+        dimension: ClickDimension,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<(Option<String>, i64)>> {
+        self.read(|db| async move {
+            db.get_top_click_values(url_id, dimension, from, to, limit)
+                .await
+        })
+        .await
+    }
+
+    async fn count_bot_clicks(
+        &self,
+        url_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> AppResult<i64> {
+        self.read(|db| async move { db.count_bot_clicks(url_id, from, to).await })
+            .await
+    }
+
+    /// Always on the primary, since it guards inserts
+    async fn short_code_exists(&self, short_code: &str) -> AppResult<bool> {
+        self.primary.short_code_exists(short_code).await
+    }
+
+    async fn existing_short_codes(&self, short_codes: &[String]) -> AppResult<Vec<String>> {
+        self.primary.existing_short_codes(short_codes).await
+    }
+
+    async fn next_short_code_sequence(&self) -> AppResult<i64> {
+        self.primary.next_short_code_sequence().await
+    }
+
+    async fn claim_pooled_code(&self) -> AppResult<Option<String>> {
+        self.primary.claim_pooled_code().await
+    }
+
+    async fn add_pooled_codes(&self, codes: &[String]) -> AppResult<u64> {
+        self.primary.add_pooled_codes(codes).await
+    }
+
+    async fn count_pooled_codes(&self) -> AppResult<i64> {
+        self.primary.count_pooled_codes().await
+    }
+
+    async fn is_code_reserved(&self, code: &str) -> AppResult<bool> {
+        self.primary.is_code_reserved(code).await
+    }
+
+    async fn reserve_code(&self, code: &str) -> AppResult<bool> {
+        self.primary.reserve_code(code).await
+    }
+
+    async fn release_reserved_code(&self, code: &str) -> AppResult<bool> {
+        self.primary.release_reserved_code(code).await
+    }
+
+    async fn list_reserved_codes(&self) -> AppResult<Vec<String>> {
+        self.primary.list_reserved_codes().await
+    }
+
+    async fn delete_url(&self, short_code: &str) -> AppResult<bool> {
+        let deleted = self.primary.delete_url(short_code).await?;
+        self.record_write(short_code, None);
+        Ok(deleted)
+    }
+
+    async fn delete_url_for_owner(&self, short_code: &str, owner_id: i64) -> AppResult<bool> {
+        let deleted = self
+            .primary
+            .delete_url_for_owner(short_code, owner_id)
+            .await?;
+        if deleted {
+            self.record_write(short_code, Some(owner_id));
+        }
+        Ok(deleted)
+    }
+
+    async fn update_url(
+        &self,
+        short_code: &str,
+        original_url: Option<&str>,
+        expires_at: Option<Option<DateTime<Utc>>>,
+    ) -> AppResult<Option<UrlEntry>> {
+        let entry = self
+            .primary
+            .update_url(short_code, original_url, expires_at)
+            .await?;
+        if let Some(entry) = &entry {
+            self.record_write(short_code, entry.owner_id);
+        }
+        Ok(entry)
+    }
+
+    async fn delete_expired_urls(&self) -> AppResult<u64> {
+        self.primary.delete_expired_urls().await
+    }
+
+    async fn get_stats(&self) -> AppResult<Stats> {
+        self.read(|db| async move { db.get_stats().await }).await
+    }
+
+    async fn get_stats_for_owner(&self, owner_id: i64) -> AppResult<Stats> {
+        self.read_unless(self.owner_recently_written(owner_id), |db| async move {
+            db.get_stats_for_owner(owner_id).await
+        })
+        .await
+    }
+
+    async fn list_urls(&self, query: &UrlListQuery) -> AppResult<Vec<UrlEntry>> {
+        let recent = query
+            .filter
+            .owner_id
+            .is_some_and(|owner_id| self.owner_recently_written(owner_id));
+        self.read_unless(recent, |db| async move { db.list_urls(query).await })
+            .await
+    }
+
+    async fn count_urls(&self, filter: &UrlFilter) -> AppResult<i64> {
+        let recent = filter
+            .owner_id
+            .is_some_and(|owner_id| self.owner_recently_written(owner_id));
+        self.read_unless(recent, |db| async move { db.count_urls(filter).await })
+            .await
+    }
+
+    async fn create_user(&self, username: &str, password_hash: &str) -> AppResult<User> {
+        self.primary.create_user(username, password_hash).await
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> AppResult<Option<User>> {
+        self.primary.get_user_by_username(username).await
+    }
+
+    async fn get_user_by_id(&self, user_id: i64) -> AppResult<Option<User>> {
+        self.primary.get_user_by_id(user_id).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::circuit_breaker::CircuitState;
+    use crate::db::{MemoryStorage, PostgresStorage};
+    use std::sync::Arc;
+
+    fn owner(owner_id: i64) -> UrlFilter {
+        UrlFilter {
+            owner_id: Some(owner_id),
+            ..UrlFilter::default()
+        }
+    }
+
+    fn storage(read_your_writes: Duration) -> (ReplicatedStorage, Repository, Repository) {
+        let primary: Repository = Arc::new(MemoryStorage::default());
+        let replica: Repository = Arc::new(MemoryStorage::default());
+        let storage =
+            ReplicatedStorage::new(primary.clone(), vec![replica.clone()], read_your_writes);
+        (storage, primary, replica)
+    }
+
+    #[tokio::test]
+    async fn test_reads_go_to_replica() {
+        let (storage, primary, replica) = storage(Duration::ZERO);
+        replica
+            .create_url("abc123", "https://example.com", None, Some(1))
+            .await
+            .unwrap();
+
+        assert!(storage
+            .get_url_by_short_code("abc123")
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(storage.count_urls(&owner(1)).await.unwrap(), 1);
+        assert!(!storage.short_code_exists("abc123").await.unwrap());
+
+        storage
+            .create_url("xyz789", "https://example.com", None, Some(1))
+            .await
+            .unwrap();
+        assert!(primary.short_code_exists("xyz789").await.unwrap());
+        assert!(storage
+            .get_url_by_short_code("xyz789")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_read_your_writes() {
+        let (storage, _, _) = storage(Duration::from_secs(60));
+        storage
+            .create_url("abc123", "https://example.com", None, Some(1))
+            .await
+            .unwrap();
+
+        assert!(storage
+            .get_url_by_short_code("abc123")
+            .await
+            .unwrap()
+            .is_some());
+        let query = UrlListQuery {
+            filter: owner(1),
+            limit: 10,
+            ..UrlListQuery::default()
+        };
+        assert_eq!(storage.list_urls(&query).await.unwrap().len(), 1);
+
+        // Other owners still read from the (empty) replica
+        storage
+            .primary
+            .create_url("other1", "https://example.com", None, Some(2))
+            .await
+            .unwrap();
+        assert_eq!(storage.count_urls(&owner(2)).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_primary_when_replica_fails() {
+        let primary: Repository = Arc::new(MemoryStorage::default());
+        primary
+            .create_url("abc123", "https://example.com", None, None)
+            .await
+            .unwrap();
+        // Nothing listens on port 1, so every replica query fails
+        let replica: Repository = Arc::new(
+            PostgresStorage::new_lazy("postgresql://127.0.0.1:1/rustlink", 1, 0, 1).unwrap(),
+        );
+        let storage = ReplicatedStorage::new(primary, vec![replica], Duration::ZERO)
+            .with_circuit_breaker(1, Duration::from_secs(60));
+
+        assert!(storage
+            .get_url_by_short_code("abc123")
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(storage.replicas[0].breaker.state(), CircuitState::Open);
+        assert_eq!(storage.count_urls(&UrlFilter::default()).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,884 @@
+//! Embedded SQLite storage backend.
+//!
+//! Lets rustLink run from a single database file, without a database server.
+//! Timestamps are stored as RFC 3339 text in UTC, so they compare correctly as
+//! strings; "now" is always bound from the application rather than taken from
+//! SQLite's clock.
+
+use super::list::{push_filter, push_page};
+use super::{
+    bucket_clicks, short_code_conflict, ClickCountDelta, Stats, Storage, UrlFilter, UrlListQuery,
+    CLICK_EVENT_INSERT_CHUNK,
+};
+use crate::analytics::{ClickDimension, ClickEvent, StatsInterval};
+use crate::error::{AppError, AppResult};
+use crate::middleware::User;
+use crate::models::UrlEntry;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions},
+    ConnectOptions, QueryBuilder, Sqlite, SqlitePool,
+};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Storage backed by an SQLite database file
+#[derive(Clone)]
+pub struct SqliteStorage {
+    pool: SqlitePool,
+}
+
+impl SqliteStorage {
+    /// Open (creating if needed) an SQLite database
+    ///
+    /// In-memory databases (`sqlite::memory:`) exist per connection, so they are
+    /// served by a single connection that is never recycled.
+    pub async fn new(
+        database_url: &str,
+        max_connections: u32,
+        acquire_timeout_seconds: u64,
+    ) -> AppResult<Self> {
+        let in_memory = database_url.contains(":memory:") || database_url.contains("mode=memory");
+
+        let mut options = SqliteConnectOptions::from_str(database_url)
+            .map_err(|e| AppError::Configuration(format!("Invalid database URL: {}", e)))?
+            .create_if_missing(true)
+            .foreign_keys(true)
+            .busy_timeout(Duration::from_secs(acquire_timeout_seconds))
+            // Destination domain filters on listings
+            .with_regexp()
+            .disable_statement_logging();
+        if !in_memory {
+            options = options.journal_mode(SqliteJournalMode::Wal);
+        }
+
+        let mut pool_options =
+            SqlitePoolOptions::new().acquire_timeout(Duration::from_secs(acquire_timeout_seconds));
+        pool_options = if in_memory {
+            pool_options
+                .max_connections(1)
+                .min_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+        } else {
+            pool_options.max_connections(max_connections)
+        };
+
+        let pool = pool_options.connect_with(options).await?;
+
+        Ok(Self { pool })
+    }
+
+    /// Statistics over all URLs, or only those of `owner_id`
+    async fn query_stats(&self, owner_id: Option<i64>) -> AppResult<Stats> {
+        let row = sqlx::query_as::<_, (i64, i64, i64, i64, i64)>(
+            r#"
+            SELECT
+                COUNT(*) AS total_urls,
+                COALESCE(SUM(click_count), 0) AS total_clicks,
+                COALESCE(SUM(bot_click_count), 0) AS bot_clicks,
+                COUNT(*) FILTER (WHERE expires_at IS NULL OR expires_at > ?1) AS active_urls,
+                COUNT(*) FILTER (WHERE expires_at IS NOT NULL AND expires_at <= ?1) AS expired_urls
+            FROM urls
+            WHERE ?2 IS NULL OR owner_id = ?2
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(owner_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Stats {
+            total_urls: row.0,
+            total_clicks: row.1,
+            bot_clicks: row.2,
+            active_urls: row.3,
+            expired_urls: row.4,
+        })
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    fn backend(&self) -> &'static str {
+        "sqlite"
+    }
+
+    /// Check that a connection can be acquired
+    async fn ping(&self) -> AppResult<()> {
+        self.pool.acquire().await?;
+        Ok(())
+    }
+
+    /// Run the SQLite migration set
+    async fn run_migrations(&self) -> AppResult<()> {
+        sqlx::migrate!("./migrations/sqlite")
+            .run(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn create_url(
+        &self,
+        short_code: &str,
+        original_url: &str,
+        expires_at: Option<DateTime<Utc>>,
+        owner_id: Option<i64>,
+    ) -> AppResult<UrlEntry> {
+        let result = sqlx::query_as::<_, UrlEntry>(
+            r#"
+            INSERT INTO urls (short_code, original_url, created_at, expires_at, click_count, owner_id)
+            VALUES (?1, ?2, ?3, ?4, 0, ?5)
+            RETURNING *
+            "#,
+        )
+        .bind(short_code)
+        .bind(original_url)
+        .bind(Utc::now())
+        .bind(expires_at)
+        .bind(owner_id)
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| short_code_conflict(e, short_code))?;
+
+        Ok(result)
+    }
+
+    async fn get_url_by_short_code(&self, short_code: &str) -> AppResult<Option<UrlEntry>> {
+        let result = sqlx::query_as::<_, UrlEntry>("SELECT * FROM urls WHERE short_code = ?1")
+            .bind(short_code)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(result)
+    }
+
+    /// Apply click count increments in a single transaction
+    async fn apply_click_counts(&self, deltas: &[ClickCountDelta]) -> AppResult<u64> {
+        if deltas.is_empty() {
+            return Ok(0);
+        }
+
+        let mut tx = self.pool.begin().await?;
+        let mut updated = 0;
+        for delta in deltas {
+            // Scalar MAX() is NULL if either side is, so fall back to whichever is set
+            let result = sqlx::query(
+                r#"
+                UPDATE urls
+                SET click_count = click_count + ?1,
+                    bot_click_count = bot_click_count + ?2,
+                    last_clicked_at = COALESCE(MAX(last_clicked_at, ?3), last_clicked_at, ?3)
+                WHERE short_code = ?4
+                "#,
+            )
+            .bind(delta.clicks)
+            .bind(delta.bot_clicks)
+            .bind(delta.last_clicked_at)
+            .bind(&delta.short_code)
+            .execute(&mut *tx)
+            .await?;
+            updated += result.rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(updated)
+    }
+
+    async fn insert_click_events(&self, events: &[ClickEvent]) -> AppResult<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut inserted = 0;
+        for chunk in events.chunks(CLICK_EVENT_INSERT_CHUNK) {
+            // VALUES columns are named column1..column11 in SQLite
+            let mut builder = QueryBuilder::<Sqlite>::new(
+                r#"
+                INSERT INTO click_events (
+                    url_id, clicked_at, referrer, user_agent, ip_hash, country,
+                    browser_family, browser_version, os_family, device_class, is_bot
+                )
+                SELECT * FROM (
+                "#,
+            );
+            builder.push_values(chunk, |mut row, event| {
+                row.push_bind(event.url_id)
+                    .push_bind(event.clicked_at)
+                    .push_bind(&event.referrer)
+                    .push_bind(&event.user_agent)
+                    .push_bind(&event.ip_hash)
+                    .push_bind(&event.country)
+                    .push_bind(&event.browser_family)
+                    .push_bind(&event.browser_version)
+                    .push_bind(&event.os_family)
+                    .push_bind(event.device_class.map(|d| d.as_str()))
+                    .push_bind(event.is_bot);
+            });
+            builder.push(") AS v WHERE v.column1 IN (SELECT id FROM urls)");
+            inserted += builder.build().execute(&mut *tx).await?.rows_affected();
+        }
+        tx.commit().await?;
+
+        Ok(inserted)
+    }
+
+    /// Bucket click times in Rust, as SQLite has no `date_trunc`/`generate_series`
+    async fn get_click_timeseries(
+        &self,
+        url_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        interval: StatsInterval,
+    ) -> AppResult<Vec<(DateTime<Utc>, i64)>> {
+        let clicks = sqlx::query_scalar::<_, DateTime<Utc>>(
+            r#"
+            SELECT clicked_at FROM click_events
+            WHERE url_id = ?1 AND clicked_at >= ?2 AND clicked_at < ?3 AND NOT is_bot
+            "#,
+        )
+        .bind(url_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(bucket_clicks(clicks, from, to, interval))
+    }
+
+    async fn get_top_click_values(
+        &self,
+        url_id: i64,
+        dimension: ClickDimension,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        limit: i64,
+    ) -> AppResult<Vec<(Option<String>, i64)>> {
+        // Column name comes from a fixed whitelist, so interpolation is safe.
+        // NULLs sort last, as in PostgreSQL.
+        let query = format!(
+            r#"
+            SELECT CAST({column} AS TEXT), COUNT(*) AS clicks
+            FROM click_events
+            WHERE url_id = ?1 AND clicked_at >= ?2 AND clicked_at < ?3 AND NOT is_bot
+            GROUP BY {column}
+            ORDER BY clicks DESC, {column} IS NULL, {column} ASC
+            LIMIT ?4
+            "#,
+            column = dimension.column()
+        );
+
+        let results = sqlx::query_as::<_, (Option<String>, i64)>(&query)
+            .bind(url_id)
+            .bind(from)
+            .bind(to)
+            .bind(limit)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results)
+    }
+
+    async fn count_bot_clicks(
+        &self,
+        url_id: i64,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> AppResult<i64> {
+        let count = sqlx::query_scalar::<_, i64>(
+            r#"
+            SELECT COUNT(*) FROM click_events
+            WHERE url_id = ?1 AND clicked_at >= ?2 AND clicked_at < ?3 AND is_bot
+            "#,
+        )
+        .bind(url_id)
+        .bind(from)
+        .bind(to)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    async fn short_code_exists(&self, short_code: &str) -> AppResult<bool> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM urls WHERE short_code = ?1")
+            .bind(short_code)
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count > 0)
+    }
+
+    async fn existing_short_codes(&self, short_codes: &[String]) -> AppResult<Vec<String>> {
+        if short_codes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut builder =
+            QueryBuilder::<Sqlite>::new("SELECT short_code FROM urls WHERE short_code IN (");
+        let mut separated = builder.separated(", ");
+        for code in short_codes {
+            separated.push_bind(code);
+        }
+        builder.push(")");
+
+        let codes = builder.build_query_scalar().fetch_all(&self.pool).await?;
+
+        Ok(codes)
+    }
+
+    async fn next_short_code_sequence(&self) -> AppResult<i64> {
+        // A single-row counter; the UPDATE serializes concurrent callers
+        let value = sqlx::query_scalar::<_, i64>(
+            "UPDATE short_code_sequence SET value = value + 1 WHERE id = 1 RETURNING value",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(value)
+    }
+
+    async fn claim_pooled_code(&self) -> AppResult<Option<String>> {
+        // SQLite serializes writers, so two claims can't take the same row
+        let code = sqlx::query_scalar::<_, String>(
+            "DELETE FROM short_code_pool WHERE code = (SELECT code FROM short_code_pool LIMIT 1) RETURNING code",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(code)
+    }
+
+    async fn add_pooled_codes(&self, codes: &[String]) -> AppResult<u64> {
+        if codes.is_empty() {
+            return Ok(0);
+        }
+
+        let mut builder = QueryBuilder::<Sqlite>::new(
+            "INSERT OR IGNORE INTO short_code_pool (code) SELECT column1 FROM (",
+        );
+        builder.push_values(codes, |mut row, code| {
+            row.push_bind(code);
+        });
+        builder.push(") WHERE NOT EXISTS (SELECT 1 FROM urls WHERE short_code = column1)");
+
+        let result = builder.build().execute(&self.pool).await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn count_pooled_codes(&self) -> AppResult<i64> {
+        let count = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM short_code_pool")
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    async fn is_code_reserved(&self, code: &str) -> AppResult<bool> {
+        let count =
+            sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM reserved_codes WHERE code = ?1")
+                .bind(code)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(count > 0)
+    }
+
+    async fn reserve_code(&self, code: &str) -> AppResult<bool> {
+        let result = sqlx::query("INSERT OR IGNORE INTO reserved_codes (code) VALUES (?1)")
+            .bind(code)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn release_reserved_code(&self, code: &str) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM reserved_codes WHERE code = ?1")
+            .bind(code)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn list_reserved_codes(&self) -> AppResult<Vec<String>> {
+        let codes =
+            sqlx::query_scalar::<_, String>("SELECT code FROM reserved_codes ORDER BY code")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(codes)
+    }
+
+    async fn delete_url(&self, short_code: &str) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM urls WHERE short_code = ?1")
+            .bind(short_code)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn delete_url_for_owner(&self, short_code: &str, owner_id: i64) -> AppResult<bool> {
+        let result = sqlx::query("DELETE FROM urls WHERE short_code = ?1 AND owner_id = ?2")
+            .bind(short_code)
+            .bind(owner_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    async fn update_url(
+        &self,
+        short_code: &str,
+        original_url: Option<&str>,
+        expires_at: Option<Option<DateTime<Utc>>>,
+    ) -> AppResult<Option<UrlEntry>> {
+        let result = sqlx::query_as::<_, UrlEntry>(
+            r#"
+            UPDATE urls
+            SET original_url = COALESCE(?1, original_url),
+                expires_at = CASE WHEN ?2 THEN ?3 ELSE expires_at END
+            WHERE short_code = ?4
+            RETURNING *
+            "#,
+        )
+        .bind(original_url)
+        .bind(expires_at.is_some())
+        .bind(expires_at.flatten())
+        .bind(short_code)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    async fn delete_expired_urls(&self) -> AppResult<u64> {
+        let result =
+            sqlx::query("DELETE FROM urls WHERE expires_at IS NOT NULL AND expires_at < ?1")
+                .bind(Utc::now())
+                .execute(&self.pool)
+                .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn get_stats(&self) -> AppResult<Stats> {
+        self.query_stats(None).await
+    }
+
+    async fn get_stats_for_owner(&self, owner_id: i64) -> AppResult<Stats> {
+        self.query_stats(Some(owner_id)).await
+    }
+
+    async fn list_urls(&self, query: &UrlListQuery) -> AppResult<Vec<UrlEntry>> {
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT * FROM urls");
+        push_page(&mut builder, query, "REGEXP", Utc::now());
+
+        let results = builder
+            .build_query_as::<UrlEntry>()
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(results)
+    }
+
+    async fn count_urls(&self, filter: &UrlFilter) -> AppResult<i64> {
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM urls");
+        push_filter(&mut builder, filter, "REGEXP", Utc::now());
+
+        let count = builder
+            .build_query_scalar::<i64>()
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(count)
+    }
+
+    async fn create_user(&self, username: &str, password_hash: &str) -> AppResult<User> {
+        let result = sqlx::query_as::<_, User>(
+            "INSERT INTO users (username, password_hash) VALUES (?1, ?2) RETURNING *",
+        )
+        .bind(username)
+        .bind(password_hash)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    async fn get_user_by_username(&self, username: &str) -> AppResult<Option<User>> {
+        let result = sqlx::query_as::<_, User>("SELECT * FROM users WHERE username = ?1")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(result)
+    }
+
+    async fn get_user_by_id(&self, user_id: i64) -> AppResult<Option<User>> {
+        let result = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = ?1")
+            .bind(user_id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn storage() -> SqliteStorage {
+        let storage = SqliteStorage::new("sqlite::memory:", 1, 5).await.unwrap();
+        storage.run_migrations().await.unwrap();
+        storage
+    }
+
+    #[tokio::test]
+    async fn test_url_lifecycle() {
+        let storage = storage().await;
+        let user = storage.create_user("alice", "hash").await.unwrap();
+        assert!(!user.is_superuser);
+
+        let entry = storage
+            .create_url("abc123", "https://example.com", None, Some(user.id))
+            .await
+            .unwrap();
+        assert_eq!(entry.click_count, 0);
+        assert!(storage.short_code_exists("abc123").await.unwrap());
+        assert!(matches!(
+            storage
+                .create_url("abc123", "https://other.example", None, None)
+                .await,
+            Err(AppError::ShortCodeExists(_))
+        ));
+
+        let updated = storage
+            .update_url("abc123", Some("https://example.org"), None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.original_url, "https://example.org");
+        assert_eq!(updated.created_at, entry.created_at);
+
+        let expires_at = Utc::now() + chrono::Duration::hours(1);
+        let updated = storage
+            .update_url("abc123", None, Some(Some(expires_at)))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(updated.original_url, "https://example.org");
+        assert!(updated.expires_at.is_some());
+        let cleared = storage
+            .update_url("abc123", None, Some(None))
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(cleared.expires_at.is_none());
+
+        let owned = UrlFilter {
+            owner_id: Some(user.id),
+            ..UrlFilter::default()
+        };
+        assert_eq!(storage.count_urls(&owned).await.unwrap(), 1);
+        assert!(!storage
+            .delete_url_for_owner("abc123", user.id + 1)
+            .await
+            .unwrap());
+        assert!(storage
+            .delete_url_for_owner("abc123", user.id)
+            .await
+            .unwrap());
+        assert!(storage
+            .get_url_by_short_code("abc123")
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_short_code_sequence() {
+        let storage = storage().await;
+        assert_eq!(storage.next_short_code_sequence().await.unwrap(), 1);
+        assert_eq!(storage.next_short_code_sequence().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_short_code_pool() {
+        let storage = storage().await;
+        storage
+            .create_url("taken1", "https://example.com", None, None)
+            .await
+            .unwrap();
+
+        let codes = ["free01", "taken1", "free02", "free01"].map(String::from);
+        assert_eq!(storage.add_pooled_codes(&codes).await.unwrap(), 2);
+        assert_eq!(storage.count_pooled_codes().await.unwrap(), 2);
+
+        let first = storage.claim_pooled_code().await.unwrap().unwrap();
+        let second = storage.claim_pooled_code().await.unwrap().unwrap();
+        assert_ne!(first, second);
+        assert!(storage.claim_pooled_code().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_reserved_codes() {
+        let storage = storage().await;
+        assert!(storage.reserve_code("promo").await.unwrap());
+        assert!(!storage.reserve_code("promo").await.unwrap());
+        assert!(storage.is_code_reserved("promo").await.unwrap());
+        assert_eq!(storage.list_reserved_codes().await.unwrap(), ["promo"]);
+
+        assert!(storage.release_reserved_code("promo").await.unwrap());
+        assert!(!storage.is_code_reserved("promo").await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_existing_short_codes() {
+        let storage = storage().await;
+        storage
+            .create_url("launch", "https://example.com", None, None)
+            .await
+            .unwrap();
+
+        let codes = ["launch2", "launch", "get-launch"].map(String::from);
+        assert_eq!(
+            storage.existing_short_codes(&codes).await.unwrap(),
+            ["launch"]
+        );
+        assert!(storage.existing_short_codes(&[]).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_click_counts_and_stats() {
+        let storage = storage().await;
+        let now = Utc::now();
+        storage
+            .create_url("live", "https://example.com", None, None)
+            .await
+            .unwrap();
+        storage
+            .create_url(
+                "old",
+                "https://example.com",
+                Some(now - chrono::Duration::hours(1)),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let delta = |clicks, last_clicked_at| ClickCountDelta {
+            short_code: "live".to_string(),
+            clicks,
+            bot_clicks: 1,
+            last_clicked_at,
+        };
+        storage
+            .apply_click_counts(&[delta(3, Some(now))])
+            .await
+            .unwrap();
+        storage
+            .apply_click_counts(&[delta(2, Some(now - chrono::Duration::minutes(5)))])
+            .await
+            .unwrap();
+        storage.apply_click_counts(&[delta(0, None)]).await.unwrap();
+
+        let entry = storage
+            .get_url_by_short_code("live")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.click_count, 5);
+        assert_eq!(entry.bot_click_count, 3);
+        assert_eq!(entry.last_clicked_at, Some(now));
+
+        let stats = storage.get_stats().await.unwrap();
+        assert_eq!(
+            (stats.total_urls, stats.total_clicks, stats.bot_clicks),
+            (2, 5, 3)
+        );
+        assert_eq!((stats.active_urls, stats.expired_urls), (1, 1));
+
+        assert_eq!(storage.delete_expired_urls().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_click_timeseries_buckets() {
+        let storage = storage().await;
+        let entry = storage
+            .create_url("abc123", "https://example.com", None, None)
+            .await
+            .unwrap();
+        let day = |d: u32| {
+            DateTime::parse_from_rfc3339(&format!("2026-01-{:02}T12:00:00Z", d))
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        for (clicked_at, is_bot) in [
+            (day(10), false),
+            (day(10), false),
+            (day(12), false),
+            (day(12), true),
+        ] {
+            let mut event = ClickEvent::new(entry.id, None, None, None, None, is_bot);
+            event.clicked_at = clicked_at;
+            storage.insert_click_events(&[event]).await.unwrap();
+        }
+
+        let series = storage
+            .get_click_timeseries(entry.id, day(10), day(13), StatsInterval::Day)
+            .await
+            .unwrap();
+        let counts: Vec<i64> = series.iter().map(|(_, count)| *count).collect();
+        assert_eq!(counts, vec![2, 0, 1, 0]);
+        assert_eq!(series[0].0, StatsInterval::Day.truncate(day(10)));
+        assert_eq!(
+            storage
+                .count_bot_clicks(entry.id, day(10), day(13))
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_urls_keyset_pages() {
+        use crate::db::{SortOrder, UrlCursor, UrlSort};
+
+        let storage = storage().await;
+        let now = Utc::now();
+        for (code, clicks) in [("a", 3), ("b", 0), ("c", 3), ("d", 1), ("e", 0)] {
+            storage
+                .create_url(code, "https://example.com", None, None)
+                .await
+                .unwrap();
+            if clicks > 0 {
+                let delta = ClickCountDelta {
+                    short_code: code.to_string(),
+                    clicks,
+                    bot_clicks: 0,
+                    last_clicked_at: Some(now - chrono::Duration::minutes(clicks)),
+                };
+                storage.apply_click_counts(&[delta]).await.unwrap();
+            }
+        }
+
+        for sort in [
+            UrlSort::CreatedAt,
+            UrlSort::ClickCount,
+            UrlSort::LastClickedAt,
+        ] {
+            for order in [SortOrder::Asc, SortOrder::Desc] {
+                let mut query = UrlListQuery {
+                    sort,
+                    order,
+                    limit: 100,
+                    ..UrlListQuery::default()
+                };
+                let mut expected = storage.list_urls(&query).await.unwrap();
+                expected.sort_by(|a, b| query.compare(a, b));
+                let expected: Vec<i64> = expected.iter().map(|u| u.id).collect();
+
+                // Walk the same order two rows at a time
+                query.limit = 2;
+                let mut paged = Vec::new();
+                loop {
+                    let page = storage.list_urls(&query).await.unwrap();
+                    let Some(last) = page.last() else { break };
+                    query.after = Some(UrlCursor::after(last, sort, order));
+                    paged.extend(page.iter().map(|u| u.id));
+                }
+                assert_eq!(paged, expected, "{:?} {:?}", sort, order);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_urls_filters() {
+        use crate::db::UrlStatus;
+
+        let storage = storage().await;
+        let now = Utc::now();
+        storage
+            .create_url("promo1", "https://shop.example.com/sale", None, None)
+            .await
+            .unwrap();
+        storage
+            .create_url("docs", "https://example.org/50%_off", None, None)
+            .await
+            .unwrap();
+        storage
+            .create_url(
+                "old",
+                "https://example.com",
+                Some(now - chrono::Duration::hours(1)),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let codes = |filter: UrlFilter| {
+            let storage = storage.clone();
+            async move {
+                let query = UrlListQuery {
+                    filter,
+                    limit: 10,
+                    ..UrlListQuery::default()
+                };
+                let mut codes: Vec<String> = storage
+                    .list_urls(&query)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|u| u.short_code)
+                    .collect();
+                codes.sort();
+                codes
+            }
+        };
+
+        let domain = UrlFilter {
+            domain: Some("example.com".to_string()),
+            ..UrlFilter::default()
+        };
+        assert_eq!(codes(domain.clone()).await, ["old", "promo1"]);
+        assert_eq!(storage.count_urls(&domain).await.unwrap(), 2);
+
+        let search = |term: &str| UrlFilter {
+            search: Some(term.to_string()),
+            ..UrlFilter::default()
+        };
+        assert_eq!(codes(search("PROMO")).await, ["promo1"]);
+        assert_eq!(codes(search("%_")).await, ["docs"]);
+
+        let expired = UrlFilter {
+            status: Some(UrlStatus::Expired),
+            ..UrlFilter::default()
+        };
+        assert_eq!(codes(expired).await, ["old"]);
+    }
+
+    #[tokio::test]
+    async fn test_insert_click_events_skips_deleted_urls() {
+        let storage = storage().await;
+        let live = storage
+            .create_url("live", "https://example.com", None, None)
+            .await
+            .unwrap();
+        let gone = storage
+            .create_url("gone", "https://example.com", None, None)
+            .await
+            .unwrap();
+        storage.delete_url("gone").await.unwrap();
+
+        let events: Vec<ClickEvent> = [live.id, gone.id, live.id]
+            .into_iter()
+            .map(|url_id| ClickEvent::new(url_id, None, None, None, None, false))
+            .collect();
+        assert_eq!(storage.insert_click_events(&events).await.unwrap(), 2);
+    }
+}
@@ -3,6 +3,7 @@ use axum::{
     response::{IntoResponse, Json, Response},
 };
 use serde_json::json;
+use std::sync::Arc;
 use thiserror::Error;
 
 /// Main application error type
@@ -20,6 +21,9 @@ pub enum AppError {
     #[error("Redis pool error: {0}")]
     RedisPool(#[from] deadpool_redis::PoolError),
 
+    #[error("Cache unavailable")]
+    CacheUnavailable,
+
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
@@ -29,9 +33,15 @@ pub enum AppError {
     #[error("Invalid URL: {0}")]
     InvalidUrl(String),
 
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+
     #[error("Short code already exists: {0}")]
     ShortCodeExists(String),
 
+    #[error("Short code is reserved: {0}")]
+    ShortCodeReserved(String),
+
     #[error("Short code generation failed")]
     ShortCodeGenerationFailed,
 
@@ -49,15 +59,40 @@ pub enum AppError {
 
     #[error("User not found: {0}")]
     UserNotFound(String),
+
+    /// An error whose response lists alternatives the client can use instead
+    #[error("{error}")]
+    WithSuggestions {
+        error: Box<AppError>,
+        suggestions: Vec<String>,
+    },
+
+    /// One error reported to every caller of a coalesced lookup
+    #[error("{0}")]
+    Shared(Arc<AppError>),
 }
 
-/// Convert AppError to HTTP response
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, error_message, error_code) = match &self {
+impl AppError {
+    /// Attach suggestions (e.g. free short codes) to the error response
+    pub fn with_suggestions(self, suggestions: Vec<String>) -> Self {
+        AppError::WithSuggestions {
+            error: Box::new(self),
+            suggestions,
+        }
+    }
+
+    /// Status, client-facing message and error code of the response
+    fn response_parts(&self) -> (StatusCode, String, &'static str) {
+        match self {
+            AppError::Shared(error) => error.response_parts(),
+            AppError::WithSuggestions { error, .. } => error.response_parts(),
             AppError::UrlNotFound(_) => (StatusCode::NOT_FOUND, self.to_string(), "NOT_FOUND"),
             AppError::InvalidUrl(_) => (StatusCode::BAD_REQUEST, self.to_string(), "INVALID_URL"),
+            AppError::BadRequest(_) => (StatusCode::BAD_REQUEST, self.to_string(), "BAD_REQUEST"),
             AppError::ShortCodeExists(_) => (StatusCode::CONFLICT, self.to_string(), "CODE_EXISTS"),
+            AppError::ShortCodeReserved(_) => {
+                (StatusCode::CONFLICT, self.to_string(), "CODE_RESERVED")
+            }
             AppError::Database(e) => {
                 tracing::error!("Database error: {:?}", e);
                 (
@@ -90,6 +125,11 @@ impl IntoResponse for AppError {
                     "CACHE_ERROR",
                 )
             }
+            AppError::CacheUnavailable => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                self.to_string(),
+                "CACHE_UNAVAILABLE",
+            ),
             AppError::Serialization(e) => {
                 tracing::error!("Serialization error: {:?}", e);
                 (
@@ -117,12 +157,27 @@ impl IntoResponse for AppError {
                     "INTERNAL_ERROR",
                 )
             }
+        }
+    }
+}
+
+/// Convert AppError to HTTP response
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (error, suggestions) = match self {
+            AppError::WithSuggestions { error, suggestions } => (*error, Some(suggestions)),
+            other => (other, None),
         };
 
-        let body = json!({
+        let (status, error_message, error_code) = error.response_parts();
+
+        let mut body = json!({
             "error": error_code,
             "message": error_message,
         });
+        if let Some(suggestions) = suggestions {
+            body["suggestions"] = json!(suggestions);
+        }
 
         (status, Json(body)).into_response()
     }
@@ -1,5 +1,16 @@
-use crate::db::Repository;
-use tokio::sync::mpsc;
+use crate::analytics::ClickEvent;
+use crate::cache::SharedCache;
+use crate::db::{ClickCountDelta, Repository};
+use crate::error::AppResult;
+use crate::services::ShortCodeService;
+use chrono::Utc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::MissedTickBehavior;
 use tracing::{error, info, warn};
 
 /// Background job types
@@ -7,12 +18,12 @@ use tracing::{error, info, warn};
 pub enum Job {
     /// Increment click count for a URL
     IncrementClickCount { short_code: String },
-    /// Delete cache entry for a URL
-    #[expect(
-        dead_code,
-        reason = "planned feature: explicit cache invalidation via job queue"
-    )]
-    InvalidateCache { short_code: String },
+    /// Increment bot click count for a URL
+    IncrementBotClickCount { short_code: String },
+    /// Insert a per-click analytics event
+    RecordClick(ClickEvent),
+    /// Flush pending click counts and stop the worker
+    Shutdown,
 }
 
 /// Background worker configuration
@@ -22,6 +33,12 @@ pub struct WorkerConfig {
     pub max_retries: u32,
     /// Backoff duration between retries
     pub retry_delay_ms: u64,
+    /// How often coalesced click counts are written to the database
+    pub click_flush_interval_ms: u64,
+    /// Number of distinct short codes (or click events) pending before an early flush
+    pub click_batch_size: usize,
+    /// Click events kept while the database is slow or down; newer ones are dropped
+    pub max_pending_click_events: usize,
 }
 
 impl Default for WorkerConfig {
@@ -29,6 +46,68 @@ impl Default for WorkerConfig {
         Self {
             max_retries: 3,
             retry_delay_ms: 1000,
+            click_flush_interval_ms: 1000,
+            click_batch_size: 500,
+            max_pending_click_events: 50_000,
+        }
+    }
+}
+
+/// In-memory accumulator that coalesces click increments per short code
+#[derive(Debug, Default)]
+pub struct ClickBatcher {
+    pending: HashMap<String, ClickCountDelta>,
+}
+
+impl ClickBatcher {
+    /// Record one click (or bot hit) for a short code
+    pub fn add(&mut self, short_code: String, is_bot: bool) {
+        let delta = self
+            .pending
+            .entry(short_code)
+            .or_insert_with_key(|code| ClickCountDelta {
+                short_code: code.clone(),
+                clicks: 0,
+                bot_clicks: 0,
+                last_clicked_at: None,
+            });
+
+        if is_bot {
+            delta.bot_clicks += 1;
+        } else {
+            delta.clicks += 1;
+            delta.last_clicked_at = Some(Utc::now());
+        }
+    }
+
+    /// Number of distinct short codes with pending increments
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Whether there is nothing to flush
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Take all pending increments, leaving the batcher empty
+    pub fn drain(&mut self) -> Vec<ClickCountDelta> {
+        self.pending.drain().map(|(_, delta)| delta).collect()
+    }
+
+    /// Put back increments from a failed flush so they are retried later
+    pub fn restore(&mut self, deltas: Vec<ClickCountDelta>) {
+        for delta in deltas {
+            match self.pending.get_mut(&delta.short_code) {
+                Some(existing) => {
+                    existing.clicks += delta.clicks;
+                    existing.bot_clicks += delta.bot_clicks;
+                    existing.last_clicked_at = existing.last_clicked_at.max(delta.last_clicked_at);
+                }
+                None => {
+                    self.pending.insert(delta.short_code.clone(), delta);
+                }
+            }
         }
     }
 }
@@ -36,104 +115,383 @@ impl Default for WorkerConfig {
 /// Background job worker
 pub struct Worker {
     repository: Repository,
-    receiver: mpsc::UnboundedReceiver<Job>,
+    receiver: mpsc::Receiver<Job>,
     config: WorkerConfig,
+    clicks: ClickBatcher,
+    /// Set by a failed flush; early flushes wait for the next tick instead of retrying per click
+    click_flush_failed: bool,
+    events: Vec<ClickEvent>,
+    /// Like `click_flush_failed`, for click events
+    event_flush_failed: bool,
+    /// Events dropped since the last flush because the buffer was full
+    dropped_events: usize,
 }
 
 impl Worker {
     /// Create a new worker
-    pub fn new(repository: Repository, receiver: mpsc::UnboundedReceiver<Job>) -> Self {
+    pub fn new(repository: Repository, receiver: mpsc::Receiver<Job>) -> Self {
         Self {
             repository,
             receiver,
             config: WorkerConfig::default(),
+            clicks: ClickBatcher::default(),
+            click_flush_failed: false,
+            events: Vec::new(),
+            event_flush_failed: false,
+            dropped_events: 0,
         }
     }
 
     /// Set worker configuration
-    #[expect(
-        dead_code,
-        reason = "builder method for configuring worker retry behavior"
-    )]
     pub fn with_config(mut self, config: WorkerConfig) -> Self {
         self.config = config;
         self
     }
 
-    /// Run the worker - processes jobs until channel closes
+    /// Run the worker - processes jobs until shutdown or the channel closes
+    ///
+    /// Click increments are coalesced in memory and click events buffered; both
+    /// are written in batches, either every `click_flush_interval_ms` or once
+    /// `click_batch_size` codes or events are pending. Pending work is always
+    /// flushed before the worker stops.
     pub async fn run(mut self) {
         info!("Background worker started");
 
-        while let Some(job) = self.receiver.recv().await {
-            self.process_job(job).await;
+        let mut ticker =
+            tokio::time::interval(Duration::from_millis(self.config.click_flush_interval_ms));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                job = self.receiver.recv() => match job {
+                    Some(Job::Shutdown) | None => break,
+                    Some(Job::IncrementClickCount { short_code }) => {
+                        self.clicks.add(short_code, false);
+                        self.flush_if_full().await;
+                    }
+                    Some(Job::IncrementBotClickCount { short_code }) => {
+                        self.clicks.add(short_code, true);
+                        self.flush_if_full().await;
+                    }
+                    Some(Job::RecordClick(event)) => {
+                        self.buffer_event(event);
+                        self.flush_events_if_full().await;
+                    }
+                },
+                _ = ticker.tick() => {
+                    self.flush_clicks().await;
+                    self.flush_events().await;
+                }
+            }
         }
 
+        self.flush_with_retries().await;
+
         info!("Background worker stopped");
     }
 
-    /// Process a single job with retries
-    async fn process_job(&self, job: Job) {
-        let mut retries = 0;
+    /// Flush early once the batch size threshold is reached, unless the last flush failed
+    async fn flush_if_full(&mut self) {
+        if !self.click_flush_failed && self.clicks.len() >= self.config.click_batch_size {
+            self.flush_clicks().await;
+        }
+    }
+
+    /// Write pending click counts once; failed batches are kept for the next flush
+    async fn flush_clicks(&mut self) -> bool {
+        if self.clicks.is_empty() {
+            return true;
+        }
+
+        let deltas = self.clicks.drain();
+        let result = self.repository.apply_click_counts(&deltas).await;
+        self.click_flush_failed = result.is_err();
+        match result {
+            Ok(_) => true,
+            Err(e) => {
+                warn!(
+                    "Failed to flush click counts for {} URL(s), will retry: {}",
+                    deltas.len(),
+                    e
+                );
+                self.clicks.restore(deltas);
+                false
+            }
+        }
+    }
+
+    /// Buffer a click event, dropping it once `max_pending_click_events` are waiting
+    fn buffer_event(&mut self, event: ClickEvent) {
+        if self.events.len() >= self.config.max_pending_click_events {
+            self.dropped_events += 1;
+        } else {
+            self.events.push(event);
+        }
+    }
+
+    /// Like [`Worker::flush_if_full`], for click events
+    async fn flush_events_if_full(&mut self) {
+        if !self.event_flush_failed && self.events.len() >= self.config.click_batch_size {
+            self.flush_events().await;
+        }
+    }
+
+    /// Write buffered click events once; failed batches are kept for the next flush
+    async fn flush_events(&mut self) -> bool {
+        if self.dropped_events > 0 {
+            warn!(
+                "Dropped {} click event(s) while the event buffer was full",
+                self.dropped_events
+            );
+            self.dropped_events = 0;
+        }
+        if self.events.is_empty() {
+            return true;
+        }
+
+        let events = std::mem::take(&mut self.events);
+        let result = self.repository.insert_click_events(&events).await;
+        self.event_flush_failed = result.is_err();
+        match result {
+            Ok(_) => true,
+            Err(e) => {
+                warn!(
+                    "Failed to write {} click event(s), will retry: {}",
+                    events.len(),
+                    e
+                );
+                self.events = events;
+                false
+            }
+        }
+    }
+
+    /// Final flush on shutdown, retried so pending counts and events are not lost
+    async fn flush_with_retries(&mut self) {
+        for attempt in 0..=self.config.max_retries {
+            let clicks_flushed = self.flush_clicks().await;
+            if self.flush_events().await && clicks_flushed {
+                return;
+            }
+
+            if attempt < self.config.max_retries {
+                tokio::time::sleep(Duration::from_millis(self.config.retry_delay_ms)).await;
+            }
+        }
+
+        error!(
+            "Dropping click counts for {} URL(s) and {} click event(s) after {} retries",
+            self.clicks.len(),
+            self.events.len(),
+            self.config.max_retries
+        );
+    }
+}
+
+/// Periodic task that moves Redis-side click counters into Postgres
+///
+/// Used when `CLICK_COUNTER_MODE=redis`. Counters are popped atomically, so
+/// several instances can run the sync concurrently without double counting.
+pub struct ClickCounterSync {
+    cache: SharedCache,
+    repository: Repository,
+    interval: Duration,
+    batch_size: usize,
+}
+
+impl ClickCounterSync {
+    /// Create a sync task that drains up to `batch_size` codes per query
+    pub fn new(
+        cache: SharedCache,
+        repository: Repository,
+        interval: Duration,
+        batch_size: usize,
+    ) -> Self {
+        Self {
+            cache,
+            repository,
+            interval,
+            batch_size,
+        }
+    }
+
+    /// Sync every `interval` until `shutdown` fires, then sync once more
+    pub async fn run(self, mut shutdown: oneshot::Receiver<()>) {
+        info!("Click counter sync started");
+
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
 
         loop {
-            match self.execute_job(&job).await {
-                Ok(_) => {
-                    // Job succeeded, move to next
-                    break;
-                }
-                Err(e) if retries < self.config.max_retries => {
-                    retries += 1;
-                    let delay = std::time::Duration::from_millis(self.config.retry_delay_ms);
-                    warn!(
-                        "Job failed (attempt {}/{}), retrying in {:?}: {:?}",
-                        retries, self.config.max_retries, delay, job
-                    );
-                    tokio::time::sleep(delay).await;
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = ticker.tick() => self.sync().await,
+            }
+        }
+
+        self.sync().await;
+
+        info!("Click counter sync stopped");
+    }
+
+    /// Apply all pending counters, one batch at a time
+    async fn sync(&self) {
+        loop {
+            let deltas = match self.cache.take_click_counters(self.batch_size).await {
+                Ok(deltas) => deltas,
+                Err(e) => {
+                    warn!("Failed to read click counters from Redis: {}", e);
+                    return;
                 }
-                Err(_e) => {
-                    // Job failed after all retries
+            };
+
+            if deltas.is_empty() {
+                return;
+            }
+
+            if let Err(e) = self.repository.apply_click_counts(&deltas).await {
+                warn!(
+                    "Failed to sync click counts for {} URL(s), will retry: {}",
+                    deltas.len(),
+                    e
+                );
+                if let Err(e) = self.cache.restore_click_counters(&deltas).await {
                     error!(
-                        "Job failed after {} retries: {:?}",
-                        self.config.max_retries, job
+                        "Dropping click counts for {} URL(s), could not restore them: {}",
+                        deltas.len(),
+                        e
                     );
-                    break;
                 }
+                return;
+            }
+
+            if deltas.len() < self.batch_size {
+                return;
             }
         }
     }
+}
+
+/// Codes generated per insert when refilling the pool
+const CODE_POOL_REFILL_BATCH: usize = 500;
+
+/// Periodic task that keeps the short code pool topped up
+///
+/// Used when `SHORT_CODE_STRATEGY=pool`. Whenever the pool drops below
+/// `low_water` it is refilled up to `target` with random codes. Codes that are
+/// already pooled or in use are skipped by the insert, so several instances can
+/// refill concurrently.
+pub struct CodePoolRefiller {
+    repository: Repository,
+    short_codes: ShortCodeService,
+    interval: Duration,
+    target: usize,
+    low_water: usize,
+}
 
-    /// Execute a job without retries
-    async fn execute_job(&self, job: &Job) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        match job {
-            Job::IncrementClickCount { short_code } => {
-                self.repository.increment_click_count(short_code).await?;
-                Ok(())
+impl CodePoolRefiller {
+    /// Create a refill task that checks the pool every `interval`
+    pub fn new(
+        repository: Repository,
+        short_codes: ShortCodeService,
+        interval: Duration,
+        target: usize,
+        low_water: usize,
+    ) -> Self {
+        Self {
+            repository,
+            short_codes,
+            interval,
+            target,
+            low_water,
+        }
+    }
+
+    /// Refill every `interval` (starting immediately) until `shutdown` fires
+    pub async fn run(self, mut shutdown: oneshot::Receiver<()>) {
+        info!("Short code pool refiller started");
+
+        let mut ticker = tokio::time::interval(self.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = &mut shutdown => break,
+                _ = ticker.tick() => {
+                    if let Err(e) = self.refill().await {
+                        warn!("Failed to refill short code pool: {}", e);
+                    }
+                }
             }
-            Job::InvalidateCache { short_code: _ } => {
-                // Cache invalidation is handled separately by the cache layer
-                // This job type is for future use or coordination
-                Ok(())
+        }
+
+        info!("Short code pool refiller stopped");
+    }
+
+    /// Top the pool up to `target` if it is below `low_water`, returning how many codes were added
+    pub async fn refill(&self) -> AppResult<u64> {
+        let size = self.repository.count_pooled_codes().await?.max(0) as usize;
+        if size >= self.low_water {
+            return Ok(0);
+        }
+
+        let mut missing = self.target.saturating_sub(size);
+        let mut added = 0;
+        while missing > 0 {
+            let codes: Vec<String> = (0..missing.min(CODE_POOL_REFILL_BATCH))
+                .map(|_| self.short_codes.random_code())
+                .filter(|code| !self.short_codes.is_blocked(code))
+                .collect();
+            let inserted = self.repository.add_pooled_codes(&codes).await?;
+            if inserted == 0 {
+                // Every candidate collided; the code space is nearly full
+                break;
             }
+            added += inserted;
+            missing = missing.saturating_sub(inserted as usize);
         }
+
+        info!("Refilled short code pool with {} code(s)", added);
+        Ok(added)
     }
 }
 
+/// Jobs queued for the worker before new ones are dropped
+const JOB_QUEUE_CAPACITY: usize = 100_000;
+
 /// Job sender - used to submit jobs to the worker
 #[derive(Clone)]
 pub struct JobSender {
-    sender: mpsc::UnboundedSender<Job>,
+    sender: mpsc::Sender<Job>,
+    /// Jobs dropped because the queue was full
+    dropped: Arc<AtomicU64>,
 }
 
 impl JobSender {
     /// Create a new job sender
-    pub fn new(sender: mpsc::UnboundedSender<Job>) -> Self {
-        Self { sender }
+    pub fn new(sender: mpsc::Sender<Job>) -> Self {
+        Self {
+            sender,
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
     }
 
     /// Submit a job to be processed asynchronously
+    ///
+    /// The job is dropped if the queue is full, so a worker stuck on a slow
+    /// database cannot make the queue grow without bound.
     pub fn send(&self, job: Job) {
-        if self.sender.send(job).is_err() {
-            error!("Failed to send job to worker - channel may be closed");
+        match self.sender.try_send(job) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                if dropped % 1000 == 1 {
+                    warn!("Job queue is full, {} job(s) dropped so far", dropped);
+                }
+            }
+            Err(TrySendError::Closed(_)) => {
+                error!("Failed to send job to worker - channel may be closed");
+            }
         }
     }
 
@@ -142,19 +500,32 @@ impl JobSender {
         self.send(Job::IncrementClickCount { short_code });
     }
 
-    /// Submit an invalidate cache job
-    #[expect(
-        dead_code,
-        reason = "planned feature: explicit cache invalidation via job queue"
-    )]
-    pub fn invalidate_cache(&self, short_code: String) {
-        self.send(Job::InvalidateCache { short_code });
+    /// Submit an increment bot click count job
+    pub fn increment_bot_click_count(&self, short_code: String) {
+        self.send(Job::IncrementBotClickCount { short_code });
+    }
+
+    /// Submit a click event recording job
+    pub fn record_click(&self, event: ClickEvent) {
+        self.send(Job::RecordClick(event));
+    }
+
+    /// Ask the worker to flush pending work and stop, waiting for queue space if needed
+    pub async fn shutdown(&self) {
+        if self.sender.send(Job::Shutdown).await.is_err() {
+            error!("Failed to send job to worker - channel may be closed");
+        }
     }
 }
 
 /// Create a new job sender and receiver pair
-pub fn create_job_channel() -> (JobSender, mpsc::UnboundedReceiver<Job>) {
-    let (sender, receiver) = mpsc::unbounded_channel();
+pub fn create_job_channel() -> (JobSender, mpsc::Receiver<Job>) {
+    create_job_channel_with_capacity(JOB_QUEUE_CAPACITY)
+}
+
+/// Create a job channel that holds at most `capacity` queued jobs
+pub fn create_job_channel_with_capacity(capacity: usize) -> (JobSender, mpsc::Receiver<Job>) {
+    let (sender, receiver) = mpsc::channel(capacity);
     (JobSender::new(sender), receiver)
 }
 
@@ -172,4 +543,166 @@ mod tests {
 
         assert!(receiver.try_recv().is_ok());
     }
+
+    #[test]
+    fn test_job_sender_drops_when_queue_full() {
+        let (sender, mut receiver) = create_job_channel_with_capacity(1);
+
+        sender.increment_click_count("first".to_string());
+        sender.increment_click_count("second".to_string());
+
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(Job::IncrementClickCount { short_code }) if short_code == "first"
+        ));
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_click_events_buffered_and_bounded() {
+        let storage: Repository = Arc::new(crate::db::MemoryStorage::default());
+        let entry = storage
+            .create_url("abc123", "https://example.com", None, None)
+            .await
+            .unwrap();
+        let (_sender, receiver) = create_job_channel();
+        let mut worker = Worker::new(storage.clone(), receiver).with_config(WorkerConfig {
+            max_pending_click_events: 2,
+            ..WorkerConfig::default()
+        });
+
+        for _ in 0..3 {
+            worker.buffer_event(ClickEvent::new(entry.id, None, None, None, None, false));
+        }
+        assert_eq!(worker.events.len(), 2);
+        assert_eq!(worker.dropped_events, 1);
+
+        assert!(worker.flush_events().await);
+        assert!(worker.events.is_empty());
+        let now = Utc::now();
+        let series = storage
+            .get_click_timeseries(
+                entry.id,
+                now - chrono::Duration::hours(1),
+                now + chrono::Duration::hours(1),
+                crate::analytics::StatsInterval::Hour,
+            )
+            .await
+            .unwrap();
+        assert_eq!(series.iter().map(|(_, count)| count).sum::<i64>(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_code_pool_refill_respects_low_water() {
+        use crate::config::{ClickCounterMode, ShortCodeAlphabet, ShortCodeStrategy, UrlConfig};
+        use std::sync::Arc;
+
+        let repository: Repository = Arc::new(crate::db::MemoryStorage::default());
+        let short_codes = ShortCodeService::new(&UrlConfig {
+            short_code_length: 8,
+            base_url: "http://localhost:3000".to_string(),
+            default_expiry_hours: 720,
+            short_code_max_attempts: 10,
+            short_code_strategy: ShortCodeStrategy::Pool,
+            short_code_salt: String::new(),
+            short_code_pool_size: 1200,
+            short_code_pool_low_water: 100,
+            short_code_alphabet: ShortCodeAlphabet::Base62,
+            short_code_lowercase: false,
+            short_code_word_filter_enabled: true,
+            short_code_blocked_words: Vec::new(),
+            reserved_codes: Vec::new(),
+            strict_url_validation: true,
+            click_counter_mode: ClickCounterMode::Worker,
+        });
+        let refiller = CodePoolRefiller::new(
+            repository.clone(),
+            short_codes,
+            Duration::from_secs(1),
+            1200,
+            100,
+        );
+
+        assert_eq!(refiller.refill().await.unwrap(), 1200);
+        assert_eq!(repository.count_pooled_codes().await.unwrap(), 1200);
+
+        // Above the low-water mark nothing is added
+        for _ in 0..1000 {
+            repository.claim_pooled_code().await.unwrap();
+        }
+        assert_eq!(refiller.refill().await.unwrap(), 0);
+
+        for _ in 0..150 {
+            repository.claim_pooled_code().await.unwrap();
+        }
+        assert_eq!(refiller.refill().await.unwrap(), 1150);
+        assert_eq!(repository.count_pooled_codes().await.unwrap(), 1200);
+    }
+
+    #[test]
+    fn test_click_batcher_coalesces_per_code() {
+        let mut batcher = ClickBatcher::default();
+
+        batcher.add("abc".to_string(), false);
+        batcher.add("abc".to_string(), false);
+        batcher.add("abc".to_string(), true);
+        batcher.add("xyz".to_string(), true);
+
+        assert_eq!(batcher.len(), 2);
+
+        let mut deltas = batcher.drain();
+        deltas.sort_by(|a, b| a.short_code.cmp(&b.short_code));
+
+        assert!(batcher.is_empty());
+        assert_eq!(deltas[0].clicks, 2);
+        assert_eq!(deltas[0].bot_clicks, 1);
+        assert!(deltas[0].last_clicked_at.is_some());
+        assert_eq!(deltas[1].clicks, 0);
+        assert_eq!(deltas[1].bot_clicks, 1);
+        assert!(deltas[1].last_clicked_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_failed_flush_waits_for_next_tick() {
+        use std::sync::Arc;
+
+        // Without migrations every click count write fails
+        let repository: Repository = Arc::new(
+            crate::db::SqliteStorage::new("sqlite::memory:", 1, 5)
+                .await
+                .unwrap(),
+        );
+        let (_sender, receiver) = create_job_channel();
+        let mut worker = Worker::new(repository.clone(), receiver).with_config(WorkerConfig {
+            click_batch_size: 1,
+            ..WorkerConfig::default()
+        });
+
+        worker.clicks.add("abc".to_string(), false);
+        worker.flush_if_full().await;
+        assert_eq!(worker.clicks.len(), 1);
+
+        // Once the database is back, clicks over the threshold still wait for the tick
+        repository.run_migrations().await.unwrap();
+        worker.clicks.add("xyz".to_string(), false);
+        worker.flush_if_full().await;
+        assert_eq!(worker.clicks.len(), 2);
+
+        assert!(worker.flush_clicks().await);
+        assert!(worker.clicks.is_empty());
+    }
+
+    #[test]
+    fn test_click_batcher_restore_merges() {
+        let mut batcher = ClickBatcher::default();
+        batcher.add("abc".to_string(), false);
+        let failed = batcher.drain();
+
+        batcher.add("abc".to_string(), false);
+        batcher.restore(failed);
+
+        let deltas = batcher.drain();
+        assert_eq!(deltas.len(), 1);
+        assert_eq!(deltas[0].clicks, 2);
+    }
 }
@@ -0,0 +1,25 @@
+//! rustLink - a URL shortener built on Axum.
+//!
+//! The `rustlink` binary is a thin CLI over this library; exposing the modules
+//! lets integration tests build the real router against in-memory backends.
+
+pub mod admin;
+pub mod analytics;
+pub mod auth;
+pub mod bot_detection;
+pub mod cache;
+pub mod circuit_breaker;
+pub mod config;
+pub mod db;
+pub mod error;
+pub mod jobs;
+pub mod middleware;
+pub mod middleware_impls;
+pub mod models;
+pub mod routes;
+pub mod server;
+pub mod services;
+pub mod single_flight;
+pub mod state;
+pub mod user_agent;
+pub mod util;
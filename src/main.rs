@@ -1,23 +1,8 @@
-mod admin;
-mod auth;
-mod cache;
-mod config;
-mod db;
-mod error;
-mod jobs;
-mod middleware;
-mod middleware_impls;
-mod models;
-mod routes;
-mod server;
-mod services;
-mod state;
-mod util;
-
-use crate::admin::AdminCommands;
-use crate::config::Config;
-use crate::error::AppResult;
 use clap::{Parser, Subcommand};
+use rustlink::admin::{self, AdminCommands};
+use rustlink::config::Config;
+use rustlink::error::AppResult;
+use rustlink::server;
 use tracing::Level;
 use tracing_subscriber::EnvFilter;
 
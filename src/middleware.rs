@@ -1,5 +1,3 @@
-use crate::db::Repository;
-use crate::error::AppError;
 use sqlx::FromRow;
 
 /// User model from database
@@ -9,59 +7,7 @@ pub struct User {
     pub username: String,
     pub password_hash: String,
     pub is_active: bool,
-}
-
-/// Repository extension for user operations
-impl Repository {
-    /// Create a new user
-    #[expect(
-        dead_code,
-        reason = "API extension: user registration endpoint planned"
-    )]
-    pub async fn create_user(&self, username: &str, password_hash: &str) -> Result<User, AppError> {
-        let result = sqlx::query_as::<_, User>(
-            r#"
-            INSERT INTO users (username, password_hash)
-            VALUES ($1, $2)
-            RETURNING *
-            "#,
-        )
-        .bind(username)
-        .bind(password_hash)
-        .fetch_one(&self.pool)
-        .await?;
-
-        Ok(result)
-    }
-
-    /// Get a user by username
-    pub async fn get_user_by_username(&self, username: &str) -> Result<Option<User>, AppError> {
-        let result = sqlx::query_as::<_, User>(
-            r#"
-            SELECT * FROM users WHERE username = $1
-            "#,
-        )
-        .bind(username)
-        .fetch_optional(&self.pool)
-        .await?;
-
-        Ok(result)
-    }
-
-    /// Get a user by ID
-    #[expect(dead_code, reason = "API extension: user profile endpoint planned")]
-    pub async fn get_user_by_id(&self, user_id: i64) -> Result<Option<User>, AppError> {
-        let result = sqlx::query_as::<_, User>(
-            r#"
-            SELECT * FROM users WHERE id = $1
-            "#,
-        )
-        .bind(user_id)
-        .fetch_optional(&self.pool)
-        .await?;
-
-        Ok(result)
-    }
+    pub is_superuser: bool,
 }
 
 #[cfg(test)]
@@ -75,6 +21,7 @@ mod tests {
             username: "testuser".to_string(),
             password_hash: "hash".to_string(),
             is_active: true,
+            is_superuser: false,
         };
         assert_eq!(user.id, 1);
         assert_eq!(user.username, "testuser");
@@ -33,6 +33,12 @@ impl RequestId {
     }
 }
 
+impl Default for RequestId {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl From<String> for RequestId {
     fn from(s: String) -> Self {
         Self(s)
@@ -22,10 +22,52 @@ pub struct UrlEntry {
     pub created_at: DateTime<Utc>,
     /// When the short URL expires (if set)
     pub expires_at: Option<DateTime<Utc>>,
-    /// Number of times the short URL has been accessed
+    /// Number of times the short URL has been accessed by humans
     pub click_count: i64,
     /// When the URL was last accessed
     pub last_clicked_at: Option<DateTime<Utc>>,
+    /// Number of hits from bots, crawlers and link previewers
+    #[serde(default)]
+    pub bot_click_count: i64,
+    /// ID of the user who created the URL (None for anonymous links)
+    pub owner_id: Option<i64>,
+}
+
+/// Immutable routing data for a short URL, as stored in the cache
+///
+/// Click counts and other frequently changing fields are left out so that
+/// redirects never have to invalidate the cached entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedUrl {
+    /// Unique database ID
+    pub id: i64,
+    /// Short code for the URL
+    pub short_code: String,
+    /// Redirect destination
+    pub original_url: String,
+    /// When the short URL expires (if set)
+    pub expires_at: Option<DateTime<Utc>>,
+    /// ID of the user who created the URL
+    pub owner_id: Option<i64>,
+}
+
+impl CachedUrl {
+    /// Whether the link has expired at `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at < now)
+    }
+}
+
+impl From<&UrlEntry> for CachedUrl {
+    fn from(entry: &UrlEntry) -> Self {
+        Self {
+            id: entry.id,
+            short_code: entry.short_code.clone(),
+            original_url: entry.original_url.clone(),
+            expires_at: entry.expires_at,
+            owner_id: entry.owner_id,
+        }
+    }
 }
 
 /// Request to create a short URL
@@ -51,6 +93,28 @@ pub struct CreateUrlRequest {
     pub custom_code: Option<String>,
 }
 
+/// Request to update an existing short URL
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct UpdateUrlRequest {
+    /// New destination URL (must be a valid HTTP/HTTPS URL)
+    #[validate(url(message = "Must be a valid URL"))]
+    #[schema(example = "https://example.com/new/path")]
+    pub url: Option<String>,
+
+    /// New expiry time in hours from now (1-87600)
+    #[validate(range(
+        min = 1,
+        max = 87600,
+        message = "Expiry must be between 1 and 87600 hours"
+    ))]
+    #[schema(example = 720)]
+    pub expiry_hours: Option<i64>,
+
+    /// Remove the expiry so the URL never expires
+    #[serde(default)]
+    pub clear_expiry: bool,
+}
+
 /// Response after creating a short URL
 #[derive(Debug, Serialize, ToSchema)]
 pub struct CreateUrlResponse {
@@ -67,6 +131,23 @@ pub struct CreateUrlResponse {
     pub expires_at: Option<DateTime<Utc>>,
 }
 
+/// Whether a custom code is free, with alternatives if it isn't
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CodeAvailabilityResponse {
+    /// The requested code
+    #[schema(example = "launch")]
+    pub code: String,
+    /// Whether a link can be created with this code
+    pub available: bool,
+    /// Why the code is unavailable: `taken` or `reserved`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schema(example = "taken")]
+    pub reason: Option<String>,
+    /// Free variants of the code, most similar first
+    #[schema(example = json!(["launch2", "launch-2", "launch26"]))]
+    pub suggestions: Vec<String>,
+}
+
 /// Response for URL info
 #[derive(Debug, Serialize, ToSchema)]
 pub struct UrlInfoResponse {
@@ -80,11 +161,17 @@ pub struct UrlInfoResponse {
     pub created_at: DateTime<Utc>,
     /// When the URL expires (if set)
     pub expires_at: Option<DateTime<Utc>>,
-    /// Number of times accessed
+    /// Number of times accessed (excluding bots)
     #[schema(example = 42)]
     pub click_count: i64,
     /// Last access time
     pub last_clicked_at: Option<DateTime<Utc>>,
+    /// Number of hits from bots, crawlers and link previewers
+    #[schema(example = 3)]
+    pub bot_clicks: i64,
+    /// Approximate distinct visitors over the retention window (None when not tracked)
+    #[schema(example = 30)]
+    pub unique_visitors: Option<i64>,
 }
 
 impl From<UrlEntry> for UrlInfoResponse {
@@ -96,6 +183,8 @@ impl From<UrlEntry> for UrlInfoResponse {
             expires_at: entry.expires_at,
             click_count: entry.click_count,
             last_clicked_at: entry.last_clicked_at,
+            bot_clicks: entry.bot_click_count,
+            unique_visitors: None,
         }
     }
 }
@@ -116,6 +205,8 @@ pub struct PaginationMeta {
     pub has_next: bool,
     /// Whether there are previous items
     pub has_prev: bool,
+    /// Opaque cursor for the next page (None on the last page)
+    pub next_cursor: Option<String>,
 }
 
 impl PaginationMeta {
@@ -129,6 +220,7 @@ impl PaginationMeta {
             offset,
             has_next,
             has_prev,
+            next_cursor: None,
         }
     }
 }
@@ -147,6 +239,14 @@ impl<T: ToSchema> PaginatedResponse<T> {
         let pagination = PaginationMeta::new(total, limit, offset);
         Self { data, pagination }
     }
+
+    /// Set the cursor for the next page, for pages fetched by cursor
+    pub fn with_next_cursor(mut self, next_cursor: Option<String>, after_cursor: bool) -> Self {
+        self.pagination.has_next = next_cursor.is_some();
+        self.pagination.has_prev |= after_cursor;
+        self.pagination.next_cursor = next_cursor;
+        self
+    }
 }
 
 /// Statistics summary
@@ -155,9 +255,15 @@ pub struct StatsResponse {
     /// Total number of URLs created
     #[schema(example = 1000)]
     pub total_urls: i64,
-    /// Total number of clicks across all URLs
+    /// Total number of clicks across all URLs (excluding bots)
     #[schema(example = 15000)]
     pub total_clicks: i64,
+    /// Total number of bot/crawler hits across all URLs
+    #[schema(example = 420)]
+    pub bot_clicks: i64,
+    /// Approximate distinct visitors over the retention window (None when not tracked)
+    #[schema(example = 9000)]
+    pub unique_visitors: Option<i64>,
     /// Number of non-expired URLs
     #[schema(example = 950)]
     pub active_urls: i64,
@@ -166,6 +272,81 @@ pub struct StatsResponse {
     pub expired_urls: i64,
 }
 
+/// Click count for a single time bucket
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TimeBucket {
+    /// Start of the bucket
+    pub bucket_start: DateTime<Utc>,
+    /// Number of clicks in the bucket
+    #[schema(example = 12)]
+    pub clicks: i64,
+}
+
+/// Unique visitors for a single day
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DailyUniqueVisitors {
+    /// The day (UTC)
+    pub date: chrono::NaiveDate,
+    /// Approximate distinct visitors on that day
+    #[schema(example = 25)]
+    pub visitors: i64,
+}
+
+/// Click count for a single value of a grouped dimension
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DimensionCount {
+    /// Dimension value (None when the click carried no value, e.g. direct traffic)
+    #[schema(example = "https://news.ycombinator.com/")]
+    pub value: Option<String>,
+    /// Number of clicks with this value
+    #[schema(example = 7)]
+    pub clicks: i64,
+}
+
+impl From<(Option<String>, i64)> for DimensionCount {
+    fn from((value, clicks): (Option<String>, i64)) -> Self {
+        DimensionCount { value, clicks }
+    }
+}
+
+/// Time-series analytics for a single short URL
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UrlAnalyticsResponse {
+    /// The short code
+    #[schema(example = "abc123XY")]
+    pub short_code: String,
+    /// Start of the reported range (inclusive)
+    pub from: DateTime<Utc>,
+    /// End of the reported range (exclusive)
+    pub to: DateTime<Utc>,
+    /// Bucket size used for the time series
+    #[schema(example = "day")]
+    pub interval: String,
+    /// Total clicks in the range (excluding bots)
+    #[schema(example = 120)]
+    pub total_clicks: i64,
+    /// Bot/crawler hits in the range
+    #[schema(example = 9)]
+    pub bot_clicks: i64,
+    /// Approximate distinct visitors in the range (None when not tracked)
+    #[schema(example = 80)]
+    pub unique_visitors: Option<i64>,
+    /// Approximate distinct visitors per day in the range
+    pub daily_unique_visitors: Vec<DailyUniqueVisitors>,
+    /// Click counts per bucket, including empty buckets
+    pub timeseries: Vec<TimeBucket>,
+    /// Most common referrers
+    pub top_referrers: Vec<DimensionCount>,
+    /// Most common countries (ISO 3166-1 alpha-2)
+    pub top_countries: Vec<DimensionCount>,
+    /// Clicks per browser family
+    pub browsers: Vec<DimensionCount>,
+    /// Clicks per operating system family
+    pub operating_systems: Vec<DimensionCount>,
+    /// Clicks per device class (desktop, mobile, tablet, bot)
+    pub devices: Vec<DimensionCount>,
+}
+
 /// Error response format (for OpenAPI documentation)
 #[derive(Debug, Serialize, ToSchema)]
 #[allow(dead_code)] // Used for OpenAPI schema generation
@@ -176,4 +357,7 @@ pub struct ErrorResponse {
     /// Human-readable error message
     #[schema(example = "URL not found: abc123")]
     pub message: String,
+    /// Free alternatives, for conflicting custom codes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestions: Option<Vec<String>>,
 }
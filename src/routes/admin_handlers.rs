@@ -6,7 +6,9 @@ use axum::response::{IntoResponse, Json};
 use std::sync::Arc;
 
 use super::AppState;
-use super::helpers::extract_claims;
+use super::helpers::{extract_claims, invalidate_cached_url, owner_scope, unique_visitors};
+use crate::cache::UniqueScope;
+use crate::db::{is_valid_domain, UrlCursor, UrlFilter, UrlListQuery};
 use super::types::ListUrlsQuery;
 
 /// Delete a short URL (requires authentication)
@@ -15,32 +17,46 @@ pub async fn delete_url(
     headers: HeaderMap,
     Path(code): Path<String>,
 ) -> AppResult<impl IntoResponse> {
-    let _claims = extract_claims(&headers, &state.auth_service)?;
-    let deleted = state.repository.delete_url(&code).await?;
+    let claims = extract_claims(&headers, &state.auth_service)?;
+    let deleted = match owner_scope(&claims)? {
+        Some(owner_id) => {
+            state
+                .repository
+                .delete_url_for_owner(&code, owner_id)
+                .await?
+        }
+        None => state.repository.delete_url(&code).await?,
+    };
 
     if !deleted {
         return Err(AppError::UrlNotFound(code));
     }
 
-    // Also remove from cache if enabled
-    if state.cache_enabled {
-        let _ = state.cache.delete_url(&code).await;
-    }
+    // Also remove from cache on every instance
+    invalidate_cached_url(&state, &code).await;
 
     Ok(StatusCode::NO_CONTENT)
 }
 
-/// Get global statistics (requires authentication)
+/// Get statistics for the caller's URLs, or all URLs for superusers (requires authentication)
 pub async fn get_stats(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
 ) -> AppResult<impl IntoResponse> {
-    let _claims = extract_claims(&headers, &state.auth_service)?;
-    let stats = state.repository.get_stats().await?;
+    let claims = extract_claims(&headers, &state.auth_service)?;
+    let (stats, scope) = match owner_scope(&claims)? {
+        Some(owner_id) => (
+            state.repository.get_stats_for_owner(owner_id).await?,
+            UniqueScope::Owner(owner_id),
+        ),
+        None => (state.repository.get_stats().await?, UniqueScope::Global),
+    };
 
     let response = StatsResponse {
         total_urls: stats.total_urls,
         total_clicks: stats.total_clicks,
+        bot_clicks: stats.bot_clicks,
+        unique_visitors: unique_visitors(&state, scope).await,
         active_urls: stats.active_urls,
         expired_urls: stats.expired_urls,
     };
@@ -48,20 +64,87 @@ pub async fn get_stats(
     Ok(Json(response))
 }
 
-/// List all URLs (paginated, requires authentication)
+/// List the caller's URLs, or all URLs for superusers (requires authentication)
+///
+/// Supports filters, sorting and keyset pagination via `next_cursor`.
 pub async fn list_urls(
     State(state): State<Arc<AppState>>,
     Query(query): Query<ListUrlsQuery>,
     headers: HeaderMap,
 ) -> AppResult<impl IntoResponse> {
-    let _claims = extract_claims(&headers, &state.auth_service)?;
-    let limit = query.limit.unwrap_or(50).min(100); // Max 100
-    let offset = query.offset.unwrap_or(0);
+    let claims = extract_claims(&headers, &state.auth_service)?;
+    let limit = query.limit.unwrap_or(50).clamp(1, 100); // Max 100
+    let sort = query.sort.unwrap_or_default();
+    let order = query.order.unwrap_or_default();
 
-    let urls = state.repository.get_all_urls(limit, offset).await?;
-    let total = state.repository.count_urls().await?;
+    let after = query
+        .cursor
+        .as_deref()
+        .map(UrlCursor::decode)
+        .transpose()
+        .map_err(AppError::BadRequest)?;
+    if after
+        .as_ref()
+        .is_some_and(|cursor| cursor.sort != sort || cursor.order != order)
+    {
+        return Err(AppError::BadRequest(
+            "Cursor was issued for a different sort order".to_string(),
+        ));
+    }
+    // A cursor already marks the position, so the offset only applies to the first page
+    let offset = match after {
+        Some(_) => 0,
+        None => query.offset.unwrap_or(0).max(0),
+    };
+
+    let domain = query
+        .domain
+        .map(|d| d.trim().trim_end_matches('.').to_ascii_lowercase())
+        .filter(|d| !d.is_empty());
+    if domain.as_deref().is_some_and(|d| !is_valid_domain(d)) {
+        return Err(AppError::BadRequest(
+            "'domain' must be a hostname".to_string(),
+        ));
+    }
+    if let (Some(from), Some(to)) = (query.created_from, query.created_to) {
+        if from >= to {
+            return Err(AppError::BadRequest(
+                "'created_from' must be earlier than 'created_to'".to_string(),
+            ));
+        }
+    }
+
+    let filter = UrlFilter {
+        owner_id: owner_scope(&claims)?,
+        status: query.status,
+        created_from: query.created_from,
+        created_to: query.created_to,
+        domain,
+        search: query.search.filter(|s| !s.is_empty()),
+    };
+    let after_cursor = after.is_some();
+    let list_query = UrlListQuery {
+        filter,
+        sort,
+        order,
+        after,
+        offset,
+        // One extra row tells whether another page follows
+        limit: limit + 1,
+    };
+
+    let mut urls = state.repository.list_urls(&list_query).await?;
+    let next_cursor = if urls.len() as i64 > limit {
+        urls.truncate(limit as usize);
+        urls.last()
+            .map(|last| UrlCursor::after(last, sort, order).encode())
+    } else {
+        None
+    };
+    let total = state.repository.count_urls(&list_query.filter).await?;
     let responses: Vec<UrlInfoResponse> = urls.into_iter().map(Into::into).collect();
 
-    let paginated_response = PaginatedResponse::new(responses, total, limit, offset);
+    let paginated_response = PaginatedResponse::new(responses, total, limit, offset)
+        .with_next_cursor(next_cursor, after_cursor);
     Ok(Json(paginated_response))
 }
@@ -0,0 +1,166 @@
+use crate::analytics::ClickDimension;
+use crate::cache::UniqueScope;
+use crate::error::{AppError, AppResult};
+use crate::models::{DailyUniqueVisitors, DimensionCount, TimeBucket, UrlAnalyticsResponse};
+use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::{IntoResponse, Json};
+use chrono::{DateTime, Duration, Utc};
+use std::sync::Arc;
+
+use super::helpers::{extract_claims, owner_scope};
+use super::types::UrlStatsQuery;
+use super::AppState;
+
+/// Default reporting window when `from` is omitted
+const DEFAULT_RANGE_DAYS: i64 = 30;
+
+/// Maximum number of buckets a single time series may contain
+const MAX_BUCKETS: i64 = 2000;
+
+/// Number of entries returned per breakdown
+const TOP_N: i64 = 10;
+
+/// Get bucketed click analytics for a short URL (requires authentication)
+pub async fn get_url_stats(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(code): Path<String>,
+    Query(query): Query<UrlStatsQuery>,
+) -> AppResult<impl IntoResponse> {
+    let claims = extract_claims(&headers, &state.auth_service)?;
+
+    let entry = state
+        .repository
+        .get_url_by_short_code(&code)
+        .await?
+        .ok_or(AppError::UrlNotFound(code.clone()))?;
+
+    // Non-superusers may only see analytics for their own links
+    if let Some(owner_id) = owner_scope(&claims)? {
+        if entry.owner_id != Some(owner_id) {
+            return Err(AppError::UrlNotFound(code));
+        }
+    }
+
+    let interval = query.interval.unwrap_or_default();
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query
+        .from
+        .unwrap_or(to - Duration::days(DEFAULT_RANGE_DAYS));
+
+    if from >= to {
+        return Err(AppError::BadRequest(
+            "'from' must be earlier than 'to'".to_string(),
+        ));
+    }
+
+    let bucket_seconds = interval.duration().num_seconds();
+    if (to - from).num_seconds() / bucket_seconds > MAX_BUCKETS {
+        return Err(AppError::BadRequest(format!(
+            "Range too large for interval '{}' (max {} buckets)",
+            interval.as_str(),
+            MAX_BUCKETS
+        )));
+    }
+
+    let timeseries: Vec<TimeBucket> = state
+        .repository
+        .get_click_timeseries(entry.id, from, to, interval)
+        .await?
+        .into_iter()
+        .map(|(bucket_start, clicks)| TimeBucket {
+            bucket_start,
+            clicks,
+        })
+        .collect();
+    let total_clicks = timeseries.iter().map(|b| b.clicks).sum();
+    let bot_clicks = state
+        .repository
+        .count_bot_clicks(entry.id, from, to)
+        .await?;
+    let (unique_visitors, daily_unique_visitors) =
+        range_unique_visitors(&state, &entry.short_code, from, to).await;
+
+    let top = |dimension| {
+        state
+            .repository
+            .get_top_click_values(entry.id, dimension, from, to, TOP_N)
+    };
+    let top_referrers = top(ClickDimension::Referrer).await?;
+    let top_countries = top(ClickDimension::Country).await?;
+    let browsers = top(ClickDimension::Browser).await?;
+    let operating_systems = top(ClickDimension::Os).await?;
+    let devices = top(ClickDimension::Device).await?;
+
+    let response = UrlAnalyticsResponse {
+        short_code: entry.short_code,
+        from,
+        to,
+        interval: interval.as_str().to_string(),
+        total_clicks,
+        bot_clicks,
+        unique_visitors,
+        daily_unique_visitors,
+        timeseries,
+        top_referrers: top_referrers
+            .into_iter()
+            .map(DimensionCount::from)
+            .collect(),
+        top_countries: top_countries
+            .into_iter()
+            .map(DimensionCount::from)
+            .collect(),
+        browsers: browsers.into_iter().map(DimensionCount::from).collect(),
+        operating_systems: operating_systems
+            .into_iter()
+            .map(DimensionCount::from)
+            .collect(),
+        devices: devices.into_iter().map(DimensionCount::from).collect(),
+    };
+
+    Ok(Json(response))
+}
+
+/// Unique visitors over the days touched by `[from, to)`, limited to the retention window
+///
+/// Returns `(None, [])` when tracking is disabled or Redis is unavailable.
+async fn range_unique_visitors(
+    state: &AppState,
+    short_code: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+) -> (Option<i64>, Vec<DailyUniqueVisitors>) {
+    if !state.unique_visitors_enabled {
+        return (None, Vec::new());
+    }
+
+    let retained_from =
+        Utc::now().date_naive() - Duration::days(state.unique_visitor_retention_days - 1);
+    let first_day = from.date_naive().max(retained_from);
+    let last_day = (to - Duration::microseconds(1)).date_naive();
+    let scope = UniqueScope::Url(short_code);
+
+    let total = state
+        .cache
+        .count_unique_visitors(scope, first_day, last_day)
+        .await;
+    let daily = state
+        .cache
+        .count_daily_unique_visitors(scope, first_day, last_day)
+        .await;
+
+    match (total, daily) {
+        (Ok(total), Ok(daily)) => (
+            Some(total),
+            daily
+                .into_iter()
+                .map(|(date, visitors)| DailyUniqueVisitors { date, visitors })
+                .collect(),
+        ),
+        (Err(e), _) | (_, Err(e)) => {
+            tracing::warn!("Failed to count unique visitors for {}: {}", short_code, e);
+            (None, Vec::new())
+        }
+    }
+}
@@ -18,15 +18,22 @@ pub async fn login(
         .ok_or(AppError::UserNotFound(payload.username.clone()))?;
 
     // Verify password
-    bcrypt::verify(&payload.password, &user.password_hash)
+    let valid = bcrypt::verify(&payload.password, &user.password_hash)
         .map_err(|e| AppError::Internal(format!("Password verification failed: {}", e)))?;
+    if !valid {
+        return Err(AppError::Unauthorized("Invalid credentials".to_string()));
+    }
 
     if !user.is_active {
         return Err(AppError::Unauthorized("User account is inactive".to_string()));
     }
 
     // Generate JWT token using auth service from state
-    let token = state.auth_service.generate_token(&user.id.to_string(), &user.username)?;
+    let token = state.auth_service.generate_token(
+        &user.id.to_string(),
+        &user.username,
+        user.is_superuser,
+    )?;
 
     Ok(Json(LoginResponse {
         token,
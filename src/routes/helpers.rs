@@ -1,5 +1,10 @@
 use crate::auth::AuthService;
+use crate::cache::UniqueScope;
+use crate::config::CacheBackendKind;
 use crate::error::{AppError, AppResult};
+use crate::models::{CachedUrl, UrlEntry};
+use crate::state::AppState;
+use chrono::{Duration, Utc};
 
 // Re-export hours_from_now from util module for convenience
 pub use crate::util::hours_from_now;
@@ -7,6 +12,83 @@ pub use crate::util::hours_from_now;
 // Re-export generate_short_code from services module for convenience
 pub use crate::services::ShortCodeService;
 
+/// Load a URL from the database, coalescing concurrent lookups of the same code
+///
+/// The cache is refreshed as part of the shared lookup: live links are cached
+/// and unknown or expired codes are tombstoned, so a burst of misses costs one
+/// query and one cache write. A lookup that overlaps an update or delete of the
+/// link leaves the cache alone, so it can't bring back the old entry.
+///
+/// With a cache, the link is read from the primary database: the result is
+/// cached for the full TTL, and a lagging replica could otherwise put back a
+/// destination that was just edited through another instance.
+pub(crate) async fn load_url(state: &AppState, short_code: &str) -> AppResult<Option<UrlEntry>> {
+    state
+        .url_lookups
+        .run(short_code, || async {
+            let generation = state.cache_generations.current(short_code);
+            let entry = if state.cache.kind() == CacheBackendKind::None {
+                state.repository.get_url_by_short_code(short_code).await?
+            } else {
+                state
+                    .repository
+                    .get_url_by_short_code_from_primary(short_code)
+                    .await?
+            };
+            if state.cache_generations.current(short_code) != generation {
+                return Ok(entry);
+            }
+
+            let cached = entry
+                .as_ref()
+                .map(CachedUrl::from)
+                .filter(|cached| !cached.is_expired(Utc::now()));
+            let result = match &cached {
+                Some(cached) => state.cache.set_url(cached).await,
+                None => state.cache.set_missing(short_code).await,
+            };
+            if let Err(e) = result {
+                tracing::warn!("Failed to refresh cache for {}: {:?}", short_code, e);
+            }
+
+            // An invalidation may have run between the check above and the write
+            if state.cache_generations.current(short_code) != generation {
+                if let Err(e) = state.cache.delete_url(short_code).await {
+                    tracing::warn!(
+                        "Failed to drop stale cache entry for {}: {:?}",
+                        short_code,
+                        e
+                    );
+                }
+            }
+
+            Ok(entry)
+        })
+        .await
+}
+
+/// Drop a mutated URL from the cache here and, via pub/sub, on every other instance
+pub(crate) async fn invalidate_cached_url(state: &AppState, short_code: &str) {
+    // Lookups still in flight read the link before the change; keep them out of the cache
+    state.cache_generations.bump(short_code);
+    if let Err(e) = state.cache.delete_url(short_code).await {
+        tracing::warn!("Failed to invalidate cache for {}: {:?}", short_code, e);
+    }
+
+    // Publish off the request path; while Redis is down this fails and is not retried
+    let cache = state.cache.clone();
+    let short_code = short_code.to_string();
+    tokio::spawn(async move {
+        if let Err(e) = cache.publish_invalidation(&short_code).await {
+            tracing::warn!(
+                "Failed to publish cache invalidation for {}: {:?}",
+                short_code,
+                e
+            );
+        }
+    });
+}
+
 /// Helper to extract JWT claims from Authorization header
 pub(crate) fn extract_claims(
     headers: &axum::http::HeaderMap,
@@ -14,14 +96,14 @@ pub(crate) fn extract_claims(
 ) -> AppResult<crate::auth::Claims> {
     let auth_header = headers
         .get("Authorization")
-        .ok_or_else(|| AppError::Internal("Missing Authorization header".to_string()))?;
+        .ok_or_else(|| AppError::Unauthorized("Missing Authorization header".to_string()))?;
 
     let auth_str = auth_header
         .to_str()
-        .map_err(|e| AppError::Internal(format!("Invalid Authorization header: {}", e)))?;
+        .map_err(|e| AppError::Unauthorized(format!("Invalid Authorization header: {}", e)))?;
 
     if !auth_str.starts_with("Bearer ") {
-        return Err(AppError::Internal(
+        return Err(AppError::Unauthorized(
             "Authorization header must start with 'Bearer '".to_string(),
         ));
     }
@@ -29,3 +111,49 @@ pub(crate) fn extract_claims(
     let token = &auth_str[7..];
     auth_service.validate_token(token)
 }
+
+/// Extract JWT claims if an Authorization header is present
+///
+/// Returns `Ok(None)` for anonymous requests, but still fails if a header is
+/// supplied and the token is invalid.
+pub(crate) fn extract_optional_claims(
+    headers: &axum::http::HeaderMap,
+    auth_service: &AuthService,
+) -> AppResult<Option<crate::auth::Claims>> {
+    if !headers.contains_key("Authorization") {
+        return Ok(None);
+    }
+
+    extract_claims(headers, auth_service).map(Some)
+}
+
+/// Determine which owner's URLs the caller may access
+///
+/// Returns `None` for superusers (no owner filter) and the caller's user ID otherwise.
+pub(crate) fn owner_scope(claims: &crate::auth::Claims) -> AppResult<Option<i64>> {
+    if claims.is_superuser {
+        return Ok(None);
+    }
+
+    claims.user_id().map(Some)
+}
+
+/// Approximate unique visitors for a scope over the retention window
+///
+/// Returns `None` when tracking is disabled or Redis is unavailable.
+pub(crate) async fn unique_visitors(state: &AppState, scope: UniqueScope<'_>) -> Option<i64> {
+    if !state.unique_visitors_enabled {
+        return None;
+    }
+
+    let to = Utc::now().date_naive();
+    let from = to - Duration::days(state.unique_visitor_retention_days - 1);
+
+    match state.cache.count_unique_visitors(scope, from, to).await {
+        Ok(count) => Some(count),
+        Err(e) => {
+            tracing::warn!("Failed to count unique visitors for {:?}: {}", scope, e);
+            None
+        }
+    }
+}
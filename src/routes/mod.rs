@@ -1,4 +1,5 @@
 pub mod admin_handlers;
+pub mod analytics_handlers;
 pub mod auth_handlers;
 pub mod health;
 pub mod helpers;
@@ -2,12 +2,13 @@ use crate::config::RateLimitConfig;
 use crate::error::{AppError, AppResult};
 use crate::middleware_impls::AuthAwareKeyExtractor;
 use axum::middleware;
-use axum::routing::{delete, get, post};
+use axum::routing::{delete, get, patch, post};
 use std::sync::Arc;
 use tower_governor::GovernorLayer;
 use tower_http::cors::{Any, CorsLayer};
 
 use super::admin_handlers;
+use super::analytics_handlers;
 use super::auth_handlers;
 use super::health;
 use super::url_handlers;
@@ -15,6 +16,10 @@ use super::AppState;
 
 /// Create application router
 ///
+/// The top-level names of fixed paths (`login`, `_stats`, ...) are added to the
+/// reserved short codes in `state`, so links can't be shadowed by routes. Register
+/// every path through `path(...)` below to keep that list complete.
+///
 /// # Errors
 ///
 /// Returns an error if rate limiter configuration fails to build.
@@ -64,27 +69,45 @@ pub fn create_router(
             .allow_headers(Any)
     };
 
+    // Records each registered path for the reserved-code registry
+    let mut paths: Vec<&'static str> = Vec::new();
+    let mut path = |p: &'static str| {
+        paths.push(p);
+        p
+    };
+
     // Build router with rate limiting using merge
-    // Strict rate limit for sensitive endpoints (POST /, POST /login, DELETE /{code}, /_stats, /_list)
+    // Strict rate limit for sensitive endpoints (POST /, POST /login, PATCH/DELETE /{code}, /{code}/stats, /_stats, /_list)
     let sensitive_routes = axum::Router::new()
-        .route("/", post(url_handlers::create_url))
-        .route("/login", post(auth_handlers::login))
-        .route("/{code}", delete(admin_handlers::delete_url))
-        .route("/_stats", get(admin_handlers::get_stats))
-        .route("/_list", get(admin_handlers::list_urls))
+        .route(path("/"), post(url_handlers::create_url))
+        .route(path("/login"), post(auth_handlers::login))
+        .route(path("/{code}"), patch(url_handlers::update_url))
+        .route(path("/{code}"), delete(admin_handlers::delete_url))
+        .route(
+            path("/{code}/stats"),
+            get(analytics_handlers::get_url_stats),
+        )
+        .route(path("/_stats"), get(admin_handlers::get_stats))
+        .route(path("/_list"), get(admin_handlers::list_urls))
         .layer(governor_layer_strict);
 
-    // Lenient rate limit for public endpoints (GET /{code}, GET /{code}/info)
+    // Lenient rate limit for public endpoints (GET /{code}, GET /{code}/info, GET /_codes/available)
     let public_routes = axum::Router::new()
-        .route("/{code}", get(url_handlers::resolve_url))
-        .route("/{code}/info", get(url_handlers::get_url_info))
+        .route(path("/{code}"), get(url_handlers::resolve_url))
+        .route(path("/{code}/info"), get(url_handlers::get_url_info))
+        .route(
+            path("/_codes/available"),
+            get(url_handlers::check_code_availability),
+        )
         .layer(governor_layer_lenient);
 
     // Health check and documentation endpoints (no rate limiting)
     let health_routes = axum::Router::new()
-        .route("/_health", get(health::health_check))
-        .route("/_openapi", get(health::openapi_spec))
-        .route("/_docs", get(health::swagger_ui));
+        .route(path("/_health"), get(health::health_check))
+        .route(path("/_openapi"), get(health::openapi_spec))
+        .route(path("/_docs"), get(health::swagger_ui));
+
+    state.short_codes.reserved().extend_from_paths(paths);
 
     // Merge routers and apply middleware layers
     Ok(sensitive_routes
@@ -1,10 +1,40 @@
+use crate::analytics::StatsInterval;
+use crate::cache::TierStats;
+use crate::db::{SortOrder, UrlSort, UrlStatus};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
 /// Query parameters for listing URLs
 #[derive(Debug, Deserialize)]
 pub struct ListUrlsQuery {
     pub limit: Option<i64>,
+    /// Deprecated in favour of `cursor`; ignored when a cursor is given
     pub offset: Option<i64>,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+    pub status: Option<UrlStatus>,
+    pub created_from: Option<DateTime<Utc>>,
+    pub created_to: Option<DateTime<Utc>>,
+    /// Destination host; subdomains match too
+    pub domain: Option<String>,
+    /// Substring of the short code or destination
+    pub search: Option<String>,
+    pub sort: Option<UrlSort>,
+    pub order: Option<SortOrder>,
+}
+
+/// Query parameters for checking a custom code
+#[derive(Debug, Deserialize)]
+pub struct CodeAvailabilityQuery {
+    pub code: String,
+}
+
+/// Query parameters for per-URL analytics
+#[derive(Debug, Deserialize)]
+pub struct UrlStatsQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub interval: Option<StatsInterval>,
 }
 
 /// Health check response
@@ -13,9 +43,41 @@ pub struct HealthCheckResponse {
     pub status: String,
     pub database: HealthStatus,
     pub cache: HealthStatus,
+    pub cache_stats: CacheStatsResponse,
+    /// Unused pre-generated short codes; only reported with `SHORT_CODE_STRATEGY=pool`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_pool_size: Option<i64>,
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+/// URL lookup cache counters since process start, per tier
+#[derive(Debug, Serialize)]
+pub struct CacheStatsResponse {
+    pub backend: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub circuit: Option<String>,
+    pub local: TierStatsResponse,
+    pub redis: TierStatsResponse,
+}
+
+/// Hit/miss counters for one cache tier
+#[derive(Debug, Serialize)]
+pub struct TierStatsResponse {
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_ratio: Option<f64>,
+}
+
+impl From<&TierStats> for TierStatsResponse {
+    fn from(stats: &TierStats) -> Self {
+        Self {
+            hits: stats.hits(),
+            misses: stats.misses(),
+            hit_ratio: stats.hit_ratio(),
+        }
+    }
+}
+
 /// Individual health status
 #[derive(Debug, Serialize)]
 pub struct HealthStatus {
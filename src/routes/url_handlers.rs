@@ -1,55 +1,91 @@
+use crate::analytics::{hash_ip, hash_visitor, normalize_country, ClickEvent};
+use crate::cache::{CacheLookup, UniqueScope};
+use crate::config::ClickCounterMode;
 use crate::error::{AppError, AppResult};
-use crate::models::{CreateUrlRequest, CreateUrlResponse, UrlInfoResponse};
-use axum::extract::{Path, State};
-use axum::http::StatusCode;
+use crate::middleware_impls::RequestContext;
+use crate::models::{
+    CachedUrl, CodeAvailabilityResponse, CreateUrlRequest, CreateUrlResponse, UpdateUrlRequest,
+    UrlInfoResponse,
+};
+use crate::routes::types::CodeAvailabilityQuery;
+use crate::services::{is_valid_custom_code, CodeAvailability};
+use axum::extract::{Extension, Path, Query, State};
+use axum::http::{header, HeaderMap, Method, StatusCode};
 use axum::response::{IntoResponse, Json, Redirect};
 use chrono::{Duration, Utc};
-use regex::Regex;
 use std::sync::Arc;
 use validator::Validate;
 use url::Url as UrlParser;
 
 use super::AppState;
-use super::helpers::{ShortCodeService, hours_from_now};
+use super::helpers::{
+    extract_claims, extract_optional_claims, hours_from_now, invalidate_cached_url, load_url,
+    owner_scope, unique_visitors,
+};
+
+/// Validate a destination URL according to the configured strictness
+fn validate_destination(state: &AppState, url: &str) -> AppResult<()> {
+    if state.strict_url_validation {
+        UrlParser::parse(url)
+            .map_err(|_| AppError::InvalidUrl("Invalid URL format".to_string()))?;
+
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(AppError::InvalidUrl(
+                "URL must start with http:// or https://".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Validate the format of a custom short code
+fn validate_custom_code(code: &str) -> AppResult<()> {
+    if !is_valid_custom_code(code) {
+        return Err(AppError::InvalidUrl(
+            "Custom code must be 4-16 alphanumeric characters, underscores, or hyphens".to_string(),
+        ));
+    }
+    Ok(())
+}
 
 /// Create a short URL
+///
+/// Anonymous requests create unowned links; authenticated requests stamp the
+/// caller as the owner.
 pub async fn create_url(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Json(payload): Json<CreateUrlRequest>,
 ) -> AppResult<impl IntoResponse> {
+    let owner_id = extract_optional_claims(&headers, &state.auth_service)?
+        .map(|claims| claims.user_id())
+        .transpose()?;
+
     payload.validate().map_err(|e| {
         AppError::InvalidUrl(format!("Validation failed: {}", e))
     })?;
 
     // Proper URL validation
-    if state.strict_url_validation {
-        UrlParser::parse(&payload.url)
-            .map_err(|_| AppError::InvalidUrl("Invalid URL format".to_string()))?;
+    validate_destination(&state, &payload.url)?;
 
-        if !payload.url.starts_with("http://") && !payload.url.starts_with("https://") {
-            return Err(AppError::InvalidUrl("URL must start with http:// or https://".to_string()));
-        }
-    }
-
-    // Validate custom code with regex if provided
+    // Validate custom code if provided
     if let Some(custom) = &payload.custom_code {
-        let code_regex = Regex::new(r"^[a-zA-Z0-9_-]{4,16}$")
-            .map_err(|e| AppError::Internal(format!("Invalid regex pattern: {}", e)))?;
-        if !code_regex.is_match(custom) {
-            return Err(AppError::InvalidUrl(
-                "Custom code must be 4-16 alphanumeric characters, underscores, or hyphens".to_string(),
-            ));
-        }
+        validate_custom_code(custom)?;
     }
 
     // Use custom code or generate a random one
     let short_code = if let Some(custom) = &payload.custom_code {
-        if state.repository.short_code_exists(custom).await? {
-            return Err(AppError::ShortCodeExists(custom.clone()));
-        }
+        state
+            .short_codes
+            .ensure_available(custom, &state.repository)
+            .await?;
         custom.clone()
     } else {
-        ShortCodeService::generate_short_code(state.short_code_length, state.short_code_max_attempts, &state.repository).await?
+        state
+            .short_codes
+            .generate_short_code(&state.repository)
+            .await?
     };
 
     // Calculate expiry
@@ -64,14 +100,26 @@ pub async fn create_url(
         .filter(|&t| hours_from_now(t) >= 0); // Never store already-expired URLs
 
     // Create URL entry
-    let entry = state
+    let entry = match state
         .repository
-        .create_url(&short_code, &payload.url, expires_at)
-        .await?;
+        .create_url(&short_code, &payload.url, expires_at, owner_id)
+        .await
+    {
+        // A concurrent create took the custom code after the availability check
+        Err(error @ AppError::ShortCodeExists(_)) if payload.custom_code.is_some() => {
+            let suggestions = state
+                .short_codes
+                .suggest_alternatives(&short_code, &state.repository)
+                .await?;
+            return Err(error.with_suggestions(suggestions));
+        }
+        result => result?,
+    };
 
-    // Cache new URL if enabled
-    if state.cache_enabled {
-        let _ = state.cache.set_url(&entry).await;
+    // Cache new URL; this also replaces any tombstone for the code
+    if state.cache.set_url(&CachedUrl::from(&entry)).await.is_err() {
+        // Make sure a stale tombstone can't hide the new link
+        let _ = state.cache.delete_url(&short_code).await;
     }
 
     let short_url = format!("{}/{}", state.base_url, short_code);
@@ -86,60 +134,240 @@ pub async fn create_url(
     Ok((StatusCode::CREATED, Json(response)))
 }
 
-/// Resolve a short URL and redirect
-pub async fn resolve_url(
+/// Check whether a custom code can be used, suggesting free variants if not
+pub async fn check_code_availability(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<CodeAvailabilityQuery>,
+) -> AppResult<impl IntoResponse> {
+    validate_custom_code(&query.code)?;
+
+    let availability = state
+        .short_codes
+        .availability(&query.code, &state.repository)
+        .await?;
+    let reason = match availability {
+        CodeAvailability::Available => None,
+        CodeAvailability::Reserved => Some("reserved"),
+        CodeAvailability::Taken => Some("taken"),
+    };
+    let suggestions = match reason {
+        Some(_) => {
+            state
+                .short_codes
+                .suggest_alternatives(&query.code, &state.repository)
+                .await?
+        }
+        None => Vec::new(),
+    };
+
+    Ok(Json(CodeAvailabilityResponse {
+        code: query.code,
+        available: availability == CodeAvailability::Available,
+        reason: reason.map(str::to_string),
+        suggestions,
+    }))
+}
+
+/// Update the destination and/or expiry of a short URL (requires authentication)
+pub async fn update_url(
     State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
     Path(code): Path<String>,
+    Json(payload): Json<UpdateUrlRequest>,
 ) -> AppResult<impl IntoResponse> {
-    // Check cache first if enabled
-    if state.cache_enabled {
-        if let Some(entry) = state.cache.get_url(&code).await? {
-            return handle_url_resolution(&state, &entry).await;
+    let claims = extract_claims(&headers, &state.auth_service)?;
+
+    payload
+        .validate()
+        .map_err(|e| AppError::InvalidUrl(format!("Validation failed: {}", e)))?;
+
+    if payload.clear_expiry && payload.expiry_hours.is_some() {
+        return Err(AppError::BadRequest(
+            "expiry_hours and clear_expiry cannot be combined".to_string(),
+        ));
+    }
+
+    let existing = state
+        .repository
+        .get_url_by_short_code(&code)
+        .await?
+        .ok_or(AppError::UrlNotFound(code.clone()))?;
+
+    // Non-superusers may only edit their own links
+    if let Some(owner_id) = owner_scope(&claims)? {
+        if existing.owner_id != Some(owner_id) {
+            return Err(AppError::UrlNotFound(code));
         }
     }
 
-    // Cache miss - check database
+    if let Some(url) = &payload.url {
+        validate_destination(&state, url)?;
+    }
+
+    // `Some(None)` clears the expiry, `None` leaves it unchanged
+    let expires_at = match payload.expiry_hours {
+        Some(hours) => Some(Some(Utc::now() + Duration::hours(hours))),
+        None if payload.clear_expiry => Some(None),
+        None => None,
+    };
+
     let entry = state
         .repository
-        .get_url_by_short_code(&code)
+        .update_url(&code, payload.url.as_deref(), expires_at)
         .await?
         .ok_or(AppError::UrlNotFound(code.clone()))?;
 
-    // Check if expired
-    if let Some(expires_at) = entry.expires_at {
-        if expires_at < Utc::now() {
+    // Drop the stale cache entry everywhere so the next resolve picks up the change
+    invalidate_cached_url(&state, &code).await;
+
+    Ok(Json(UrlInfoResponse::from(entry)))
+}
+
+/// Resolve a short URL and redirect
+pub async fn resolve_url(
+    State(state): State<Arc<AppState>>,
+    Path(code): Path<String>,
+    method: Method,
+    headers: HeaderMap,
+    context: Option<Extension<RequestContext>>,
+) -> AppResult<impl IntoResponse> {
+    let context = context.map(|Extension(ctx)| ctx);
+
+    // Check cache first
+    match state.cache.get_url(&code).await? {
+        CacheLookup::Hit(entry) | CacheLookup::Stale(entry) if entry.is_expired(Utc::now()) => {
+            if let Err(e) = state.cache.set_missing(&code).await {
+                tracing::warn!("Failed to cache missing code {}: {:?}", code, e);
+            }
             return Err(AppError::UrlNotFound(code));
         }
+        CacheLookup::Hit(entry) => {
+            return handle_url_resolution(&state, &entry, &method, &headers, context.as_ref())
+                .await;
+        }
+        CacheLookup::Stale(entry) => {
+            // Serve the cached entry and refresh it in the background
+            let refresh_state = state.clone();
+            let refresh_code = code.clone();
+            tokio::spawn(async move {
+                if let Err(e) = load_url(&refresh_state, &refresh_code).await {
+                    tracing::warn!("Early cache refresh failed for {}: {:?}", refresh_code, e);
+                }
+            });
+            return handle_url_resolution(&state, &entry, &method, &headers, context.as_ref())
+                .await;
+        }
+        CacheLookup::Tombstone => return Err(AppError::UrlNotFound(code)),
+        CacheLookup::Miss => {}
     }
 
-    // Cache for future requests if enabled
-    if state.cache_enabled {
-        let _ = state.cache.set_url(&entry).await;
+    // Cache miss - check database (coalesced per short code; re-caches the result)
+    let entry = match load_url(&state, &code).await? {
+        Some(entry) => CachedUrl::from(&entry),
+        None => return Err(AppError::UrlNotFound(code)),
+    };
+
+    // Check if expired
+    if entry.is_expired(Utc::now()) {
+        return Err(AppError::UrlNotFound(code));
     }
 
-    handle_url_resolution(&state, &entry).await
+    handle_url_resolution(&state, &entry, &method, &headers, context.as_ref()).await
 }
 
-/// Handle actual URL resolution (increment click count and redirect)
+/// Handle actual URL resolution (record the click and redirect)
 async fn handle_url_resolution(
     state: &Arc<AppState>,
-    entry: &crate::models::UrlEntry,
+    entry: &CachedUrl,
+    method: &Method,
+    headers: &HeaderMap,
+    context: Option<&RequestContext>,
 ) -> AppResult<Redirect> {
-    // Submit click count increment job to worker
-    state.job_sender.increment_click_count(entry.short_code.clone());
-
-    // Invalidate cache entry asynchronously
-    if state.cache_enabled {
-        let cache = state.cache.clone();
-        let code = entry.short_code.clone();
-        tokio::spawn(async move {
-            if let Err(e) = cache.delete_url(&code).await {
-                tracing::error!("Failed to invalidate cache for {}: {:?}", code, e);
+    let user_agent = context.and_then(|ctx| ctx.user_agent.clone());
+    let is_bot = state
+        .bot_detector
+        .is_bot(method, headers, user_agent.as_deref());
+
+    // Count the click (bots are counted separately)
+    match state.click_counter_mode {
+        ClickCounterMode::Worker => {
+            if is_bot {
+                state
+                    .job_sender
+                    .increment_bot_click_count(entry.short_code.clone());
+            } else {
+                state
+                    .job_sender
+                    .increment_click_count(entry.short_code.clone());
             }
+        }
+        ClickCounterMode::Redis => {
+            let cache = state.cache.clone();
+            let job_sender = state.job_sender.clone();
+            let code = entry.short_code.clone();
+            tokio::spawn(async move {
+                if let Err(e) = cache
+                    .increment_click_counter(&code, is_bot, Utc::now())
+                    .await
+                {
+                    // Fall back to the in-process worker so the click is not lost
+                    tracing::warn!("Failed to count click in Redis for {}: {}", code, e);
+                    if is_bot {
+                        job_sender.increment_bot_click_count(code);
+                    } else {
+                        job_sender.increment_click_count(code);
+                    }
+                }
+            });
+        }
+    }
+
+    // Track unique human visitors in Redis without delaying the redirect
+    if state.unique_visitors_enabled && !is_bot {
+        let visitor = context.and_then(|ctx| {
+            hash_visitor(&state.ip_hash_salt, &ctx.client_ip, user_agent.as_deref())
         });
+        if let Some(visitor) = visitor {
+            let cache = state.cache.clone();
+            let code = entry.short_code.clone();
+            let owner_id = entry.owner_id;
+            let retention =
+                std::time::Duration::from_secs(state.unique_visitor_retention_days as u64 * 86400);
+            tokio::spawn(async move {
+                let today = Utc::now().date_naive();
+                if let Err(e) = cache
+                    .record_unique_visitor(&code, owner_id, &visitor, today, retention)
+                    .await
+                {
+                    tracing::warn!("Failed to record unique visitor for {}: {:?}", code, e);
+                }
+            });
+        }
     }
 
-    Ok(Redirect::permanent(&entry.original_url))
+    // Queue the per-click event for the worker to batch, in either click counter mode
+    if state.click_events_enabled {
+        let referrer = headers
+            .get(header::REFERER)
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string());
+        let country = headers
+            .get(state.country_header.as_str())
+            .and_then(|h| h.to_str().ok())
+            .and_then(normalize_country);
+
+        state.job_sender.record_click(ClickEvent::new(
+            entry.id,
+            referrer,
+            user_agent,
+            context.and_then(|ctx| hash_ip(&state.ip_hash_salt, &ctx.client_ip)),
+            country,
+            is_bot,
+        ));
+    }
+
+    // Temporary, so browsers and proxies pick up later edits and deletions
+    Ok(Redirect::temporary(&entry.original_url))
 }
 
 /// Get information about a short URL
@@ -147,26 +375,12 @@ pub async fn get_url_info(
     State(state): State<Arc<AppState>>,
     Path(code): Path<String>,
 ) -> AppResult<impl IntoResponse> {
-    // Check cache first if enabled
-    if state.cache_enabled {
-        if let Some(entry) = state.cache.get_url(&code).await? {
-            let response = UrlInfoResponse::from(entry);
-            return Ok(Json(response));
-        }
-    }
-
-    // Cache miss - check database
-    let entry = state
-        .repository
-        .get_url_by_short_code(&code)
+    // Always read from the database: the cache only holds routing data, not click counts
+    let entry = load_url(&state, &code)
         .await?
         .ok_or(AppError::UrlNotFound(code.clone()))?;
 
-    // Cache for future requests if enabled
-    if state.cache_enabled {
-        let _ = state.cache.set_url(&entry).await;
-    }
-
-    let response = UrlInfoResponse::from(entry);
+    let mut response = UrlInfoResponse::from(entry);
+    response.unique_visitors = unique_visitors(&state, UniqueScope::Url(&code)).await;
     Ok(Json(response))
 }
@@ -8,14 +8,14 @@
 //! - Server binding and graceful shutdown
 //! - Background worker spawning and cleanup
 
-use crate::auth::AuthService;
-use crate::cache::Cache;
-use crate::config::Config;
+use crate::cache;
+use crate::config::{ClickCounterMode, Config, ShortCodeStrategy};
 use crate::error::{AppError, AppResult};
-use crate::jobs::{create_job_channel, Worker};
+use crate::jobs::{create_job_channel, ClickCounterSync, CodePoolRefiller, Worker, WorkerConfig};
 use crate::routes;
 use crate::state;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::net::TcpListener;
 use tracing::{error, info};
 
@@ -49,13 +49,8 @@ pub async fn run_server(config: Config, addr: String, should_migrate: bool) -> A
 
     // Initialize database connection pool
     info!("Connecting to database...");
-    let repository = crate::db::Repository::new(
-        &config.database.url,
-        config.database.max_connections,
-        config.database.min_connections,
-        config.database.acquire_timeout_seconds,
-    )
-    .await?;
+    let repository = crate::db::connect(&config.database).await?;
+    info!("Storage backend: {}", repository.backend());
 
     // Run migrations if requested
     if should_migrate {
@@ -66,12 +61,8 @@ pub async fn run_server(config: Config, addr: String, should_migrate: bool) -> A
 
     // Initialize cache
     info!("Connecting to cache...");
-    let cache = Cache::new(
-        &config.cache.url,
-        config.cache.max_connections,
-        config.cache.default_ttl_seconds,
-    )
-    .await?;
+    let cache = cache::from_config(&config.cache).await?;
+    info!("Cache backend: {}", cache.kind().as_str());
 
     // Ping cache to verify connection
     match cache.ping().await {
@@ -81,34 +72,81 @@ pub async fn run_server(config: Config, addr: String, should_migrate: bool) -> A
         }
     }
 
-    // Create application state
-    let auth_service = AuthService::new(
-        config.auth.jwt_secret.clone(),
-        config.auth.jwt_expiration_hours,
-    );
-
     // Create background job worker
     let (job_sender, job_receiver) = create_job_channel();
-    let worker = Worker::new(repository.clone(), job_receiver);
+    let worker = Worker::new(repository.clone(), job_receiver).with_config(WorkerConfig {
+        click_flush_interval_ms: config.jobs.click_flush_interval_ms,
+        click_batch_size: config.jobs.click_batch_size,
+        ..WorkerConfig::default()
+    });
+    let worker_shutdown = job_sender.clone();
 
     // Start background worker in separate task
     let worker_handle = tokio::spawn(worker.run());
 
-    let state = Arc::new(state::AppState {
-        repository,
+    // Evict URLs changed on other instances from the in-process cache
+    let invalidation_listener = {
+        let cache = cache.clone();
+        tokio::spawn(async move { cache.listen_for_invalidations().await })
+    };
+
+    // Start Redis click counter sync if counts are accumulated in Redis
+    let click_sync = match config.url.click_counter_mode {
+        ClickCounterMode::Redis => {
+            let (stop_sender, stop_receiver) = tokio::sync::oneshot::channel();
+            let sync = ClickCounterSync::new(
+                cache.clone(),
+                repository.clone(),
+                Duration::from_millis(config.jobs.click_flush_interval_ms),
+                config.jobs.click_batch_size,
+            );
+            Some((stop_sender, tokio::spawn(sync.run(stop_receiver))))
+        }
+        ClickCounterMode::Worker => None,
+    };
+
+    // Create application state
+    let state = Arc::new(state::AppState::new(
+        &config,
+        repository.clone(),
         cache,
-        auth_service,
         job_sender,
-        base_url: config.url.base_url.clone(),
-        default_expiry_hours: config.url.default_expiry_hours,
-        short_code_length: config.url.short_code_length,
-        short_code_max_attempts: config.url.short_code_max_attempts,
-        cache_enabled: config.url.cache_enabled,
-        strict_url_validation: config.url.strict_url_validation,
-    });
+    ));
+
+    // Keep the short code pool topped up if codes are claimed from it
+    let code_pool_refill = match state.short_codes.strategy() {
+        ShortCodeStrategy::Pool => {
+            let (stop_sender, stop_receiver) = tokio::sync::oneshot::channel();
+            let refiller = CodePoolRefiller::new(
+                repository,
+                state.short_codes.clone(),
+                Duration::from_millis(config.jobs.code_pool_refill_interval_ms),
+                config.url.short_code_pool_size,
+                config.url.short_code_pool_low_water,
+            );
+            Some((stop_sender, tokio::spawn(refiller.run(stop_receiver))))
+        }
+        ShortCodeStrategy::Random | ShortCodeStrategy::Sequence => None,
+    };
 
     // Create router
-    let app = routes::create_router(state, config.cors.allowed_origins, config.rate_limit)?;
+    let app = routes::create_router(
+        state.clone(),
+        config.cors.allowed_origins,
+        config.rate_limit,
+    )?;
+
+    // Links created before a route or reservation was added can't be reached any more
+    for code in state.short_codes.reserved().to_vec() {
+        match state.repository.short_code_exists(&code).await {
+            Ok(true) => tracing::warn!(
+                "Link '{}' uses a reserved code and may be shadowed by a route",
+                code
+            ),
+            Ok(false) => {}
+            Err(e) => tracing::warn!("Failed to check reserved code '{}': {}", code, e),
+        }
+    }
 
     // Start server
     let listener = TcpListener::bind(&addr)
@@ -127,7 +165,26 @@ pub async fn run_server(config: Config, addr: String, should_migrate: bool) -> A
         .await
         .map_err(|e| AppError::Internal(format!("Server error: {}", e)))?;
 
-    // Wait for background worker to finish
+    invalidation_listener.abort();
+
+    if let Some((stop_sender, refill_handle)) = code_pool_refill {
+        let _ = stop_sender.send(());
+        refill_handle.await.unwrap_or_else(|e| {
+            error!("Short code pool refiller failed: {:?}", e);
+        });
+    }
+
+    // Sync remaining Redis click counters
+    if let Some((stop_sender, sync_handle)) = click_sync {
+        let _ = stop_sender.send(());
+        sync_handle.await.unwrap_or_else(|e| {
+            error!("Click counter sync task failed: {:?}", e);
+        });
+    }
+
+    // Flush pending click counts and wait for background worker to finish
+    info!("Flushing pending background jobs...");
+    worker_shutdown.shutdown().await;
     worker_handle.await.unwrap_or_else(|e| {
         error!("Worker task failed: {:?}", e);
     });
@@ -1,3 +1,10 @@
+pub mod reserved;
+pub mod sequence;
 pub mod short_code;
+pub mod suggestions;
+pub mod word_filter;
 
-pub use short_code::ShortCodeService;
+pub use reserved::ReservedCodes;
+pub use sequence::SequenceCodec;
+pub use short_code::{is_valid_custom_code, CodeAvailability, ShortCodeService};
+pub use word_filter::WordFilter;
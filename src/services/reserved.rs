@@ -0,0 +1,102 @@
+//! Registry of short codes that can't be used for links.
+//!
+//! A link whose code matches a fixed route (`/login`, `/_stats`, ...) would be
+//! shadowed by that route, so those names are reserved. The registry combines:
+//! - the first segment of every fixed path registered by `routes::create_router`
+//! - codes listed in `RESERVED_CODES`
+//! - codes reserved at runtime with `rustlink admin reserve-code`, kept in the database
+//!
+//! Matching is case-insensitive, so `_Stats` is rejected along with `_stats`.
+
+use crate::db::Repository;
+use crate::error::AppResult;
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+
+/// Reserved codes known to this process, plus lookups of database reservations
+#[derive(Debug, Default)]
+pub struct ReservedCodes {
+    codes: RwLock<BTreeSet<String>>,
+}
+
+impl ReservedCodes {
+    /// Create a registry with the configured codes
+    pub fn new(codes: &[String]) -> Self {
+        let registry = Self::default();
+        registry.extend(codes.iter().map(String::as_str));
+        registry
+    }
+
+    /// Reserve additional codes for the lifetime of the process
+    pub fn extend<'a>(&self, codes: impl IntoIterator<Item = &'a str>) {
+        let mut reserved = self.codes.write().unwrap_or_else(|e| e.into_inner());
+        reserved.extend(
+            codes
+                .into_iter()
+                .map(|c| c.trim().to_ascii_lowercase())
+                .filter(|c| !c.is_empty()),
+        );
+    }
+
+    /// Reserve the top-level names of fixed route paths, skipping `/` and `/{param}` paths
+    pub fn extend_from_paths<'a>(&self, paths: impl IntoIterator<Item = &'a str>) {
+        self.extend(paths.into_iter().filter_map(|path| {
+            let segment = path.trim_start_matches('/').split('/').next()?;
+            (!segment.is_empty() && !segment.starts_with('{')).then_some(segment)
+        }));
+    }
+
+    /// Whether a code is reserved in this process, without consulting the database
+    pub fn contains(&self, code: &str) -> bool {
+        self.codes
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&code.to_ascii_lowercase())
+    }
+
+    /// Whether a code is reserved, including reservations made through the admin CLI
+    pub async fn is_reserved(&self, code: &str, repository: &Repository) -> AppResult<bool> {
+        if self.contains(code) {
+            return Ok(true);
+        }
+        repository
+            .is_code_reserved(&code.to_ascii_lowercase())
+            .await
+    }
+
+    /// All codes reserved in this process, sorted
+    pub fn to_vec(&self) -> Vec<String> {
+        self.codes
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_extend_from_paths() {
+        let reserved = ReservedCodes::new(&[" Promo ".to_string()]);
+        reserved.extend_from_paths(["/", "/login", "/_stats", "/{code}", "/{code}/info"]);
+
+        assert_eq!(reserved.to_vec(), ["_stats", "login", "promo"]);
+        assert!(reserved.contains("LOGIN"));
+        assert!(!reserved.contains("info"));
+    }
+
+    #[tokio::test]
+    async fn test_is_reserved_checks_storage() {
+        let repository: Repository = Arc::new(crate::db::MemoryStorage::default());
+        repository.reserve_code("launch").await.unwrap();
+
+        let reserved = ReservedCodes::default();
+        assert!(reserved.is_reserved("Launch", &repository).await.unwrap());
+        assert!(!reserved.is_reserved("other", &repository).await.unwrap());
+    }
+}
@@ -0,0 +1,222 @@
+//! Reversible encoding of sequence numbers into fixed-length short codes.
+//!
+//! Each sequence value maps to exactly one code of the configured length, so
+//! codes drawn from a database sequence never collide. The mapping is keyed by
+//! a salt, in the spirit of sqids: the value is scrambled by an affine
+//! permutation, its digits (in the alphabet's base) are mixed so that every
+//! character changes between neighbouring values, and the digits are spelled
+//! with a shuffled alphabet. This hides the sequence from casual inspection; it is not
+//! encryption, and anyone who learns the salt can decode a code.
+
+/// Stateless codec between sequence values and short codes
+#[derive(Debug, Clone)]
+pub struct SequenceCodec {
+    alphabet: Vec<char>,
+    length: usize,
+    modulus: u128,
+    multiplier: u128,
+    inverse: u128,
+    offset: u128,
+}
+
+impl SequenceCodec {
+    /// Build the codec for codes of `length` characters over `alphabet`, keyed by `salt`
+    ///
+    /// The alphabet must not contain duplicates, and `alphabet.len()^length`
+    /// must fit in a `u128`.
+    pub fn new(alphabet: &[char], length: usize, salt: &str) -> Self {
+        let base = alphabet.len() as u128;
+        let modulus = base
+            .checked_pow(length as u32)
+            .expect("short code space must fit in u128");
+        let mut rng = SplitMix64(fnv1a(salt.as_bytes()));
+
+        // Fisher-Yates with the salted generator
+        let mut alphabet = alphabet.to_vec();
+        for i in (1..alphabet.len()).rev() {
+            let j = (rng.next() % (i as u64 + 1)) as usize;
+            alphabet.swap(i, j);
+        }
+
+        // Any multiplier coprime with the modulus makes the affine map a permutation
+        let mut multiplier = u128::from(rng.next()) % modulus;
+        while multiplier < 2 || gcd(multiplier, modulus) != 1 {
+            multiplier = (multiplier + 1) % modulus;
+        }
+        let inverse = mod_inverse(multiplier, modulus);
+        let offset = u128::from(rng.next()) % modulus;
+
+        Self {
+            alphabet,
+            length,
+            modulus,
+            multiplier,
+            inverse,
+            offset,
+        }
+    }
+
+    /// Number of distinct codes, i.e. the largest value that can be encoded plus one
+    pub fn capacity(&self) -> u128 {
+        self.modulus
+    }
+
+    /// Encode a sequence value, or `None` once the code space is exhausted
+    pub fn encode(&self, value: u64) -> Option<String> {
+        let value = u128::from(value);
+        if value >= self.modulus {
+            return None;
+        }
+
+        let base = self.alphabet.len() as u128;
+        let mut x = (mul_mod(value, self.multiplier, self.modulus) + self.offset) % self.modulus;
+        let mut digits = vec![0u128; self.length];
+        for digit in digits.iter_mut() {
+            *digit = x % base;
+            x /= base;
+        }
+
+        // Chain the digits forwards then backwards so every digit depends on all others
+        for i in 1..self.length {
+            digits[i] = (digits[i] + digits[i - 1]) % base;
+        }
+        for i in (0..self.length.saturating_sub(1)).rev() {
+            digits[i] = (digits[i] + digits[i + 1]) % base;
+        }
+
+        Some(digits.iter().map(|&d| self.alphabet[d as usize]).collect())
+    }
+
+    /// Recover the sequence value behind a code produced by [`encode`](Self::encode)
+    pub fn decode(&self, code: &str) -> Option<u64> {
+        let base = self.alphabet.len() as u128;
+        let mut digits = code
+            .chars()
+            .map(|c| {
+                self.alphabet
+                    .iter()
+                    .position(|&a| a == c)
+                    .map(|d| d as u128)
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if digits.len() != self.length {
+            return None;
+        }
+
+        for i in 0..self.length.saturating_sub(1) {
+            digits[i] = (digits[i] + base - digits[i + 1]) % base;
+        }
+        for i in (1..self.length).rev() {
+            digits[i] = (digits[i] + base - digits[i - 1]) % base;
+        }
+
+        let x = digits.iter().rev().fold(0u128, |acc, &d| acc * base + d);
+        let shifted = (x + self.modulus - self.offset) % self.modulus;
+        u64::try_from(mul_mod(shifted, self.inverse, self.modulus)).ok()
+    }
+}
+
+/// `a * b % m` without overflowing, for `m < 2^127`
+fn mul_mod(mut a: u128, mut b: u128, m: u128) -> u128 {
+    let mut result = 0;
+    a %= m;
+    while b > 0 {
+        if b & 1 == 1 {
+            result = (result + a) % m;
+        }
+        a = (a << 1) % m;
+        b >>= 1;
+    }
+    result
+}
+
+fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Inverse of `a` modulo `m` (extended Euclid), for `a` coprime with `m`
+fn mod_inverse(a: u128, m: u128) -> u128 {
+    // Track the Bezout coefficient modulo m to stay unsigned
+    let (mut old_r, mut r) = (a, m);
+    let (mut old_s, mut s) = (1u128, 0u128);
+    while r != 0 {
+        let q = old_r / r;
+        (old_r, r) = (r, old_r - q * r);
+        (old_s, s) = (s, (old_s + m - mul_mod(q, s, m)) % m);
+    }
+    old_s
+}
+
+/// FNV-1a, a stable hash so the same salt gives the same codes across builds
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Small deterministic generator for deriving the codec parameters
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn alphabet() -> Vec<char> {
+        ('0'..='9').chain('A'..='Z').chain('a'..='z').collect()
+    }
+
+    #[test]
+    fn test_round_trip_and_unique() {
+        let codec = SequenceCodec::new(&alphabet(), 6, "salt");
+        let mut seen = HashSet::new();
+        for value in (1..2000).chain([u64::from(u32::MAX), 62u64.pow(6) - 1]) {
+            let code = codec.encode(value).unwrap();
+            assert_eq!(code.len(), 6);
+            assert_eq!(codec.decode(&code), Some(value));
+            assert!(seen.insert(code));
+        }
+    }
+
+    #[test]
+    fn test_neighbours_differ_everywhere() {
+        let codec = SequenceCodec::new(&alphabet(), 8, "salt");
+        let a = codec.encode(41).unwrap();
+        let b = codec.encode(42).unwrap();
+        let same = a.chars().zip(b.chars()).filter(|(x, y)| x == y).count();
+        assert!(same <= 2, "{} and {} look sequential", a, b);
+    }
+
+    #[test]
+    fn test_salt_changes_codes() {
+        let a = SequenceCodec::new(&alphabet(), 8, "one");
+        let b = SequenceCodec::new(&alphabet(), 8, "two");
+        assert_ne!(a.encode(1), b.encode(1));
+        assert_eq!(
+            a.encode(1),
+            SequenceCodec::new(&alphabet(), 8, "one").encode(1)
+        );
+    }
+
+    #[test]
+    fn test_exhausted_and_invalid() {
+        let codec = SequenceCodec::new(&alphabet(), 4, "");
+        assert_eq!(codec.capacity(), 62u128.pow(4));
+        assert!(codec.encode(62u64.pow(4)).is_none());
+        assert!(codec.decode("abc").is_none());
+        assert!(codec.decode("ab-d").is_none());
+    }
+}
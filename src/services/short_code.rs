@@ -1,43 +1,109 @@
+use super::reserved::ReservedCodes;
+use super::sequence::SequenceCodec;
+use super::suggestions;
+use super::word_filter::WordFilter;
+use crate::config::{ShortCodeStrategy, UrlConfig};
 use crate::db::Repository;
 use crate::error::{AppError, AppResult};
+use chrono::{Datelike, Utc};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::warn;
 
-/// Character set for generating short codes.
-const ALPHABET_CHARS: &[char] = &[
-    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9',
-    'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M',
-    'N', 'O', 'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z',
-    'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm',
-    'n', 'o', 'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z',
-];
+/// Maximum number of alternatives offered for an unavailable custom code
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Whether `code` is acceptable as a custom code: 4-16 letters, digits, `_` or `-`
+pub fn is_valid_custom_code(code: &str) -> bool {
+    (4..=16).contains(&code.len())
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Whether a custom code can be used for a new link
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeAvailability {
+    Available,
+    /// Reserved for a route or by configuration
+    Reserved,
+    /// Used by an existing link
+    Taken,
+}
 
 /// Service for generating unique short codes.
-pub struct ShortCodeService;
+#[derive(Debug, Clone)]
+pub struct ShortCodeService {
+    strategy: ShortCodeStrategy,
+    length: usize,
+    max_attempts: u32,
+    alphabet: Vec<char>,
+    codec: SequenceCodec,
+    word_filter: WordFilter,
+    reserved: Arc<ReservedCodes>,
+}
 
 impl ShortCodeService {
+    /// Create a generator from the URL configuration
+    pub fn new(config: &UrlConfig) -> Self {
+        let alphabet = config
+            .short_code_alphabet
+            .chars(config.short_code_lowercase);
+
+        Self {
+            strategy: config.short_code_strategy,
+            length: config.short_code_length,
+            max_attempts: config.short_code_max_attempts,
+            codec: SequenceCodec::new(&alphabet, config.short_code_length, &config.short_code_salt),
+            alphabet,
+            word_filter: WordFilter::new(
+                config.short_code_word_filter_enabled,
+                &config.short_code_blocked_words,
+            ),
+            reserved: Arc::new(ReservedCodes::new(&config.reserved_codes)),
+        }
+    }
+
     /// Generate a unique short code that doesn't already exist in the database.
     ///
-    /// # Arguments
-    ///
-    /// * `length` - The desired length of the short code
-    /// * `max_attempts` - Maximum number of attempts to generate a unique code
-    /// * `repository` - Database repository to check for existing codes
-    ///
-    /// # Returns
-    ///
-    /// A unique short code string, or an error if unable to generate a unique code
-    /// after max_attempts.
+    /// With the random strategy codes are guessed until an unused one is found.
+    /// With the sequence strategy each attempt draws the next sequence value, so
+    /// a retry is only needed when a custom code (or a code from a different
+    /// salt or strategy) already occupies the encoded value. With the pool
+    /// strategy each attempt claims a pre-generated code, falling back to a
+    /// random code if the pool has run dry. Codes containing a blocked word are
+    /// discarded and count as a failed attempt, as do reserved codes.
     ///
     /// # Errors
     ///
     /// Returns `AppError::ShortCodeGenerationFailed` if unable to generate a unique
-    /// code within the specified number of attempts.
-    pub async fn generate_short_code(
-        length: usize,
-        max_attempts: u32,
-        repository: &Repository,
-    ) -> AppResult<String> {
-        for _ in 0..max_attempts {
-            let code = nanoid::nanoid!(length, ALPHABET_CHARS);
+    /// code within the configured number of attempts, or once the sequence has
+    /// used up every code of the configured length.
+    pub async fn generate_short_code(&self, repository: &Repository) -> AppResult<String> {
+        for _ in 0..self.max_attempts {
+            let code = match self.strategy {
+                ShortCodeStrategy::Random => self.random_code(),
+                ShortCodeStrategy::Sequence => {
+                    let value = repository.next_short_code_sequence().await?;
+                    u64::try_from(value)
+                        .ok()
+                        .and_then(|v| self.codec.encode(v))
+                        .ok_or(AppError::ShortCodeGenerationFailed)?
+                }
+                ShortCodeStrategy::Pool => match repository.claim_pooled_code().await? {
+                    Some(code) => code,
+                    None => {
+                        warn!("Short code pool is empty, generating a random code");
+                        self.random_code()
+                    }
+                },
+            };
+
+            if self.word_filter.is_blocked(&code)
+                || self.reserved.is_reserved(&code, repository).await?
+            {
+                continue;
+            }
 
             if !repository.short_code_exists(&code).await? {
                 return Ok(code);
@@ -46,22 +112,216 @@ impl ShortCodeService {
 
         Err(AppError::ShortCodeGenerationFailed)
     }
+
+    /// Whether a custom code is free, reserved or used by another link
+    pub async fn availability(
+        &self,
+        code: &str,
+        repository: &Repository,
+    ) -> AppResult<CodeAvailability> {
+        if self.reserved.is_reserved(code, repository).await? {
+            return Ok(CodeAvailability::Reserved);
+        }
+        if repository.short_code_exists(code).await? {
+            return Ok(CodeAvailability::Taken);
+        }
+        Ok(CodeAvailability::Available)
+    }
+
+    /// Check that a custom code is neither reserved nor taken by another link
+    ///
+    /// # Errors
+    ///
+    /// Returns `AppError::ShortCodeReserved` or `AppError::ShortCodeExists`,
+    /// with free alternatives attached as suggestions.
+    pub async fn ensure_available(&self, code: &str, repository: &Repository) -> AppResult<()> {
+        let error = match self.availability(code, repository).await? {
+            CodeAvailability::Available => return Ok(()),
+            CodeAvailability::Reserved => AppError::ShortCodeReserved(code.to_string()),
+            CodeAvailability::Taken => AppError::ShortCodeExists(code.to_string()),
+        };
+        let suggestions = self.suggest_alternatives(code, repository).await?;
+        Err(error.with_suggestions(suggestions))
+    }
+
+    /// Up to a handful of free variants of a valid custom code
+    ///
+    /// Candidates are checked with one query against existing links and one
+    /// against stored reservations, so a suggestion can still be taken by a
+    /// concurrent create before the client uses it.
+    pub async fn suggest_alternatives(
+        &self,
+        code: &str,
+        repository: &Repository,
+    ) -> AppResult<Vec<String>> {
+        let candidates: Vec<String> = suggestions::candidates(code, Utc::now().year())
+            .into_iter()
+            .filter(|c| !self.reserved.contains(c))
+            .collect();
+        let existing: HashSet<String> = repository
+            .existing_short_codes(&candidates)
+            .await?
+            .into_iter()
+            .collect();
+        let stored_reservations: HashSet<String> = repository
+            .list_reserved_codes()
+            .await?
+            .into_iter()
+            .collect();
+
+        Ok(candidates
+            .into_iter()
+            .filter(|c| {
+                !existing.contains(c) && !stored_reservations.contains(&c.to_ascii_lowercase())
+            })
+            .take(MAX_SUGGESTIONS)
+            .collect())
+    }
+
+    /// Codes that can't be used for links
+    pub fn reserved(&self) -> &ReservedCodes {
+        &self.reserved
+    }
+
+    /// The configured generation strategy
+    pub fn strategy(&self) -> ShortCodeStrategy {
+        self.strategy
+    }
+
+    /// A random code of the configured length, not checked for uniqueness or blocked words
+    pub fn random_code(&self) -> String {
+        let length = self.length;
+        nanoid::nanoid!(length, &self.alphabet)
+    }
+
+    /// Whether a generated code contains a blocked word or a name reserved in this process
+    pub fn is_blocked(&self, code: &str) -> bool {
+        self.word_filter.is_blocked(code) || self.reserved.contains(code)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::ShortCodeAlphabet;
 
     #[test]
-    fn test_alphabet_chars_const() {
-        // Verify the alphabet has 62 characters (0-9, A-Z, a-z)
-        assert_eq!(ALPHABET_CHARS.len(), 62);
+    fn test_default_alphabet() {
+        // Verify the default alphabet has 62 characters (0-9, A-Z, a-z)
+        let service = ShortCodeService::new(&url_config(ShortCodeStrategy::Random));
+        assert_eq!(service.alphabet.len(), 62);
     }
 
     #[test]
     fn test_alphabet_chars_unique() {
         // Verify all characters are unique
-        let unique: std::collections::HashSet<_> = ALPHABET_CHARS.iter().collect();
-        assert_eq!(unique.len(), ALPHABET_CHARS.len());
+        let service = ShortCodeService::new(&url_config(ShortCodeStrategy::Random));
+        let unique: std::collections::HashSet<_> = service.alphabet.iter().collect();
+        assert_eq!(unique.len(), service.alphabet.len());
+    }
+
+    #[test]
+    fn test_unambiguous_lowercase_codes() {
+        let mut config = url_config(ShortCodeStrategy::Random);
+        config.short_code_alphabet = ShortCodeAlphabet::Unambiguous;
+        config.short_code_lowercase = true;
+        let service = ShortCodeService::new(&config);
+
+        for _ in 0..100 {
+            let code = service.random_code();
+            assert!(code
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()));
+            assert!(!code.contains(['0', 'o', '1', 'l']));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blocked_codes_are_skipped() {
+        let repository: Repository = std::sync::Arc::new(crate::db::MemoryStorage::default());
+
+        // The code for sequence value 1 is blocked, so value 2 is used
+        let mut config = url_config(ShortCodeStrategy::Sequence);
+        let first = ShortCodeService::new(&config).codec.encode(1).unwrap();
+        config.short_code_blocked_words = vec![first.to_lowercase()];
+        let service = ShortCodeService::new(&config);
+        let code = service.generate_short_code(&repository).await.unwrap();
+        assert_eq!(code, service.codec.encode(2).unwrap());
+
+        // Blocking every character leaves nothing to generate
+        let mut config = url_config(ShortCodeStrategy::Random);
+        config.short_code_blocked_words = ('0'..='9')
+            .chain('a'..='z')
+            .map(|c| c.to_string())
+            .collect();
+        let service = ShortCodeService::new(&config);
+        assert!(matches!(
+            service.generate_short_code(&repository).await,
+            Err(AppError::ShortCodeGenerationFailed)
+        ));
+    }
+
+    fn url_config(strategy: ShortCodeStrategy) -> UrlConfig {
+        UrlConfig {
+            short_code_length: 6,
+            base_url: "http://localhost:3000".to_string(),
+            default_expiry_hours: 720,
+            short_code_max_attempts: 3,
+            short_code_strategy: strategy,
+            short_code_salt: "salt".to_string(),
+            short_code_pool_size: 10,
+            short_code_pool_low_water: 5,
+            short_code_alphabet: ShortCodeAlphabet::Base62,
+            short_code_lowercase: false,
+            short_code_word_filter_enabled: true,
+            short_code_blocked_words: Vec::new(),
+            reserved_codes: Vec::new(),
+            strict_url_validation: true,
+            click_counter_mode: crate::config::ClickCounterMode::Worker,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sequence_strategy_skips_taken_codes() {
+        let repository: Repository = std::sync::Arc::new(crate::db::MemoryStorage::default());
+        let service = ShortCodeService::new(&url_config(ShortCodeStrategy::Sequence));
+
+        // Occupy the code for sequence value 1, as a custom code could
+        let first = service.codec.encode(1).unwrap();
+        repository
+            .create_url(&first, "https://example.com", None, None)
+            .await
+            .unwrap();
+
+        let code = service.generate_short_code(&repository).await.unwrap();
+        assert_eq!(code, service.codec.encode(2).unwrap());
+        assert_eq!(code.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_pool_strategy_claims_then_falls_back() {
+        let repository: Repository = std::sync::Arc::new(crate::db::MemoryStorage::default());
+        let service = ShortCodeService::new(&url_config(ShortCodeStrategy::Pool));
+        repository
+            .add_pooled_codes(&["pooled".to_string()])
+            .await
+            .unwrap();
+
+        assert_eq!(
+            service.generate_short_code(&repository).await.unwrap(),
+            "pooled"
+        );
+        assert_eq!(repository.count_pooled_codes().await.unwrap(), 0);
+
+        let code = service.generate_short_code(&repository).await.unwrap();
+        assert_eq!(code.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_random_strategy_length() {
+        let repository: Repository = std::sync::Arc::new(crate::db::MemoryStorage::default());
+        let service = ShortCodeService::new(&url_config(ShortCodeStrategy::Random));
+        let code = service.generate_short_code(&repository).await.unwrap();
+        assert_eq!(code.len(), 6);
     }
 }
@@ -0,0 +1,113 @@
+//! Alternatives to offer when a requested custom code is unavailable.
+//!
+//! Candidates are variants of the requested code in rough order of
+//! similarity: separator changes, numbered and year suffixes, and a few
+//! hyphenated vanity forms. The caller filters out codes that are taken.
+
+use super::short_code::is_valid_custom_code;
+
+/// Hyphenated vanity forms, `{}` standing for the requested code
+const VANITY_FORMS: &[&str] = &["get-{}", "{}-now", "try-{}", "my{}", "{}-hq", "go-{}"];
+
+/// Variants of `code` (itself a valid custom code) that are valid custom codes, most similar first
+pub fn candidates(code: &str, year: i32) -> Vec<String> {
+    let mut candidates = Vec::new();
+
+    // Same words with different separators
+    if code.contains(['-', '_']) {
+        candidates.push(code.replace('_', "-"));
+        candidates.push(code.replace('-', "_"));
+        candidates.push(code.replace(['-', '_'], ""));
+    }
+
+    candidates.push(with_suffix(code, "2"));
+    candidates.push(with_suffix(code, "-2"));
+    candidates.push(with_suffix(code, &format!("{:02}", year % 100)));
+    candidates.push(with_suffix(code, &format!("-{}", year)));
+    candidates.extend(VANITY_FORMS.iter().map(|form| vanity(code, form)));
+    for n in 3..=9 {
+        candidates.push(with_suffix(code, &n.to_string()));
+        candidates.push(with_suffix(code, &format!("-{}", n)));
+    }
+
+    let mut seen = Vec::new();
+    candidates.retain(|c| {
+        let keep = c != code && is_valid_custom_code(c) && !seen.contains(c);
+        seen.push(c.clone());
+        keep
+    });
+    candidates
+}
+
+/// `code` followed by `suffix`, shortening `code` if the result would be too long
+fn with_suffix(code: &str, suffix: &str) -> String {
+    let keep = code.len().min(16usize.saturating_sub(suffix.len()));
+    format!("{}{}", &code[..keep], suffix)
+}
+
+/// `code` substituted into a vanity form, shortening `code` to fit
+fn vanity(code: &str, form: &str) -> String {
+    let keep = code.len().min(16usize.saturating_sub(form.len() - 2));
+    form.replace("{}", &code[..keep])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_candidates_order_and_validity() {
+        let launch = candidates("launch", 2026);
+        assert_eq!(
+            launch[..6],
+            [
+                "launch2",
+                "launch-2",
+                "launch26",
+                "launch-2026",
+                "get-launch",
+                "launch-now"
+            ]
+        );
+        assert!(launch.iter().all(|c| is_valid_custom_code(c)));
+    }
+
+    #[test]
+    fn test_separator_variants_and_long_codes() {
+        let sale = candidates("spring_sale", 2026);
+        assert_eq!(sale[..2], ["spring-sale", "springsale"]);
+
+        let long = candidates("abcdefghijklmnop", 2026);
+        assert_eq!(long[0], "abcdefghijklmno2");
+        assert!(long.iter().all(|c| c.len() <= 16));
+    }
+
+    #[test]
+    fn test_documented_examples_match_candidates() {
+        // The README and OpenAPI examples show the suggestions for `launch` in 2026
+        let launch = candidates("launch", 2026);
+
+        let readme = include_str!("../../README.md");
+        let readme_examples: Vec<Vec<String>> = readme
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("\"suggestions\": "))
+            .map(|list| serde_json::from_str(list).unwrap())
+            .collect();
+        assert_eq!(readme_examples.len(), 2);
+        for example in readme_examples {
+            assert_eq!(example, launch[..example.len()]);
+        }
+
+        let openapi = include_str!("../../docs/openapi.yaml");
+        let openapi_examples: Vec<Vec<String>> = openapi
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("example: [launch"))
+            .map(|list| format!("launch{}", list.trim_end_matches(']')))
+            .map(|list| list.split(", ").map(String::from).collect())
+            .collect();
+        assert_eq!(openapi_examples.len(), 2);
+        for example in openapi_examples {
+            assert_eq!(example, launch[..example.len()]);
+        }
+    }
+}
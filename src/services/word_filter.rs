@@ -0,0 +1,90 @@
+//! Blocked-word filter for generated short codes.
+//!
+//! Random codes occasionally spell out offensive words, which is embarrassing
+//! on printed material. Generated codes are rejected when they contain a
+//! blocked word, compared case-insensitively and with common digit
+//! substitutions (`5h1t`) undone. Custom codes are never filtered.
+
+/// Built-in blocked substrings, used unless the word filter is disabled
+const DEFAULT_BLOCKED_WORDS: &[&str] = &[
+    "anal", "anus", "arse", "ass", "bitch", "butt", "cock", "coon", "crap", "cum", "cunt", "dick",
+    "dyke", "fag", "fuck", "jizz", "kike", "nazi", "nigg", "penis", "piss", "poop", "porn",
+    "pussy", "rape", "sex", "shit", "slut", "spic", "tits", "twat", "wank", "whore",
+];
+
+/// Digits commonly used in place of letters
+const DIGIT_SUBSTITUTIONS: &[(char, char)] = &[
+    ('0', 'o'),
+    ('1', 'i'),
+    ('3', 'e'),
+    ('4', 'a'),
+    ('5', 's'),
+    ('7', 't'),
+];
+
+/// Configurable blocked-word filter
+#[derive(Debug, Clone)]
+pub struct WordFilter {
+    words: Vec<String>,
+}
+
+impl WordFilter {
+    /// Create a filter from the built-in list (if enabled) plus extra words
+    pub fn new(use_defaults: bool, extra_words: &[String]) -> Self {
+        let defaults = DEFAULT_BLOCKED_WORDS
+            .iter()
+            .filter(|_| use_defaults)
+            .map(|w| w.to_string());
+        let mut words: Vec<String> = defaults
+            .chain(extra_words.iter().map(|w| w.trim().to_ascii_lowercase()))
+            .filter(|w| !w.is_empty())
+            .collect();
+        words.sort();
+        words.dedup();
+
+        Self { words }
+    }
+
+    /// Whether a generated code contains a blocked word
+    pub fn is_blocked(&self, code: &str) -> bool {
+        if self.words.is_empty() {
+            return false;
+        }
+
+        let lower = code.to_ascii_lowercase();
+        let unsubstituted: String = lower
+            .chars()
+            .map(|c| {
+                DIGIT_SUBSTITUTIONS
+                    .iter()
+                    .find(|(digit, _)| *digit == c)
+                    .map_or(c, |(_, letter)| *letter)
+            })
+            .collect();
+
+        self.words
+            .iter()
+            .any(|w| lower.contains(w.as_str()) || unsubstituted.contains(w.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks_words_case_insensitively() {
+        let filter = WordFilter::new(true, &[]);
+        assert!(filter.is_blocked("xxSHiTxx"));
+        assert!(filter.is_blocked("ab5h1tcd"));
+        assert!(!filter.is_blocked("aB3dE9xQ"));
+    }
+
+    #[test]
+    fn test_extra_words_and_disabled_defaults() {
+        let filter = WordFilter::new(false, &[" Promo ".to_string()]);
+        assert!(filter.is_blocked("xPROMOx"));
+        assert!(!filter.is_blocked("xxshitxx"));
+        assert!(!WordFilter::new(false, &[]).is_blocked("xxshitxx"));
+    }
+}
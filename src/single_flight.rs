@@ -0,0 +1,143 @@
+//! Request coalescing for expensive lookups.
+//!
+//! When many requests miss the cache for the same key at once, only the first
+//! one runs the lookup; the others wait for its result instead of issuing
+//! their own queries.
+
+use crate::error::{AppError, AppResult};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::sync::OnceCell;
+
+/// In-flight lookup shared by all callers for one key
+type Call<T> = Arc<OnceCell<Result<T, Arc<AppError>>>>;
+
+/// Coalesces concurrent calls for the same key into a single execution
+pub struct SingleFlight<T> {
+    in_flight: Mutex<HashMap<String, Call<T>>>,
+}
+
+impl<T> Default for SingleFlight<T> {
+    fn default() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T: Clone> SingleFlight<T> {
+    /// Run `lookup` for `key`, or wait for the result of a call already in flight
+    ///
+    /// A failed lookup's error is shared with every caller as
+    /// [`AppError::Shared`], so all of them get the same response; the caller
+    /// that ran the lookup gets it unwrapped if nobody else is holding it.
+    /// Results are not kept once the call completes. If the running caller is
+    /// cancelled, one of the waiting callers runs its own lookup instead.
+    pub async fn run<F, Fut>(&self, key: &str, lookup: F) -> AppResult<T>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = AppResult<T>>,
+    {
+        let call = self
+            .in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(key.to_string())
+            .or_default()
+            .clone();
+
+        let result = call
+            .get_or_init(|| async { lookup().await.map_err(Arc::new) })
+            .await
+            .clone();
+
+        self.finish(key, &call);
+        drop(call);
+
+        result.map_err(|e| Arc::try_unwrap(e).unwrap_or_else(AppError::Shared))
+    }
+
+    /// Forget a completed call so the next caller starts a fresh lookup
+    fn finish(&self, key: &str, call: &Call<T>) {
+        let mut in_flight = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+        if in_flight
+            .get(key)
+            .is_some_and(|current| Arc::ptr_eq(current, call))
+        {
+            in_flight.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::response::IntoResponse;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_are_coalesced() {
+        let group = Arc::new(SingleFlight::<i64>::default());
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let tasks: Vec<_> = (0..50)
+            .map(|_| {
+                let group = group.clone();
+                let calls = calls.clone();
+                tokio::spawn(async move {
+                    group
+                        .run("hot", || async {
+                            calls.fetch_add(1, Ordering::SeqCst);
+                            tokio::time::sleep(Duration::from_millis(50)).await;
+                            Ok(42)
+                        })
+                        .await
+                })
+            })
+            .collect();
+
+        for task in tasks {
+            assert_eq!(task.await.unwrap().unwrap(), 42);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(group.in_flight.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sequential_calls_are_not_cached() {
+        let group = SingleFlight::<i64>::default();
+
+        assert_eq!(group.run("key", || async { Ok(1) }).await.unwrap(), 1);
+        assert_eq!(group.run("key", || async { Ok(2) }).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_error_shared_with_waiters() {
+        let group = Arc::new(SingleFlight::<i64>::default());
+
+        let leader = {
+            let group = group.clone();
+            tokio::spawn(async move {
+                group
+                    .run("key", || async {
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        Err(AppError::UrlNotFound("key".to_string()))
+                    })
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let waiter = group.run("key", || async { Ok(7) }).await;
+
+        // Both callers report the leader's error, with the same response
+        assert!(
+            matches!(waiter, Err(AppError::Shared(ref e)) if matches!(**e, AppError::UrlNotFound(_)))
+        );
+        for result in [leader.await.unwrap(), waiter] {
+            let response = result.unwrap_err().into_response();
+            assert_eq!(response.status(), axum::http::StatusCode::NOT_FOUND);
+        }
+    }
+}
@@ -1,7 +1,13 @@
 use crate::auth::AuthService;
-use crate::cache::Cache;
+use crate::bot_detection::BotDetector;
+use crate::cache::{CacheGenerations, SharedCache};
+use crate::config::{CacheBackendKind, ClickCounterMode, Config};
 use crate::db::Repository;
 use crate::jobs::JobSender;
+use crate::models::UrlEntry;
+use crate::services::ShortCodeService;
+use crate::single_flight::SingleFlight;
+use std::sync::Arc;
 
 /// Application state shared across all HTTP handlers.
 ///
@@ -13,8 +19,14 @@ pub struct AppState {
     /// Database repository for URL and user operations
     pub repository: Repository,
 
-    /// Redis cache for storing frequently accessed URLs
-    pub cache: Cache,
+    /// Cache backend for frequently accessed URLs (may be a no-op)
+    pub cache: SharedCache,
+
+    /// Coalesces concurrent database lookups of the same short code
+    pub url_lookups: Arc<SingleFlight<Option<UrlEntry>>>,
+
+    /// Lets lookups that raced with an update or delete skip re-caching
+    pub cache_generations: Arc<CacheGenerations>,
 
     /// JWT authentication service for token generation and validation
     pub auth_service: AuthService,
@@ -28,15 +40,67 @@ pub struct AppState {
     /// Default expiry time for newly created short URLs (in hours)
     pub default_expiry_hours: i64,
 
-    /// Length of randomly generated short codes
-    pub short_code_length: usize,
-
-    /// Maximum number of attempts to generate a unique short code
-    pub short_code_max_attempts: u32,
-
-    /// Whether caching is enabled for URL lookups
-    pub cache_enabled: bool,
+    /// Generates short codes for URLs created without a custom code
+    pub short_codes: ShortCodeService,
 
     /// Whether strict URL validation is enabled (requires http:// or https://)
     pub strict_url_validation: bool,
+
+    /// Where redirect click counts are accumulated before reaching the database
+    pub click_counter_mode: ClickCounterMode,
+
+    /// Whether per-click analytics events are recorded
+    pub click_events_enabled: bool,
+
+    /// Salt used when hashing client IPs for click events
+    pub ip_hash_salt: String,
+
+    /// Request header carrying the client's country code (lowercase)
+    pub country_header: String,
+
+    /// Classifies redirect requests as bot or human traffic
+    pub bot_detector: BotDetector,
+
+    /// Whether unique visitors are tracked (requires the cache)
+    pub unique_visitors_enabled: bool,
+
+    /// Days of unique-visitor history kept in Redis
+    pub unique_visitor_retention_days: i64,
+}
+
+impl AppState {
+    /// Build the handler state from configuration and already-connected backends
+    pub fn new(
+        config: &Config,
+        repository: Repository,
+        cache: SharedCache,
+        job_sender: JobSender,
+    ) -> Self {
+        Self {
+            repository,
+            cache,
+            url_lookups: Arc::new(SingleFlight::default()),
+            cache_generations: Arc::new(CacheGenerations::default()),
+            auth_service: AuthService::new(
+                config.auth.jwt_secret.clone(),
+                config.auth.jwt_expiration_hours,
+            ),
+            job_sender,
+            base_url: config.url.base_url.clone(),
+            default_expiry_hours: config.url.default_expiry_hours,
+            short_codes: ShortCodeService::new(&config.url),
+            strict_url_validation: config.url.strict_url_validation,
+            click_counter_mode: config.url.click_counter_mode,
+            click_events_enabled: config.analytics.click_events_enabled,
+            ip_hash_salt: config.analytics.ip_hash_salt.clone(),
+            country_header: config.analytics.country_header.clone(),
+            bot_detector: BotDetector::new(
+                config.analytics.bot_filter_enabled,
+                config.analytics.bot_user_agent_patterns.clone(),
+            ),
+            unique_visitors_enabled: config.cache.backend != CacheBackendKind::None
+                && config.analytics.unique_visitors_enabled,
+            unique_visitor_retention_days: config.analytics.unique_visitor_retention_days,
+        }
+    }
 }
@@ -0,0 +1,366 @@
+//! User-agent classification for click analytics.
+//!
+//! Turns a raw `User-Agent` header into a browser family and major version,
+//! an OS family and a coarse device class. The rules are intentionally simple
+//! substring checks ordered from most to least specific; they cover the
+//! browsers and crawlers that show up in real traffic without pulling in a
+//! full UA database.
+
+/// Coarse device class of a client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceClass {
+    Desktop,
+    Mobile,
+    Tablet,
+    Bot,
+}
+
+impl DeviceClass {
+    /// Name stored in the database and returned by the API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeviceClass::Desktop => "desktop",
+            DeviceClass::Mobile => "mobile",
+            DeviceClass::Tablet => "tablet",
+            DeviceClass::Bot => "bot",
+        }
+    }
+}
+
+/// Classified user agent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAgentInfo {
+    /// Browser (or crawler) family, e.g. "Chrome" or "Googlebot"
+    pub browser_family: String,
+    /// Major browser version, if present
+    pub browser_version: Option<String>,
+    /// Operating system family, e.g. "Windows" or "iOS"
+    pub os_family: String,
+    /// Device class
+    pub device_class: DeviceClass,
+}
+
+/// Known crawlers, link unfurlers and HTTP clients: (UA token, family name)
+const KNOWN_BOTS: &[(&str, &str)] = &[
+    ("googlebot", "Googlebot"),
+    ("bingbot", "Bingbot"),
+    ("duckduckbot", "DuckDuckBot"),
+    ("yandexbot", "YandexBot"),
+    ("baiduspider", "Baiduspider"),
+    ("applebot", "Applebot"),
+    ("slackbot", "Slackbot"),
+    ("twitterbot", "Twitterbot"),
+    ("facebookexternalhit", "Facebook"),
+    ("facebookcatalog", "Facebook"),
+    ("discordbot", "Discordbot"),
+    ("linkedinbot", "LinkedInBot"),
+    ("telegrambot", "TelegramBot"),
+    ("whatsapp", "WhatsApp"),
+    ("skypeuripreview", "Skype"),
+    ("curl/", "curl"),
+    ("wget/", "Wget"),
+    ("python-requests", "Python Requests"),
+    ("go-http-client", "Go HTTP Client"),
+];
+
+/// Generic substrings that indicate an automated client
+const GENERIC_BOT_MARKERS: &[&str] = &["crawler", "spider", "headless"];
+
+/// Browser tokens in match order: (UA token, family name)
+///
+/// Order matters because most browsers also advertise the engines they are
+/// based on (Edge and Opera claim Chrome, Chrome claims Safari, etc.).
+const BROWSERS: &[(&str, &str)] = &[
+    ("EdgA/", "Edge"),
+    ("EdgiOS/", "Edge"),
+    ("Edg/", "Edge"),
+    ("Edge/", "Edge"),
+    ("OPR/", "Opera"),
+    ("SamsungBrowser/", "Samsung Internet"),
+    ("FxiOS/", "Firefox"),
+    ("Firefox/", "Firefox"),
+    ("CriOS/", "Chrome"),
+    ("Chrome/", "Chrome"),
+    ("Version/", "Safari"),
+    ("MSIE ", "Internet Explorer"),
+    ("Trident/", "Internet Explorer"),
+];
+
+/// Classify a raw user-agent string
+pub fn classify(user_agent: &str) -> UserAgentInfo {
+    let lower = user_agent.to_ascii_lowercase();
+    let os_family = detect_os(user_agent).to_string();
+
+    if let Some(bot_family) = detect_bot(&lower) {
+        return UserAgentInfo {
+            browser_family: bot_family.to_string(),
+            browser_version: None,
+            os_family,
+            device_class: DeviceClass::Bot,
+        };
+    }
+
+    let (browser_family, browser_version) = detect_browser(user_agent);
+
+    UserAgentInfo {
+        browser_family: browser_family.to_string(),
+        browser_version,
+        device_class: detect_device(user_agent, &os_family),
+        os_family,
+    }
+}
+
+/// Return the crawler family if the (lowercased) user agent looks automated
+fn detect_bot(lower: &str) -> Option<&'static str> {
+    if lower.trim().is_empty() {
+        return Some("Other Bot");
+    }
+
+    if let Some((_, family)) = KNOWN_BOTS.iter().find(|(token, _)| lower.contains(token)) {
+        return Some(family);
+    }
+
+    (GENERIC_BOT_MARKERS
+        .iter()
+        .any(|marker| lower.contains(marker))
+        || has_bot_token(lower))
+    .then_some("Other Bot")
+}
+
+/// Whether "bot" appears as a crawler name (`AhrefsBot/7.0`, `PetalBot;`) or a word of its own
+///
+/// A bare substring match would also catch device names such as Cubot phones.
+fn has_bot_token(lower: &str) -> bool {
+    lower.match_indices("bot").any(|(idx, _)| {
+        let before = lower[..idx].chars().next_back();
+        let after = lower[idx + 3..].chars().next();
+        let is_word_char = |c: Option<char>| c.is_some_and(|c| c.is_ascii_alphanumeric());
+        matches!(after, Some('/' | ';' | '-')) || (!is_word_char(before) && !is_word_char(after))
+    })
+}
+
+fn detect_browser(user_agent: &str) -> (&'static str, Option<String>) {
+    for (token, family) in BROWSERS {
+        if let Some(idx) = user_agent.find(token) {
+            // "Version/" alone is only Safari when the Safari token is also present
+            if *token == "Version/" && !user_agent.contains("Safari/") {
+                continue;
+            }
+
+            let version = if *token == "Trident/" {
+                trident_to_ie_version(&user_agent[idx + token.len()..])
+            } else {
+                major_version(&user_agent[idx + token.len()..])
+            };
+            return (family, version.map(|v| v.to_string()));
+        }
+    }
+
+    ("Other", None)
+}
+
+fn detect_os(user_agent: &str) -> &'static str {
+    if user_agent.contains("Windows") {
+        "Windows"
+    } else if user_agent.contains("iPhone")
+        || user_agent.contains("iPad")
+        || user_agent.contains("iPod")
+    {
+        "iOS"
+    } else if user_agent.contains("Android") {
+        "Android"
+    } else if user_agent.contains("CrOS") {
+        "Chrome OS"
+    } else if user_agent.contains("Macintosh") || user_agent.contains("Mac OS X") {
+        "macOS"
+    } else if user_agent.contains("Linux") || user_agent.contains("X11") {
+        "Linux"
+    } else {
+        "Other"
+    }
+}
+
+fn detect_device(user_agent: &str, os_family: &str) -> DeviceClass {
+    if user_agent.contains("iPad") || user_agent.contains("Tablet") {
+        return DeviceClass::Tablet;
+    }
+
+    // Android phones advertise "Mobile"; Android tablets don't
+    if os_family == "Android" {
+        return if user_agent.contains("Mobile") {
+            DeviceClass::Mobile
+        } else {
+            DeviceClass::Tablet
+        };
+    }
+
+    if user_agent.contains("Mobi") || user_agent.contains("iPhone") || user_agent.contains("iPod") {
+        return DeviceClass::Mobile;
+    }
+
+    DeviceClass::Desktop
+}
+
+/// Extract the leading major version number from a version string
+///
+/// Versions that don't fit a `u32` are dropped, which keeps the stored value
+/// within the `browser_version` column.
+fn major_version(rest: &str) -> Option<u32> {
+    let major: String = rest.chars().take_while(|c| c.is_ascii_digit()).collect();
+    major.parse().ok()
+}
+
+/// Map a Trident engine version to the Internet Explorer version it ships with
+fn trident_to_ie_version(rest: &str) -> Option<u32> {
+    major_version(rest).and_then(|trident| trident.checked_add(4))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// (user agent, browser family, browser version, OS family, device class)
+    const CORPUS: &[(&str, &str, Option<&str>, &str, DeviceClass)] = &[
+        (
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            "Chrome", Some("120"), "Windows", DeviceClass::Desktop,
+        ),
+        (
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 Edg/120.0.2210.91",
+            "Edge", Some("120"), "Windows", DeviceClass::Desktop,
+        ),
+        (
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:121.0) Gecko/20100101 Firefox/121.0",
+            "Firefox", Some("121"), "Windows", DeviceClass::Desktop,
+        ),
+        (
+            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Safari/605.1.15",
+            "Safari", Some("17"), "macOS", DeviceClass::Desktop,
+        ),
+        (
+            "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36 OPR/106.0.0.0",
+            "Opera", Some("106"), "macOS", DeviceClass::Desktop,
+        ),
+        (
+            "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/119.0.0.0 Safari/537.36",
+            "Chrome", Some("119"), "Linux", DeviceClass::Desktop,
+        ),
+        (
+            "Mozilla/5.0 (X11; CrOS x86_64 14541.0.0) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            "Chrome", Some("120"), "Chrome OS", DeviceClass::Desktop,
+        ),
+        (
+            "Mozilla/5.0 (Windows NT 6.1; WOW64; Trident/7.0; rv:11.0) like Gecko",
+            "Internet Explorer", Some("11"), "Windows", DeviceClass::Desktop,
+        ),
+        (
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Mobile/15E148 Safari/604.1",
+            "Safari", Some("17"), "iOS", DeviceClass::Mobile,
+        ),
+        (
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) CriOS/120.0.6099.119 Mobile/15E148 Safari/604.1",
+            "Chrome", Some("120"), "iOS", DeviceClass::Mobile,
+        ),
+        (
+            "Mozilla/5.0 (iPhone; CPU iPhone OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) FxiOS/121.0 Mobile/15E148 Safari/605.1.15",
+            "Firefox", Some("121"), "iOS", DeviceClass::Mobile,
+        ),
+        (
+            "Mozilla/5.0 (iPad; CPU OS 17_2 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.2 Mobile/15E148 Safari/604.1",
+            "Safari", Some("17"), "iOS", DeviceClass::Tablet,
+        ),
+        (
+            "Mozilla/5.0 (Linux; Android 14; Pixel 8) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.6099.144 Mobile Safari/537.36",
+            "Chrome", Some("120"), "Android", DeviceClass::Mobile,
+        ),
+        (
+            "Mozilla/5.0 (Linux; Android 13; SM-S918B) AppleWebKit/537.36 (KHTML, like Gecko) SamsungBrowser/23.0 Chrome/115.0.0.0 Mobile Safari/537.36",
+            "Samsung Internet", Some("23"), "Android", DeviceClass::Mobile,
+        ),
+        (
+            "Mozilla/5.0 (Linux; Android 13; SM-X710) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
+            "Chrome", Some("120"), "Android", DeviceClass::Tablet,
+        ),
+        (
+            "Mozilla/5.0 (Android 14; Mobile; rv:121.0) Gecko/121.0 Firefox/121.0",
+            "Firefox", Some("121"), "Android", DeviceClass::Mobile,
+        ),
+        (
+            "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/99999999999999999.0 Safari/537.36",
+            "Chrome", None, "Linux", DeviceClass::Desktop,
+        ),
+        (
+            "Mozilla/5.0 (Linux; Android 10; CUBOT X30) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Mobile Safari/537.36",
+            "Chrome", Some("120"), "Android", DeviceClass::Mobile,
+        ),
+        (
+            "Mozilla/5.0 (compatible; Googlebot/2.1; +http://www.google.com/bot.html)",
+            "Googlebot", None, "Other", DeviceClass::Bot,
+        ),
+        (
+            "Mozilla/5.0 (compatible; bingbot/2.0; +http://www.bing.com/bingbot.htm)",
+            "Bingbot", None, "Other", DeviceClass::Bot,
+        ),
+        (
+            "Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)",
+            "Slackbot", None, "Other", DeviceClass::Bot,
+        ),
+        (
+            "Twitterbot/1.0",
+            "Twitterbot", None, "Other", DeviceClass::Bot,
+        ),
+        (
+            "facebookexternalhit/1.1 (+http://www.facebook.com/externalhit_uatext.php)",
+            "Facebook", None, "Other", DeviceClass::Bot,
+        ),
+        (
+            "Mozilla/5.0 (compatible; Discordbot/2.0; +https://discordapp.com)",
+            "Discordbot", None, "Other", DeviceClass::Bot,
+        ),
+        (
+            "Mozilla/5.0 (compatible; AhrefsBot/7.0; +http://ahrefs.com/robot/)",
+            "Other Bot", None, "Other", DeviceClass::Bot,
+        ),
+        (
+            "Mozilla/5.0 (Linux; Android 7.0;) AppleWebKit/537.36 (KHTML, like Gecko) Mobile Safari/537.36 (compatible; PetalBot;+https://webmaster.petalsearch.com/site/petalbot)",
+            "Other Bot", None, "Android", DeviceClass::Bot,
+        ),
+        (
+            "curl/8.4.0",
+            "curl", None, "Other", DeviceClass::Bot,
+        ),
+        (
+            "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) HeadlessChrome/120.0.0.0 Safari/537.36",
+            "Other Bot", None, "Linux", DeviceClass::Bot,
+        ),
+    ];
+
+    #[test]
+    fn test_classify_corpus() {
+        for (ua, browser, version, os, device) in CORPUS {
+            let info = classify(ua);
+            assert_eq!(info.browser_family, *browser, "browser for {}", ua);
+            assert_eq!(
+                info.browser_version.as_deref(),
+                *version,
+                "version for {}",
+                ua
+            );
+            assert_eq!(info.os_family, *os, "OS for {}", ua);
+            assert_eq!(info.device_class, *device, "device for {}", ua);
+        }
+    }
+
+    #[test]
+    fn test_classify_unrecognized() {
+        let info = classify("SomeCustomClient");
+        assert_eq!(info.browser_family, "Other");
+        assert_eq!(info.os_family, "Other");
+        assert_eq!(info.device_class, DeviceClass::Desktop);
+    }
+
+    #[test]
+    fn test_classify_empty_is_bot() {
+        assert_eq!(classify("").device_class, DeviceClass::Bot);
+    }
+}
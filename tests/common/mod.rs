@@ -0,0 +1,203 @@
+//! Shared harness for in-process router tests.
+//!
+//! Builds the full application (state, worker and router) on the in-memory
+//! storage and cache backends, so requests go through the same handlers and
+//! middleware as a real server without needing PostgreSQL or Redis.
+
+use axum::http::StatusCode;
+use axum_test::TestServer;
+use rustlink::auth::AuthService;
+use rustlink::cache::SharedCache;
+use rustlink::config::{
+    AnalyticsConfig, AuthConfig, CacheBackendKind, CacheConfig, ClickCounterMode, Config,
+    CorsConfig, DatabaseConfig, JobsConfig, RateLimitConfig, ServerConfig, ShortCodeAlphabet,
+    ShortCodeStrategy, UrlConfig,
+};
+use rustlink::db::Repository;
+use rustlink::jobs::{create_job_channel, Worker, WorkerConfig};
+use rustlink::routes::{create_router, AppState};
+use rustlink::{cache, db};
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+/// Browser user agent, so redirects count as human clicks
+pub const BROWSER_USER_AGENT: &str =
+    "Mozilla/5.0 (X11; Linux x86_64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0 Safari/537.36";
+
+/// A running application and direct access to its storage
+pub struct TestApp {
+    pub server: TestServer,
+    pub repository: Repository,
+    pub cache: SharedCache,
+    auth_service: AuthService,
+}
+
+/// Configuration used by the harness, with limits high enough not to interfere
+pub fn test_config() -> Config {
+    Config {
+        server: ServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 3000,
+        },
+        database: DatabaseConfig {
+            url: "memory:".to_string(),
+            max_connections: 1,
+            min_connections: 1,
+            acquire_timeout_seconds: 5,
+            replica_urls: Vec::new(),
+            read_your_writes_seconds: 5,
+            replica_failure_threshold: 3,
+            replica_cooldown_seconds: 30,
+        },
+        cache: CacheConfig {
+            backend: CacheBackendKind::Memory,
+            url: String::new(),
+            max_connections: 1,
+            default_ttl_seconds: 3600,
+            local_max_entries: 1000,
+            local_ttl_seconds: 5,
+            negative_ttl_seconds: 60,
+            early_refresh_seconds: 0,
+            breaker_failure_threshold: 5,
+            breaker_cooldown_seconds: 10,
+            command_timeout_ms: 500,
+        },
+        url: UrlConfig {
+            short_code_length: 8,
+            base_url: "http://localhost:3000".to_string(),
+            default_expiry_hours: 720,
+            short_code_max_attempts: 10,
+            short_code_strategy: ShortCodeStrategy::Random,
+            short_code_salt: String::new(),
+            short_code_pool_size: 10000,
+            short_code_pool_low_water: 2000,
+            short_code_alphabet: ShortCodeAlphabet::Base62,
+            short_code_lowercase: false,
+            short_code_word_filter_enabled: true,
+            short_code_blocked_words: Vec::new(),
+            reserved_codes: Vec::new(),
+            strict_url_validation: true,
+            click_counter_mode: ClickCounterMode::Worker,
+        },
+        auth: AuthConfig {
+            jwt_secret: "test_secret_for_router_tests".to_string(),
+            jwt_expiration_hours: 1,
+        },
+        rate_limit: RateLimitConfig {
+            requests_per_minute: 6000,
+            burst_size: 1000,
+        },
+        cors: CorsConfig {
+            allowed_origins: vec!["*".to_string()],
+        },
+        analytics: AnalyticsConfig {
+            click_events_enabled: true,
+            ip_hash_salt: "test_salt_value_1234".to_string(),
+            country_header: "cf-ipcountry".to_string(),
+            bot_filter_enabled: true,
+            bot_user_agent_patterns: Vec::new(),
+            unique_visitors_enabled: true,
+            unique_visitor_retention_days: 90,
+        },
+        jobs: JobsConfig {
+            click_flush_interval_ms: 10,
+            click_batch_size: 500,
+            code_pool_refill_interval_ms: 1000,
+        },
+    }
+}
+
+impl TestApp {
+    /// Start the application with the default test configuration
+    pub async fn new() -> Self {
+        Self::with_config(test_config()).await
+    }
+
+    /// Start the application with a custom configuration
+    pub async fn with_config(config: Config) -> Self {
+        let repository = db::connect(&config.database)
+            .await
+            .expect("storage should connect");
+        Self::with_repository(config, repository).await
+    }
+
+    /// Start the application on storage built by the test, e.g. shared between instances
+    pub async fn with_repository(config: Config, repository: Repository) -> Self {
+        let cache = cache::from_config(&config.cache)
+            .await
+            .expect("cache should build");
+
+        let (job_sender, job_receiver) = create_job_channel();
+        let worker = Worker::new(repository.clone(), job_receiver).with_config(WorkerConfig {
+            click_flush_interval_ms: config.jobs.click_flush_interval_ms,
+            click_batch_size: config.jobs.click_batch_size,
+            ..WorkerConfig::default()
+        });
+        tokio::spawn(worker.run());
+
+        let state = Arc::new(AppState::new(
+            &config,
+            repository.clone(),
+            cache.clone(),
+            job_sender,
+        ));
+        let router = create_router(state, config.cors.allowed_origins, config.rate_limit)
+            .expect("router should build");
+
+        Self {
+            server: TestServer::new(router).expect("test server should start"),
+            repository,
+            cache,
+            auth_service: AuthService::new(
+                config.auth.jwt_secret.clone(),
+                config.auth.jwt_expiration_hours,
+            ),
+        }
+    }
+
+    /// Create an active user directly in storage
+    pub async fn create_user(&self, username: &str, password: &str) {
+        // Minimum cost keeps the tests fast
+        let hash = bcrypt::hash(password, 4).expect("password should hash");
+        self.repository
+            .create_user(username, &hash)
+            .await
+            .expect("user should be created");
+    }
+
+    /// Log in and return a bearer token
+    pub async fn login(&self, username: &str, password: &str) -> String {
+        let response = self
+            .server
+            .post("/login")
+            .json(&json!({ "username": username, "password": password }))
+            .await;
+        response.assert_status(StatusCode::OK);
+
+        response.json::<Value>()["token"]
+            .as_str()
+            .expect("login response should contain a token")
+            .to_string()
+    }
+
+    /// Create a user and return a token for them
+    pub async fn user_token(&self, username: &str) -> String {
+        self.create_user(username, "correct horse").await;
+        self.login(username, "correct horse").await
+    }
+
+    /// Create a regular user and issue a token that claims superuser rights
+    ///
+    /// Handlers trust the `is_superuser` claim rather than the database, so the
+    /// stored user is left unflagged.
+    pub async fn superuser_token(&self, username: &str) -> String {
+        let user = self
+            .repository
+            .create_user(username, "unused")
+            .await
+            .expect("user should be created");
+        self.auth_service
+            .generate_token(&user.id.to_string(), username, true)
+            .expect("token should be issued")
+    }
+}
@@ -0,0 +1,618 @@
+//! End-to-end tests against the real router.
+//!
+//! Every test starts its own application on the in-memory backends (see
+//! `common`), so they exercise handlers, middleware and rate limiting without
+//! external services.
+
+mod common;
+
+use axum::http::StatusCode;
+use common::{test_config, TestApp, BROWSER_USER_AGENT};
+use rustlink::config::{ClickCounterMode, RateLimitConfig, ShortCodeStrategy};
+use rustlink::db::{MemoryStorage, ReplicatedStorage, Repository};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+#[tokio::test]
+async fn test_create_resolve_info_delete() {
+    let app = TestApp::new().await;
+    let token = app.user_token("alice").await;
+
+    let created = app
+        .server
+        .post("/")
+        .authorization_bearer(&token)
+        .json(&json!({ "url": "https://example.com/page", "custom_code": "mylink" }))
+        .await;
+    created.assert_status(StatusCode::CREATED);
+    let body: Value = created.json();
+    assert_eq!(body["short_code"], "mylink");
+    assert_eq!(body["short_url"], "http://localhost:3000/mylink");
+
+    let redirect = app
+        .server
+        .get("/mylink")
+        .add_header("user-agent", BROWSER_USER_AGENT)
+        .await;
+    redirect.assert_status(StatusCode::TEMPORARY_REDIRECT);
+    assert_eq!(redirect.header("location"), "https://example.com/page");
+
+    // The click reaches storage through the background worker
+    let mut info = Value::Null;
+    for _ in 0..50 {
+        info = app.server.get("/mylink/info").await.json();
+        if info["click_count"] == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(info["original_url"], "https://example.com/page");
+    assert_eq!(info["click_count"], 1);
+
+    app.server
+        .delete("/mylink")
+        .authorization_bearer(&token)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    app.server
+        .get("/mylink")
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    app.server
+        .get("/mylink/info")
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_repeated_resolves_hit_cache() {
+    let app = TestApp::new().await;
+    app.server
+        .post("/")
+        .json(&json!({ "url": "https://example.com", "custom_code": "hot001" }))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    let resolve = || {
+        app.server
+            .get("/hot001")
+            .add_header("user-agent", BROWSER_USER_AGENT)
+    };
+    for _ in 0..50 {
+        resolve()
+            .await
+            .assert_status(StatusCode::TEMPORARY_REDIRECT);
+    }
+
+    // Flushing the clicks to storage must not evict the cached link
+    let mut info = Value::Null;
+    for _ in 0..50 {
+        info = app.server.get("/hot001/info").await.json();
+        if info["click_count"] == 50 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(info["click_count"], 50);
+    resolve()
+        .await
+        .assert_status(StatusCode::TEMPORARY_REDIRECT);
+
+    let health: Value = app.server.get("/_health").await.json();
+    assert_eq!(health["cache_stats"]["backend"], "memory");
+    assert_eq!(health["cache_stats"]["local"]["hits"], 51);
+    assert_eq!(health["cache_stats"]["local"]["misses"], 0);
+}
+
+#[tokio::test]
+async fn test_duplicate_custom_code_conflicts() {
+    let app = TestApp::new().await;
+    let request = json!({ "url": "https://example.com", "custom_code": "taken1" });
+
+    app.server
+        .post("/")
+        .json(&request)
+        .await
+        .assert_status(StatusCode::CREATED);
+    let conflict = app.server.post("/").json(&request).await;
+    conflict.assert_status(StatusCode::CONFLICT);
+    assert_eq!(conflict.json::<Value>()["error"], "CODE_EXISTS");
+}
+
+#[tokio::test]
+async fn test_update_url() {
+    let app = TestApp::new().await;
+    let alice = app.user_token("alice").await;
+    let bob = app.user_token("bob").await;
+
+    app.server
+        .post("/")
+        .authorization_bearer(&alice)
+        .json(&json!({ "url": "https://example.com/old", "custom_code": "edit01" }))
+        .await
+        .assert_status(StatusCode::CREATED);
+    // Resolve once so the old destination is cached
+    app.server
+        .get("/edit01")
+        .await
+        .assert_status(StatusCode::TEMPORARY_REDIRECT);
+
+    // Only the owner may edit
+    app.server
+        .patch("/edit01")
+        .authorization_bearer(&bob)
+        .json(&json!({ "url": "https://example.com/bob" }))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    let conflicting = app
+        .server
+        .patch("/edit01")
+        .authorization_bearer(&alice)
+        .json(&json!({ "expiry_hours": 1, "clear_expiry": true }))
+        .await;
+    conflicting.assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(conflicting.json::<Value>()["error"], "BAD_REQUEST");
+
+    let updated = app
+        .server
+        .patch("/edit01")
+        .authorization_bearer(&alice)
+        .json(&json!({ "url": "https://example.com/new", "clear_expiry": true }))
+        .await;
+    updated.assert_status(StatusCode::OK);
+    let body: Value = updated.json();
+    assert_eq!(body["original_url"], "https://example.com/new");
+    assert!(body["expires_at"].is_null());
+
+    let redirect = app.server.get("/edit01").await;
+    redirect.assert_status(StatusCode::TEMPORARY_REDIRECT);
+    assert_eq!(redirect.header("location"), "https://example.com/new");
+}
+
+#[tokio::test]
+async fn test_redis_counter_mode_still_records_click_events() {
+    let mut config = test_config();
+    config.url.click_counter_mode = ClickCounterMode::Redis;
+    let app = TestApp::with_config(config).await;
+    let token = app.user_token("alice").await;
+    app.server
+        .post("/")
+        .authorization_bearer(&token)
+        .json(&json!({ "url": "https://example.com", "custom_code": "redis1" }))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    app.server
+        .get("/redis1")
+        .add_header("user-agent", BROWSER_USER_AGENT)
+        .add_header("referer", "https://news.example.org/")
+        .await
+        .assert_status(StatusCode::TEMPORARY_REDIRECT);
+
+    // Counts go to Redis in this mode, but events are still batched by the worker
+    let mut stats = Value::Null;
+    for _ in 0..50 {
+        stats = app
+            .server
+            .get("/redis1/stats")
+            .authorization_bearer(&token)
+            .await
+            .json();
+        if stats["total_clicks"] == 1 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(stats["total_clicks"], 1);
+    assert_eq!(stats["top_referrers"][0]["value"], "https://news.example.org/");
+}
+
+#[tokio::test]
+async fn test_url_stats_rejects_invalid_range() {
+    let app = TestApp::new().await;
+    let token = app.user_token("alice").await;
+    app.server
+        .post("/")
+        .authorization_bearer(&token)
+        .json(&json!({ "url": "https://example.com", "custom_code": "range1" }))
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    let response = app
+        .server
+        .get("/range1/stats")
+        .authorization_bearer(&token)
+        .add_query_param("from", "2026-02-01T00:00:00Z")
+        .add_query_param("to", "2026-01-01T00:00:00Z")
+        .await;
+    response.assert_status(StatusCode::BAD_REQUEST);
+    let body: Value = response.json();
+    assert_eq!(body["error"], "BAD_REQUEST");
+    assert_eq!(
+        body["message"],
+        "Bad request: 'from' must be earlier than 'to'"
+    );
+}
+
+#[tokio::test]
+async fn test_edit_seen_by_other_instance_despite_replica_lag() {
+    // Two instances share a primary and a replica that never catches up
+    let primary: Repository = Arc::new(MemoryStorage::default());
+    let replica: Repository = Arc::new(MemoryStorage::default());
+    for db in [&primary, &replica] {
+        db.create_url("shared1", "https://example.com/old", None, None)
+            .await
+            .unwrap();
+    }
+    let instance = || async {
+        let storage = ReplicatedStorage::new(
+            primary.clone(),
+            vec![replica.clone()],
+            Duration::from_secs(5),
+        );
+        TestApp::with_repository(test_config(), Arc::new(storage)).await
+    };
+    let a = instance().await;
+    let b = instance().await;
+
+    let redirect = b.server.get("/shared1").await;
+    assert_eq!(redirect.header("location"), "https://example.com/old");
+
+    let root = a.superuser_token("root").await;
+    a.server
+        .patch("/shared1")
+        .authorization_bearer(&root)
+        .json(&json!({ "url": "https://example.com/new" }))
+        .await
+        .assert_status(StatusCode::OK);
+
+    // What the pub/sub invalidation does on the other instance
+    b.cache.delete_url("shared1").await.unwrap();
+
+    let redirect = b.server.get("/shared1").await;
+    assert_eq!(redirect.header("location"), "https://example.com/new");
+}
+
+#[tokio::test]
+async fn test_auth_failures() {
+    let app = TestApp::new().await;
+    app.create_user("alice", "correct horse").await;
+
+    // Wrong password and unknown user
+    app.server
+        .post("/login")
+        .json(&json!({ "username": "alice", "password": "battery staple" }))
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    app.server
+        .post("/login")
+        .json(&json!({ "username": "mallory", "password": "correct horse" }))
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+
+    app.server
+        .post("/")
+        .json(&json!({ "url": "https://example.com", "custom_code": "owned1" }))
+        .authorization_bearer(app.login("alice", "correct horse").await)
+        .await
+        .assert_status(StatusCode::CREATED);
+
+    // Missing and malformed tokens
+    app.server
+        .delete("/owned1")
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    app.server
+        .delete("/owned1")
+        .authorization_bearer("not-a-jwt")
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+    app.server
+        .get("/_stats")
+        .await
+        .assert_status(StatusCode::UNAUTHORIZED);
+
+    // Another user cannot delete alice's URL
+    let bob = app.user_token("bob").await;
+    app.server
+        .delete("/owned1")
+        .authorization_bearer(&bob)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    app.server
+        .get("/owned1/info")
+        .await
+        .assert_status(StatusCode::OK);
+}
+
+/// Short codes of every link the token's user can list
+async fn listed_codes(app: &TestApp, token: &str) -> Vec<String> {
+    let page: Value = app
+        .server
+        .get("/_list")
+        .authorization_bearer(token)
+        .add_query_param("sort", "created_at")
+        .add_query_param("order", "asc")
+        .await
+        .json();
+    page["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|u| u["short_code"].as_str().unwrap().to_string())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_owner_scoping() {
+    let app = TestApp::new().await;
+    let alice = app.user_token("alice").await;
+    let bob = app.user_token("bob").await;
+    let root = app.superuser_token("root").await;
+    for (token, code) in [(&alice, "alice1"), (&alice, "alice2"), (&bob, "bob1")] {
+        app.server
+            .post("/")
+            .authorization_bearer(token)
+            .json(&json!({ "url": "https://example.com", "custom_code": code }))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    // Each user only sees their own links
+    assert_eq!(listed_codes(&app, &alice).await, ["alice1", "alice2"]);
+    assert_eq!(listed_codes(&app, &bob).await, ["bob1"]);
+    let stats: Value = app
+        .server
+        .get("/_stats")
+        .authorization_bearer(&bob)
+        .await
+        .json();
+    assert_eq!(stats["total_urls"], 1);
+
+    // ...and can only delete their own
+    app.server
+        .delete("/alice1")
+        .authorization_bearer(&bob)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    app.server
+        .delete("/bob1")
+        .authorization_bearer(&alice)
+        .await
+        .assert_status(StatusCode::NOT_FOUND);
+    app.server
+        .delete("/alice2")
+        .authorization_bearer(&alice)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+
+    // Superusers see and manage everything
+    assert_eq!(listed_codes(&app, &root).await, ["alice1", "bob1"]);
+    let stats: Value = app
+        .server
+        .get("/_stats")
+        .authorization_bearer(&root)
+        .await
+        .json();
+    assert_eq!(stats["total_urls"], 2);
+    app.server
+        .delete("/bob1")
+        .authorization_bearer(&root)
+        .await
+        .assert_status(StatusCode::NO_CONTENT);
+    assert!(listed_codes(&app, &bob).await.is_empty());
+}
+
+#[tokio::test]
+async fn test_rate_limiting() {
+    let mut config = test_config();
+    config.rate_limit = RateLimitConfig {
+        requests_per_minute: 1,
+        burst_size: 2,
+    };
+    let app = TestApp::with_config(config).await;
+
+    let create = |ip: &'static str| {
+        app.server
+            .post("/")
+            .add_header("x-forwarded-for", ip)
+            .json(&json!({ "url": "https://example.com" }))
+    };
+
+    create("203.0.113.1")
+        .await
+        .assert_status(StatusCode::CREATED);
+    create("203.0.113.1")
+        .await
+        .assert_status(StatusCode::CREATED);
+    create("203.0.113.1")
+        .await
+        .assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+    // Other clients have their own budget
+    create("203.0.113.2")
+        .await
+        .assert_status(StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_list_cursor_pagination_and_filters() {
+    let app = TestApp::new().await;
+    let token = app.user_token("alice").await;
+    for (code, url) in [
+        ("shop1", "https://shop.example.com/a"),
+        ("shop2", "https://example.com/b"),
+        ("blog1", "https://blog.example.org/c"),
+    ] {
+        app.server
+            .post("/")
+            .authorization_bearer(&token)
+            .json(&json!({ "url": url, "custom_code": code }))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    // Walk all links two at a time, newest first
+    let mut codes = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let mut request = app
+            .server
+            .get("/_list")
+            .authorization_bearer(&token)
+            .add_query_param("limit", 2);
+        if let Some(cursor) = &cursor {
+            request = request.add_query_param("cursor", cursor);
+        }
+        let page: Value = request.await.json();
+        assert_eq!(page["pagination"]["total"], 3);
+        codes.extend(
+            page["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|u| u["short_code"].as_str().unwrap().to_string()),
+        );
+        match page["pagination"]["next_cursor"].as_str() {
+            Some(next) => cursor = Some(next.to_string()),
+            None => break,
+        }
+    }
+    assert_eq!(codes, ["blog1", "shop2", "shop1"]);
+
+    let filtered: Value = app
+        .server
+        .get("/_list")
+        .authorization_bearer(&token)
+        .add_query_param("domain", "example.com")
+        .add_query_param("sort", "created_at")
+        .add_query_param("order", "asc")
+        .await
+        .json();
+    assert_eq!(filtered["data"][0]["short_code"], "shop1");
+    assert_eq!(filtered["pagination"]["total"], 2);
+    assert!(filtered["pagination"]["next_cursor"].is_null());
+
+    // A cursor only continues the listing it was issued for
+    let mismatched = app
+        .server
+        .get("/_list")
+        .authorization_bearer(&token)
+        .add_query_param("cursor", cursor.unwrap())
+        .add_query_param("sort", "click_count")
+        .await;
+    mismatched.assert_status(StatusCode::BAD_REQUEST);
+    assert_eq!(mismatched.json::<Value>()["error"], "BAD_REQUEST");
+}
+
+#[tokio::test]
+async fn test_pool_strategy_claims_codes_and_reports_size() {
+    let mut config = test_config();
+    config.url.short_code_strategy = ShortCodeStrategy::Pool;
+    let app = TestApp::with_config(config).await;
+    app.repository
+        .add_pooled_codes(&["pool0001".to_string(), "pool0002".to_string()])
+        .await
+        .unwrap();
+
+    let created: Value = app
+        .server
+        .post("/")
+        .json(&json!({ "url": "https://example.com" }))
+        .await
+        .json();
+    assert!(created["short_code"]
+        .as_str()
+        .unwrap()
+        .starts_with("pool000"));
+
+    let health: Value = app.server.get("/_health").await.json();
+    assert_eq!(health["code_pool_size"], 1);
+}
+
+#[tokio::test]
+async fn test_reserved_codes_are_rejected() {
+    let mut config = test_config();
+    config.url.reserved_codes = vec!["promo".to_string()];
+    let app = TestApp::with_config(config).await;
+    app.repository.reserve_code("launch").await.unwrap();
+
+    // Route names, configured codes and CLI reservations, in any case
+    for code in ["_stats", "_list", "login", "_Health", "PROMO", "launch"] {
+        let response = app
+            .server
+            .post("/")
+            .json(&json!({ "url": "https://example.com", "custom_code": code }))
+            .await;
+        response.assert_status(StatusCode::CONFLICT);
+        assert_eq!(response.json::<Value>()["error"], "CODE_RESERVED");
+    }
+
+    app.server
+        .post("/")
+        .json(&json!({ "url": "https://example.com", "custom_code": "stats_" }))
+        .await
+        .assert_status(StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn test_code_availability_and_suggestions() {
+    let app = TestApp::new().await;
+    for code in ["launch", "launch2"] {
+        app.server
+            .post("/")
+            .json(&json!({ "url": "https://example.com", "custom_code": code }))
+            .await
+            .assert_status(StatusCode::CREATED);
+    }
+
+    let free: Value = app
+        .server
+        .get("/_codes/available")
+        .add_query_param("code", "spring")
+        .await
+        .json();
+    assert_eq!(free["available"], true);
+    assert_eq!(free["suggestions"], json!([]));
+
+    let taken: Value = app
+        .server
+        .get("/_codes/available")
+        .add_query_param("code", "launch")
+        .await
+        .json();
+    assert_eq!(taken["available"], false);
+    assert_eq!(taken["reason"], "taken");
+    let suggestions = taken["suggestions"].as_array().unwrap();
+    assert_eq!(suggestions[0], "launch-2");
+    assert!(!suggestions.contains(&json!("launch2")));
+
+    // The conflict on create carries the same suggestions
+    let conflict = app
+        .server
+        .post("/")
+        .json(&json!({ "url": "https://example.com", "custom_code": "launch" }))
+        .await;
+    conflict.assert_status(StatusCode::CONFLICT);
+    assert_eq!(
+        conflict.json::<Value>()["suggestions"],
+        taken["suggestions"]
+    );
+
+    // The endpoint's own name is reserved
+    let reserved: Value = app
+        .server
+        .get("/_codes/available")
+        .add_query_param("code", "_codes")
+        .await
+        .json();
+    assert_eq!(reserved["reason"], "reserved");
+
+    app.server
+        .get("/_codes/available")
+        .add_query_param("code", "a/b")
+        .await
+        .assert_status(StatusCode::BAD_REQUEST);
+}